@@ -2,6 +2,7 @@ use anyhow::*;
 use fs_extra::copy_items;
 use fs_extra::dir::CopyOptions;
 use std::env;
+use std::path::Path;
 
 fn main() -> Result<()> {
     println!("cargo:rerun-if-changed=resources/");
@@ -17,5 +18,23 @@ fn main() -> Result<()> {
     let out_dir = "./web";
     copy_items(&paths_to_copy, out_dir, &copy_options)?;
 
+    // tutorial shaders, so they can be loaded (and reloaded) at runtime as
+    // `shaders/<chapter>/<file>`, e.g. `shaders/_1_getting_started/3.3.shader.vs`
+    for shader_dir in glob::glob("src/*/shaders")? {
+        let shader_dir = shader_dir?;
+        println!("cargo:rerun-if-changed={}", shader_dir.display());
+        let chapter = shader_dir.parent().unwrap().file_name().unwrap();
+        let shaders = glob::glob(&format!("{}/*", shader_dir.display()))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for out_dir in [env::var("OUT_DIR")?.as_str(), "./web"] {
+            let target = Path::new(out_dir)
+                .join("resources")
+                .join("shaders")
+                .join(chapter);
+            std::fs::create_dir_all(&target)?;
+            copy_items(&shaders, &target, &copy_options)?;
+        }
+    }
+
     Ok(())
 }
@@ -3,6 +3,13 @@ const HEIGHT: u32 = 600;
 
 // window functions will move to `window.rs`
 pub async unsafe fn main_1_1_1() {
+    // this tutorial builds its window by hand instead of going through `window::run`,
+    // and draws nothing, so there is nothing to do without a window
+    if let Some(crate::window::Backend::Headless { .. }) = crate::window::backend_override() {
+        log::info!("Hello window has nothing to render headless");
+        return;
+    }
+
     // Create a context from a WebGL2 context on wasm32 targets
     #[cfg(target_arch = "wasm32")]
     let (_gl, _shader_version, _window, _event_loop) = {
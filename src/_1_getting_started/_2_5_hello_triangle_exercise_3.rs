@@ -1,3 +1,4 @@
+use crate::gl_object::{OwnedBuffer, OwnedProgram, OwnedVertexArray, SharedGl};
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
 use std::mem::size_of;
@@ -23,24 +24,22 @@ const VERTICES: [f32; 18] = [
 ];
 
 struct App {
-    vao: VertexArray,
-    vbo: Buffer,
-    program_0: Program,
-    program_1: Program,
+    vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
+    program_0: OwnedProgram,
+    program_1: OwnedProgram,
 }
 
 impl Application for App {
     async unsafe fn new(ctx: &AppContext) -> Self {
         let gl = ctx.gl();
         let shader_version = ctx.suggested_shader_version();
-        let vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "vao");
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
 
-        gl.bind_vertex_array(Some(vao));
+        gl.bind_vertex_array(Some(vao.raw()));
 
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 3 * size_of::<f32>() as i32, 0);
@@ -92,7 +91,7 @@ impl Application for App {
 
         Self {
             vao,
-            vbo,
+            _vbo: vbo,
             program_0,
             program_1,
         }
@@ -104,40 +103,28 @@ impl Application for App {
         gl.clear(COLOR_BUFFER_BIT);
         // seeing as we only have a single VAO there's no need to bind it every time,
         // but we'll do so to keep things a bit more organized
-        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_vertex_array(Some(self.vao.raw()));
 
-        gl.use_program(Some(self.program_0));
+        gl.use_program(Some(self.program_0.raw()));
         gl.draw_arrays(TRIANGLES, 0, 3);
 
-        gl.use_program(Some(self.program_1));
+        gl.use_program(Some(self.program_1.raw()));
         gl.draw_arrays(TRIANGLES, 3, 3);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        gl.delete_program(self.program_0);
-
-        gl.delete_program(self.program_1);
-
-        gl.delete_vertex_array(self.vao);
-
-        gl.delete_buffer(self.vbo);
-    }
 }
 
 fn create_program(
-    gl: &Context,
+    gl: &SharedGl,
     vertex_shader: &str,
     fragment_shader: &str,
     shader_version: &str,
-) -> Result<Program, String> {
+) -> Result<OwnedProgram, String> {
     let (vertex_shader, fragment_shader) = (
         format!("{}\n{}", shader_version, vertex_shader),
         format!("{}\n{}", shader_version, fragment_shader),
     );
 
-    let program = unsafe { gl.create_program().expect("Cannot create program") };
+    let program = OwnedProgram::create_program(gl, "program");
 
     let (vertex, fragment) = (
         compile_shader(gl, VERTEX_SHADER, &vertex_shader)?,
@@ -145,18 +132,18 @@ fn create_program(
     );
 
     unsafe {
-        gl.attach_shader(program, vertex);
-        gl.attach_shader(program, fragment);
-        gl.link_program(program);
+        gl.attach_shader(program.raw(), vertex);
+        gl.attach_shader(program.raw(), fragment);
+        gl.link_program(program.raw());
     }
 
-    if !unsafe { gl.get_program_link_status(program) } {
-        return Err(unsafe { gl.get_program_info_log(program) });
+    if !unsafe { gl.get_program_link_status(program.raw()) } {
+        return Err(unsafe { gl.get_program_info_log(program.raw()) });
     }
 
     unsafe {
-        gl.detach_shader(program, vertex);
-        gl.detach_shader(program, fragment);
+        gl.detach_shader(program.raw(), vertex);
+        gl.detach_shader(program.raw(), fragment);
         gl.delete_shader(vertex);
         gl.delete_shader(fragment);
     }
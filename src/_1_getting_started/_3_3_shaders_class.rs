@@ -1,4 +1,5 @@
-use crate::shader::MyShader;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::hot_reload::ReloadableShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
 use std::mem::size_of;
@@ -20,31 +21,30 @@ const VERTICES: [f32; 18] = [
 ];
 
 struct App {
-    vao: VertexArray,
-    vbo: Buffer,
-    shader: MyShader,
+    vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
+    shader: ReloadableShader,
 }
 
 impl Application for App {
     async unsafe fn new(ctx: &AppContext) -> Self {
         let gl = ctx.gl();
-        let shader = MyShader::new_from_source(
+        // loaded from files and recompiled whenever they are saved, try editing the color
+        let shader = ReloadableShader::new(
             gl,
-            // embedded shader
-            include_str!("./shaders/3.3.shader.vs"),
-            include_str!("./shaders/3.3.shader.fs"),
+            "shaders/_1_getting_started/3.3.shader.vs",
+            "shaders/_1_getting_started/3.3.shader.fs",
             Some(ctx.suggested_shader_version()),
         )
+        .await
         .expect("Failed to create program");
 
-        let vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "vao");
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
 
-        gl.bind_vertex_array(Some(vao));
+        gl.bind_vertex_array(Some(vao.raw()));
 
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 6 * size_of::<f32>() as i32, 0);
@@ -67,7 +67,11 @@ impl Application for App {
         // VAOs requires a call to glBindVertexArray anyway, so we generally don't unbind VAOs (nor VBOs) when it's not directly necessary.
         gl.bind_vertex_array(None);
 
-        Self { shader, vao, vbo }
+        Self {
+            shader,
+            vao,
+            _vbo: vbo,
+        }
     }
 
     unsafe fn render(&mut self, ctx: &AppContext) {
@@ -75,21 +79,12 @@ impl Application for App {
         gl.clear_color(0.2, 0.3, 0.3, 1.0);
         gl.clear(COLOR_BUFFER_BIT);
 
+        self.shader.reload_if_changed(gl);
         self.shader.use_shader(gl);
 
         // seeing as we only have a single VAO there's no need to bind it every time,
         // but we'll do so to keep things a bit more organized
-        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_vertex_array(Some(self.vao.raw()));
         gl.draw_arrays(TRIANGLES, 0, 3);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.vao);
-
-        gl.delete_buffer(self.vbo);
-    }
 }
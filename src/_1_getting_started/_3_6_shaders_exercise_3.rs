@@ -1,3 +1,4 @@
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
@@ -20,8 +21,8 @@ const VERTICES: [f32; 18] = [
 ];
 
 struct App {
-    vao: VertexArray,
-    vbo: Buffer,
+    vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
     shader: MyShader,
 }
 
@@ -37,14 +38,12 @@ impl Application for App {
         )
         .expect("Failed to create program");
 
-        let vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "vao");
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
 
-        gl.bind_vertex_array(Some(vao));
+        gl.bind_vertex_array(Some(vao.raw()));
 
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 6 * size_of::<f32>() as i32, 0);
@@ -67,7 +66,11 @@ impl Application for App {
         // VAOs requires a call to glBindVertexArray anyway, so we generally don't unbind VAOs (nor VBOs) when it's not directly necessary.
         gl.bind_vertex_array(None);
 
-        Self { vao, vbo, shader }
+        Self {
+            vao,
+            _vbo: vbo,
+            shader,
+        }
     }
 
     unsafe fn render(&mut self, ctx: &AppContext) {
@@ -79,17 +82,7 @@ impl Application for App {
 
         // seeing as we only have a single VAO there's no need to bind it every time,
         // but we'll do so to keep things a bit more organized
-        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_vertex_array(Some(self.vao.raw()));
         gl.draw_arrays(TRIANGLES, 0, 3);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.vao);
-
-        gl.delete_buffer(self.vbo);
-    }
 }
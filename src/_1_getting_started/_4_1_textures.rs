@@ -1,3 +1,4 @@
+use crate::gl_object::{OwnedBuffer, OwnedTexture, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
@@ -25,9 +26,9 @@ const VERTICES: [f32; 32] = [
 const INDICES: [u32; 6] = [0, 1, 3, 1, 2, 3];
 
 struct App {
-    vao: VertexArray,
-    vbo: Buffer,
-    texture: Texture,
+    vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
+    texture: OwnedTexture,
     shader: MyShader,
 }
 
@@ -43,15 +44,13 @@ impl Application for App {
         )
         .expect("Failed to create program");
 
-        let vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "vao");
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
         let ebo = gl.create_buffer().expect("Cannot create ebo buffer");
 
-        gl.bind_vertex_array(Some(vao));
+        gl.bind_vertex_array(Some(vao.raw()));
 
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
         gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(ebo));
         gl.buffer_data_u8_slice(
@@ -84,8 +83,8 @@ impl Application for App {
         gl.enable_vertex_attrib_array(2);
 
         // load texture
-        let texture = gl.create_texture().expect("Cannot create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture));
+        let texture = OwnedTexture::create_texture(gl, "texture");
+        gl.bind_texture(TEXTURE_2D, Some(texture.raw()));
         // set the texture wrapping parameters
         // set texture wrapping to GL_REPEAT (default wrapping method)
         gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, REPEAT as i32);
@@ -122,7 +121,7 @@ impl Application for App {
         Self {
             shader,
             vao,
-            vbo,
+            _vbo: vbo,
             texture,
         }
     }
@@ -132,25 +131,13 @@ impl Application for App {
         gl.clear_color(0.2, 0.3, 0.3, 1.0);
         gl.clear(COLOR_BUFFER_BIT);
 
-        gl.bind_texture(TEXTURE_2D, Some(self.texture));
+        gl.bind_texture(TEXTURE_2D, Some(self.texture.raw()));
 
         self.shader.use_shader(gl);
 
         // seeing as we only have a single VAO there's no need to bind it every time,
         // but we'll do so to keep things a bit more organized
-        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_vertex_array(Some(self.vao.raw()));
         gl.draw_elements(TRIANGLES, 6, UNSIGNED_INT, 0);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.vao);
-
-        gl.delete_buffer(self.vbo);
-
-        gl.delete_texture(self.texture);
-    }
 }
@@ -1,3 +1,4 @@
+use crate::gl_object::{OwnedBuffer, OwnedTexture, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
@@ -28,10 +29,10 @@ const VERTICES: [f32; 32] = [
 const INDICES: [u32; 6] = [0, 1, 3, 1, 2, 3];
 
 struct App {
-    vao: VertexArray,
-    vbo: Buffer,
-    texture_1: Texture,
-    texture_2: Texture,
+    vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
+    texture_1: OwnedTexture,
+    texture_2: OwnedTexture,
     shader: MyShader,
     mix_value: f32,
 }
@@ -48,15 +49,13 @@ impl Application for App {
         )
         .expect("Failed to create program");
 
-        let vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "vao");
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
         let ebo = gl.create_buffer().expect("Cannot create ebo buffer");
 
-        gl.bind_vertex_array(Some(vao));
+        gl.bind_vertex_array(Some(vao.raw()));
 
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
         gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(ebo));
         gl.buffer_data_u8_slice(
@@ -90,8 +89,8 @@ impl Application for App {
 
         // texture 1
         // ---------
-        let texture_1 = gl.create_texture().expect("Cannot create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture_1));
+        let texture_1 = OwnedTexture::create_texture(gl, "texture 1");
+        gl.bind_texture(TEXTURE_2D, Some(texture_1.raw()));
         // set the texture wrapping parameters
         // set texture wrapping to GL_REPEAT (default wrapping method)
         gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, REPEAT as i32);
@@ -124,8 +123,8 @@ impl Application for App {
 
         // texture 2
         // ---------
-        let texture_2 = gl.create_texture().expect("Cannot create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture_2));
+        let texture_2 = OwnedTexture::create_texture(gl, "texture 2");
+        gl.bind_texture(TEXTURE_2D, Some(texture_2.raw()));
         gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, REPEAT as i32);
         gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, REPEAT as i32);
         gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR_MIPMAP_LINEAR as i32);
@@ -169,7 +168,7 @@ impl Application for App {
         Self {
             shader,
             vao,
-            vbo,
+            _vbo: vbo,
             texture_1,
             texture_2,
             mix_value: 0.2,
@@ -182,17 +181,17 @@ impl Application for App {
         gl.clear(COLOR_BUFFER_BIT);
 
         gl.active_texture(TEXTURE0);
-        gl.bind_texture(TEXTURE_2D, Some(self.texture_1));
+        gl.bind_texture(TEXTURE_2D, Some(self.texture_1.raw()));
 
         gl.active_texture(TEXTURE1);
-        gl.bind_texture(TEXTURE_2D, Some(self.texture_2));
+        gl.bind_texture(TEXTURE_2D, Some(self.texture_2.raw()));
 
         self.shader.use_shader(gl);
         self.shader.set_float(gl, "mixValue", self.mix_value);
 
         // seeing as we only have a single VAO there's no need to bind it every time,
         // but we'll do so to keep things a bit more organized
-        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_vertex_array(Some(self.vao.raw()));
         gl.draw_elements(TRIANGLES, 6, UNSIGNED_INT, 0);
     }
 
@@ -211,18 +210,4 @@ impl Application for App {
             }
         }
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.vao);
-
-        gl.delete_buffer(self.vbo);
-
-        gl.delete_texture(self.texture_1);
-
-        gl.delete_texture(self.texture_2);
-    }
 }
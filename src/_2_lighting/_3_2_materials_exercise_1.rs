@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
@@ -64,9 +65,9 @@ const VERTICES: [f32; 216] = [
 const LIGHT_POS: glm::Vec3 = glm::Vec3::new(1.2, 1.0, 2.0);
 
 struct App {
-    cube_vao: VertexArray,
-    light_vao: VertexArray,
-    vbo: Buffer,
+    cube_vao: OwnedVertexArray,
+    light_vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
     lighting_shader: MyShader,
     lighting_cube_shader: MyShader,
     camera: Camera,
@@ -97,14 +98,12 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         // first, configure the cube's VAO (and VBO)
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         // position attribute
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 6 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
@@ -120,11 +119,9 @@ impl Application for App {
         gl.enable_vertex_attrib_array(1);
 
         // second, configure the light's VAO (VBO stays the same; the vertices are the same for the light object which is also a 3D cube)
-        let light_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(light_vao));
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        let light_vao = OwnedVertexArray::create_vertex_array(gl, "light vao");
+        gl.bind_vertex_array(Some(light_vao.raw()));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         // note that we update the lamp's position attribute's stride to reflect the updated buffer data
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 6 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
@@ -132,7 +129,7 @@ impl Application for App {
         Self {
             cube_vao,
             light_vao,
-            vbo,
+            _vbo: vbo,
             lighting_shader,
             lighting_cube_shader,
             camera,
@@ -192,7 +189,7 @@ impl Application for App {
         let model = glm::Mat4::identity();
         self.lighting_shader.set_mat4(gl, "model", &model);
 
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         // draw the lamp object
@@ -205,7 +202,7 @@ impl Application for App {
         model = glm::scale(&model, &glm::vec3(0.2, 0.2, 0.2)); // a smaller cube
         self.lighting_cube_shader.set_mat4(gl, "model", &model);
 
-        gl.bind_vertex_array(Some(self.light_vao));
+        gl.bind_vertex_array(Some(self.light_vao.raw()));
         gl.draw_arrays(TRIANGLES, 0, 36);
     }
 
@@ -213,17 +210,4 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.lighting_shader.delete(gl);
-        self.lighting_cube_shader.delete(gl);
-
-        gl.delete_vertex_array(self.cube_vao);
-
-        gl.delete_vertex_array(self.light_vao);
-
-        gl.delete_buffer(self.vbo);
-    }
 }
@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedTexture, OwnedVertexArray, SharedGl};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use anyhow::Result;
@@ -78,13 +79,14 @@ const CUBE_POSITIONS: [glm::Vec3; 10] = [
 ];
 
 struct App {
-    cube_vao: VertexArray,
-    light_vao: VertexArray,
-    vbo: Buffer,
-    diffuse_map: Texture,
-    specular_map: Texture,
+    cube_vao: OwnedVertexArray,
+    _light_vao: OwnedVertexArray,
+    _vbo: OwnedBuffer,
+    diffuse_map: OwnedTexture,
+    specular_map: OwnedTexture,
     lighting_shader: MyShader,
-    lighting_cube_shader: MyShader,
+    // built like in the original, which does not draw the lamp
+    _lighting_cube_shader: MyShader,
     camera: Camera,
 }
 
@@ -113,14 +115,12 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         // first, configure the cube's VAO (and VBO)
-        let vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        let vbo = OwnedBuffer::create_buffer(gl, "vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         // position attribute
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 8 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
@@ -146,11 +146,9 @@ impl Application for App {
         gl.enable_vertex_attrib_array(2);
 
         // second, configure the light's VAO (VBO stays the same; the vertices are the same for the light object which is also a 3D cube)
-        let light_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(light_vao));
-        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        let light_vao = OwnedVertexArray::create_vertex_array(gl, "light vao");
+        gl.bind_vertex_array(Some(light_vao.raw()));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo.raw()));
         // note that we update the lamp's position attribute's stride to reflect the updated buffer data
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 8 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
@@ -174,10 +172,10 @@ impl Application for App {
 
         Self {
             cube_vao,
-            light_vao,
-            vbo,
+            _light_vao: light_vao,
+            _vbo: vbo,
             lighting_shader,
-            lighting_cube_shader,
+            _lighting_cube_shader: lighting_cube_shader,
             camera,
             diffuse_map,
             specular_map,
@@ -221,12 +219,12 @@ impl Application for App {
 
         // bind diffuse map
         gl.active_texture(TEXTURE0);
-        gl.bind_texture(TEXTURE_2D, Some(self.diffuse_map));
+        gl.bind_texture(TEXTURE_2D, Some(self.diffuse_map.raw()));
         // bind specular map
         gl.active_texture(TEXTURE1);
-        gl.bind_texture(TEXTURE_2D, Some(self.specular_map));
+        gl.bind_texture(TEXTURE_2D, Some(self.specular_map.raw()));
 
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
 
         for (i, pos) in CUBE_POSITIONS.iter().enumerate() {
             let mut model = glm::Mat4::identity();
@@ -243,32 +241,15 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.lighting_shader.delete(gl);
-        self.lighting_cube_shader.delete(gl);
-
-        gl.delete_vertex_array(self.cube_vao);
-
-        gl.delete_vertex_array(self.light_vao);
-
-        gl.delete_buffer(self.vbo);
-
-        gl.delete_texture(self.diffuse_map);
-
-        gl.delete_texture(self.specular_map);
-    }
 }
 
-fn load_texture_from_bytes(gl: &Context, bytes: &[u8]) -> Result<Texture> {
+fn load_texture_from_bytes(gl: &SharedGl, bytes: &[u8]) -> Result<OwnedTexture> {
     let img = image::load_from_memory(bytes)?.flipv().to_rgba8();
     let (width, height) = img.dimensions();
     let data = img.into_raw();
     let texture = unsafe {
-        let texture = gl.create_texture().expect("Create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture));
+        let texture = OwnedTexture::create_texture(gl, "texture");
+        gl.bind_texture(TEXTURE_2D, Some(texture.raw()));
         gl.tex_image_2d(
             TEXTURE_2D,
             0,
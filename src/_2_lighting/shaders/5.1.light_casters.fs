@@ -5,23 +5,11 @@ in vec3 FragPos;
 in vec3 Normal;
 in vec2 TexCoords;
 
-struct Material {
-    sampler2D diffuse;
-    sampler2D specular;
-    float shininess;
-};
-
-struct Light {
-    vec3 direction;
-
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-};
+#include "common/lights.glsl"
 
 uniform vec3 viewPos;
 uniform Material material;
-uniform Light light;
+uniform DirLight light;
 
 void main()
 {
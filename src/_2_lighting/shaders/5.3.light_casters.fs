@@ -5,29 +5,11 @@ in vec3 FragPos;
 in vec3 Normal;
 in vec2 TexCoords;
 
-struct Material {
-    sampler2D diffuse;
-    sampler2D specular;
-    float shininess;
-};
-
-struct Light {
-    vec3 position;
-    vec3 direction;
-    float cutOff;
-
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-
-    float constant;
-    float linear;
-    float quadratic;
-};
+#include "common/lights.glsl"
 
 uniform vec3 viewPos;
 uniform Material material;
-uniform Light light;
+uniform SpotLight light;
 
 void main()
 {
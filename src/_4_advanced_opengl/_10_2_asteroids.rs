@@ -5,7 +5,7 @@ use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
 use nalgebra_glm as glm;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use winit_input_helper::WinitInputHelper;
 
 pub async unsafe fn main_4_10_2() {
@@ -124,23 +124,14 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        self.rock.delete(gl);
-        self.planet.delete(gl);
-    }
 }
 
 pub fn generate_matrices(amount: usize) -> Vec<glm::Mat4> {
     let mut model_matrices = Vec::with_capacity(amount);
     let radius = 50.0;
     let offset = 2.5;
-    // initialize random seed
-    let mut rng = rand::thread_rng();
+    // initialize random seed, fixed so the field looks the same every run (and in golden images)
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
 
     for i in 0..amount {
         let mut model = glm::Mat4::identity();
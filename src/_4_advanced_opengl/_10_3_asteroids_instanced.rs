@@ -1,11 +1,12 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, SharedGl};
 use crate::model::Model;
 use crate::resources;
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
 use nalgebra_glm as glm;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use winit_input_helper::WinitInputHelper;
 
 pub async unsafe fn main_4_10_3() {
@@ -21,7 +22,7 @@ struct App {
     rock: Model,
     planet: Model,
     model_matrices: Vec<glm::Mat4>,
-    buffer: Buffer,
+    _buffer: OwnedBuffer,
 
     asteroid_shader: MyShader,
     planet_shader: MyShader,
@@ -71,7 +72,7 @@ impl Application for App {
             rock,
             planet,
             model_matrices,
-            buffer,
+            _buffer: buffer,
             asteroid_shader,
             planet_shader,
             camera,
@@ -111,7 +112,7 @@ impl Application for App {
         self.asteroid_shader.set_int(gl, "texture_diffuse1", 0);
         gl.bind_texture(TEXTURE_2D, Some(self.rock.materials[0].textures[0].raw()));
         for mesh in &self.rock.meshes {
-            gl.bind_vertex_array(Some(mesh.vao));
+            gl.bind_vertex_array(Some(mesh.vao.raw()));
             gl.draw_elements_instanced(
                 TRIANGLES,
                 mesh.indices.len() as i32,
@@ -141,11 +142,9 @@ impl Application for App {
             );
             if amount != self.model_matrices.len() as f32 {
                 let gl = &gl_ctx.gl;
-                unsafe {
-                    gl.delete_buffer(self.buffer);
-                }
                 self.model_matrices = generate_matrices(amount as usize);
-                self.buffer = unsafe { create_buffer(gl, &self.rock, &self.model_matrices) };
+                // replacing the buffer drops, and so deletes, the old one
+                self._buffer = unsafe { create_buffer(gl, &self.rock, &self.model_matrices) };
             }
         });
     }
@@ -154,24 +153,13 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.asteroid_shader.delete(gl);
-        self.planet_shader.delete(gl);
-        gl.delete_buffer(self.buffer);
-
-        self.rock.delete(gl);
-        self.planet.delete(gl);
-    }
 }
 
-unsafe fn create_buffer(gl: &Context, rock: &Model, model_matrices: &[glm::Mat4]) -> Buffer {
+unsafe fn create_buffer(gl: &SharedGl, rock: &Model, model_matrices: &[glm::Mat4]) -> OwnedBuffer {
     // configure instanced array
     // -------------------------
-    let buffer = gl.create_buffer().expect("Create buffer failed");
-    gl.bind_buffer(ARRAY_BUFFER, Some(buffer));
+    let buffer = OwnedBuffer::create_buffer(gl, "buffer");
+    gl.bind_buffer(ARRAY_BUFFER, Some(buffer.raw()));
     gl.buffer_data_u8_slice(
         ARRAY_BUFFER,
         bytemuck::cast_slice(model_matrices),
@@ -183,7 +171,7 @@ unsafe fn create_buffer(gl: &Context, rock: &Model, model_matrices: &[glm::Mat4]
     // normally you'd want to do this in a more organized fashion, but for learning purposes this will do.
     // -----------------------------------------------------------------------------------------------------------------------------------
     for mesh in &rock.meshes {
-        let vao = mesh.vao;
+        let vao = mesh.vao.raw();
         gl.bind_vertex_array(Some(vao));
         // 4 times vec4
         let stride = std::mem::size_of::<glm::Mat4>() as i32;
@@ -217,8 +205,8 @@ pub fn generate_matrices(amount: usize) -> Vec<glm::Mat4> {
     let mut model_matrices = Vec::with_capacity(amount);
     let radius = 150.0;
     let offset = 25.0;
-    // initialize random seed
-    let mut rng = rand::thread_rng();
+    // initialize random seed, fixed so the field looks the same every run (and in golden images)
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
 
     for i in 0..amount {
         let mut model = glm::Mat4::identity();
@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
@@ -63,8 +64,8 @@ const VERTICES: [f32; 108] = [
 ];
 
 struct App {
-    cube_vao: VertexArray,
-    cube_vbo: Buffer,
+    cube_vao: OwnedVertexArray,
+    _cube_vbo: OwnedBuffer,
     shader: MyShader,
     camera: Camera,
 }
@@ -90,14 +91,12 @@ impl Application for App {
         // #[cfg(target_arch = "wasm32")]
         log::warn!("I don't know how to enable MSAA on window crate (glutin, webgl2 canvas) yet");
 
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 3 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
 
@@ -105,7 +104,7 @@ impl Application for App {
 
         Self {
             cube_vao,
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             shader,
             camera,
         }
@@ -129,7 +128,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
         self.shader.set_mat4(gl, "model", &glm::Mat4::identity());
 
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         gl.draw_arrays(TRIANGLES, 0, 36);
     }
 
@@ -137,13 +136,4 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.cube_vao);
-        gl.delete_buffer(self.cube_vbo);
-    }
 }
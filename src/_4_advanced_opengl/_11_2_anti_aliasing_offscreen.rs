@@ -0,0 +1,250 @@
+use crate::camera::Camera;
+use crate::framebuffer::{ColorFormat, Depth, Framebuffer, FramebufferDesc};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::shader::MyShader;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_4_11_2() {
+    let init_info = WindowInitInfo::builder()
+        .title("Anti Aliasing Offscreen".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const VERTICES: [f32; 108] = [
+    // pos           
+    -0.5, -0.5, -0.5,
+    0.5, -0.5, -0.5,
+    0.5,  0.5, -0.5,
+    0.5,  0.5, -0.5,
+    -0.5,  0.5, -0.5,
+    -0.5, -0.5, -0.5,
+
+    -0.5, -0.5,  0.5,
+    0.5, -0.5,  0.5,
+    0.5,  0.5,  0.5,
+    0.5,  0.5,  0.5,
+    -0.5,  0.5,  0.5,
+    -0.5, -0.5,  0.5,
+
+    -0.5,  0.5,  0.5,
+    -0.5,  0.5, -0.5,
+    -0.5, -0.5, -0.5,
+    -0.5, -0.5, -0.5,
+    -0.5, -0.5,  0.5,
+    -0.5,  0.5,  0.5,
+
+    0.5,  0.5,  0.5,
+    0.5,  0.5, -0.5,
+    0.5, -0.5, -0.5,
+    0.5, -0.5, -0.5,
+    0.5, -0.5,  0.5,
+    0.5,  0.5,  0.5,
+
+    -0.5, -0.5, -0.5,
+    0.5, -0.5, -0.5,
+    0.5, -0.5,  0.5,
+    0.5, -0.5,  0.5,
+    -0.5, -0.5,  0.5,
+    -0.5, -0.5, -0.5,
+
+    -0.5,  0.5, -0.5,
+    0.5,  0.5, -0.5,
+    0.5,  0.5,  0.5,
+    0.5,  0.5,  0.5,
+    -0.5,  0.5,  0.5,
+    -0.5,  0.5, -0.5,
+];
+
+#[rustfmt::skip]
+const QUAD_VERTICES: [f32; 24] = [
+    // vertex attributes for a quad that fills the entire screen in Normalized Device Coordinates.
+    // positions    texture Coords
+    -1.0,  1.0,  0.0, 1.0,
+    -1.0, -1.0,  0.0, 0.0,
+    1.0, -1.0,  1.0, 0.0,
+
+    -1.0,  1.0,  0.0, 1.0,
+    1.0, -1.0,  1.0, 0.0,
+    1.0,  1.0,  1.0, 1.0
+];
+
+struct App {
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
+    _quad_vbo: OwnedBuffer,
+    quad_vao: OwnedVertexArray,
+    /// the scene is rendered here with 4 samples per pixel
+    multisampled: Framebuffer,
+    /// `multisampled` is resolved into this one, to be sampled by the post-processing shader
+    intermediate: Framebuffer,
+    shader: MyShader,
+    screen_shader: MyShader,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_9_3_default.vs"),
+            include_str!("./shaders/_8_1_green.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+        let screen_shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_5_1_framebuffers_screen.vs"),
+            include_str!("./shaders/_11_2_aa_post.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 3.0));
+
+        gl.enable(DEPTH_TEST);
+
+        // setup cube VAO
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
+        gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
+
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 3 * size_of::<f32>() as i32, 0);
+        gl.enable_vertex_attrib_array(0);
+
+        // setup screen VAO
+        let quad_vbo = OwnedBuffer::create_buffer(gl, "quad vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(quad_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&QUAD_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let quad_vao = OwnedVertexArray::create_vertex_array(gl, "quad vao");
+        gl.bind_vertex_array(Some(quad_vao.raw()));
+        let stride = 4 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 2, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 2, FLOAT, false, stride, 2 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // configure MSAA framebuffer
+        // --------------------------
+        // multisampled color and depth/stencil renderbuffers
+        let multisampled = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(vec![ColorFormat::Rgba8])
+                .depth(Depth::Renderbuffer)
+                .stencil(true)
+                .samples(4)
+                .label("multisampled")
+                .build(),
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create framebuffer");
+        // configure second post-processing framebuffer, only a color texture is needed
+        let intermediate = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(vec![ColorFormat::Rgba8])
+                .label("intermediate")
+                .build(),
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create framebuffer");
+
+        // shader configuration
+        // --------------------
+        screen_shader.use_shader(gl);
+        screen_shader.set_int(gl, "screenTexture", 0);
+
+        Self {
+            _cube_vbo: cube_vbo,
+            cube_vao,
+            _quad_vbo: quad_vbo,
+            quad_vao,
+            multisampled,
+            intermediate,
+            shader,
+            screen_shader,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // 1. draw scene as normal in multisampled buffers
+        self.multisampled.bind(gl);
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        gl.enable(DEPTH_TEST);
+
+        self.shader.use_shader(gl);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            1000.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        self.shader.set_mat4(gl, "model", &glm::Mat4::identity());
+
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
+        gl.draw_arrays(TRIANGLES, 0, 36);
+
+        // 2. now blit multisampled buffer(s) to normal colorbuffer of intermediate FBO. Image is
+        // stored in its color texture
+        self.multisampled
+            .blit_to(gl, Some(&self.intermediate), ctx.width(), ctx.height());
+
+        // 3. now render quad with scene's visuals as its texture image
+        gl.bind_framebuffer(FRAMEBUFFER, None);
+        gl.viewport(0, 0, ctx.width() as i32, ctx.height() as i32);
+        gl.clear_color(1.0, 1.0, 1.0, 1.0);
+        gl.clear(COLOR_BUFFER_BIT);
+        gl.disable(DEPTH_TEST);
+
+        // draw Screen quad
+        self.screen_shader.use_shader(gl);
+        gl.bind_vertex_array(Some(self.quad_vao.raw()));
+        // use the now resolved color attachment as the quad's texture
+        self.intermediate.bind_color(gl, 0, 0);
+        gl.draw_arrays(TRIANGLES, 0, 6);
+        gl.bind_vertex_array(None);
+    }
+
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        for framebuffer in [&mut self.multisampled, &mut self.intermediate] {
+            framebuffer
+                .resize(gl, width, height)
+                .expect("Failed to resize framebuffer");
+        }
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+    }
+}
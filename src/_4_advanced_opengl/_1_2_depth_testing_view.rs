@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use crate::{resources, texture};
@@ -76,12 +77,12 @@ const PLANE_VERTICES: [f32; 30] = [
 ];
 
 struct App {
-    cube_vbo: Buffer,
-    cube_vao: VertexArray,
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
     cube_texture: texture::Texture,
 
-    plane_vbo: Buffer,
-    plane_vao: VertexArray,
+    _plane_vbo: OwnedBuffer,
+    plane_vao: OwnedVertexArray,
     plane_texture: texture::Texture,
 
     shader: MyShader,
@@ -107,14 +108,12 @@ impl Application for App {
         // gl.depth_func(ALWAYS); // always pass the depth test (same effect as glDisable(GL_DEPTH_TEST))
 
         //  cube vao
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -129,18 +128,16 @@ impl Application for App {
         gl.bind_vertex_array(None);
 
         // plane vao
-        let plane_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo));
+        let plane_vbo = OwnedBuffer::create_buffer(gl, "plane vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&PLANE_VERTICES),
             STATIC_DRAW,
         );
 
-        let plane_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(plane_vao));
+        let plane_vao = OwnedVertexArray::create_vertex_array(gl, "plane vao");
+        gl.bind_vertex_array(Some(plane_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -166,10 +163,10 @@ impl Application for App {
         // shader.set_int(gl, "texture1", 0);
 
         Self {
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             cube_vao,
             cube_texture,
-            plane_vbo,
+            _plane_vbo: plane_vbo,
             plane_vao,
             plane_texture,
             shader,
@@ -195,7 +192,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
 
         // cubes
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.cube_texture.bind(gl, 0);
 
         let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(-1.0, 0.0, -1.0));
@@ -207,7 +204,7 @@ impl Application for App {
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         // plane
-        gl.bind_vertex_array(Some(self.plane_vao));
+        gl.bind_vertex_array(Some(self.plane_vao.raw()));
         self.plane_texture.bind(gl, 0);
         let model = glm::Mat4::identity();
         self.shader.set_mat4(gl, "model", &model);
@@ -220,18 +217,4 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_buffer(self.cube_vbo);
-        gl.delete_vertex_array(self.cube_vao);
-        self.cube_texture.delete(gl);
-
-        gl.delete_buffer(self.plane_vbo);
-        gl.delete_vertex_array(self.plane_vao);
-        self.plane_texture.delete(gl);
-    }
 }
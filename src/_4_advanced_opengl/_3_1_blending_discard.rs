@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use crate::{resources, texture};
@@ -96,16 +97,16 @@ const VEGETATION_POSITION: [glm::Vec3; 5] = [
 ];
 
 struct App {
-    cube_vbo: Buffer,
-    cube_vao: VertexArray,
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
     cube_texture: texture::Texture,
 
-    plane_vbo: Buffer,
-    plane_vao: VertexArray,
+    _plane_vbo: OwnedBuffer,
+    plane_vao: OwnedVertexArray,
     plane_texture: texture::Texture,
 
-    vegetation_vbo: Buffer,
-    vegetation_vao: VertexArray,
+    _vegetation_vbo: OwnedBuffer,
+    vegetation_vao: OwnedVertexArray,
     vegetation_texture: texture::Texture,
 
     shader: MyShader,
@@ -129,14 +130,12 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         //  cube vao
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -151,18 +150,16 @@ impl Application for App {
         gl.bind_vertex_array(None);
 
         // plane vao
-        let plane_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo));
+        let plane_vbo = OwnedBuffer::create_buffer(gl, "plane vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&PLANE_VERTICES),
             STATIC_DRAW,
         );
 
-        let plane_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(plane_vao));
+        let plane_vao = OwnedVertexArray::create_vertex_array(gl, "plane vao");
+        gl.bind_vertex_array(Some(plane_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -176,18 +173,16 @@ impl Application for App {
         gl.enable_vertex_attrib_array(1);
 
         // vegetation vao
-        let vegetation_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(vegetation_vbo));
+        let vegetation_vbo = OwnedBuffer::create_buffer(gl, "vegetation vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(vegetation_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&TRANSPARENT_VERTICES),
             STATIC_DRAW,
         );
 
-        let vegetation_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(vegetation_vao));
+        let vegetation_vao = OwnedVertexArray::create_vertex_array(gl, "vegetation vao");
+        gl.bind_vertex_array(Some(vegetation_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -218,13 +213,13 @@ impl Application for App {
         shader.set_int(gl, "texture1", 0);
 
         Self {
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             cube_vao,
             cube_texture,
-            plane_vbo,
+            _plane_vbo: plane_vbo,
             plane_vao,
             plane_texture,
-            vegetation_vbo,
+            _vegetation_vbo: vegetation_vbo,
             vegetation_vao,
             vegetation_texture,
             shader,
@@ -250,7 +245,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
 
         // cubes
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.cube_texture.bind(gl, 0);
 
         let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(-1.0, 0.0, -1.0));
@@ -262,14 +257,14 @@ impl Application for App {
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         // plane
-        gl.bind_vertex_array(Some(self.plane_vao));
+        gl.bind_vertex_array(Some(self.plane_vao.raw()));
         self.plane_texture.bind(gl, 0);
         let model = glm::Mat4::identity();
         self.shader.set_mat4(gl, "model", &model);
         gl.draw_arrays(TRIANGLES, 0, 6);
 
         // vegetation
-        gl.bind_vertex_array(Some(self.vegetation_vao));
+        gl.bind_vertex_array(Some(self.vegetation_vao.raw()));
         self.vegetation_texture.bind(gl, 0);
         for &position in VEGETATION_POSITION.iter() {
             let model = glm::translate(&glm::Mat4::identity(), &position);
@@ -284,23 +279,4 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-        unsafe {
-            gl.delete_buffer(self.cube_vbo);
-            gl.delete_vertex_array(self.cube_vao);
-            self.cube_texture.delete(gl);
-
-            gl.delete_buffer(self.plane_vbo);
-            gl.delete_vertex_array(self.plane_vao);
-            self.plane_texture.delete(gl);
-
-            gl.delete_buffer(self.vegetation_vbo);
-            gl.delete_vertex_array(self.vegetation_vao);
-            self.vegetation_texture.delete(gl);
-        }
-    }
 }
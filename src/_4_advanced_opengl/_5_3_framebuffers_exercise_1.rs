@@ -1,4 +1,6 @@
 use crate::camera::Camera;
+use crate::framebuffer::{ColorFormat, Depth, Framebuffer, FramebufferDesc};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use crate::{resources, texture};
@@ -88,20 +90,18 @@ const QUAD_VERTICES: [f32; 24] = [
 ];
 
 struct App {
-    cube_vbo: Buffer,
-    cube_vao: VertexArray,
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
     cube_texture: texture::Texture,
 
-    plane_vbo: Buffer,
-    plane_vao: VertexArray,
+    _plane_vbo: OwnedBuffer,
+    plane_vao: OwnedVertexArray,
     plane_texture: texture::Texture,
 
-    quad_vbo: Buffer,
-    quad_vao: VertexArray,
+    _quad_vbo: OwnedBuffer,
+    quad_vao: OwnedVertexArray,
 
     framebuffer: Framebuffer,
-    texture_color_buffer: Texture,
-    rbo: Renderbuffer,
 
     shader: MyShader,
     screen_shader: MyShader,
@@ -132,14 +132,12 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         //  cube vao
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -154,18 +152,16 @@ impl Application for App {
         gl.bind_vertex_array(None);
 
         // plane vao
-        let plane_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo));
+        let plane_vbo = OwnedBuffer::create_buffer(gl, "plane vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&PLANE_VERTICES),
             STATIC_DRAW,
         );
 
-        let plane_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(plane_vao));
+        let plane_vao = OwnedVertexArray::create_vertex_array(gl, "plane vao");
+        gl.bind_vertex_array(Some(plane_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -179,18 +175,16 @@ impl Application for App {
         gl.enable_vertex_attrib_array(1);
 
         // screen quad vao
-        let quad_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(quad_vbo));
+        let quad_vbo = OwnedBuffer::create_buffer(gl, "quad vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(quad_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&QUAD_VERTICES),
             STATIC_DRAW,
         );
 
-        let quad_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(quad_vao));
+        let quad_vao = OwnedVertexArray::create_vertex_array(gl, "quad vao");
+        gl.bind_vertex_array(Some(quad_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 2, FLOAT, false, 4 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -221,69 +215,33 @@ impl Application for App {
 
         // framebuffer configuration
         // -------------------------
-        let framebuffer = gl.create_framebuffer().expect("Create framebuffer");
-        gl.bind_framebuffer(FRAMEBUFFER, Some(framebuffer));
-        // create a color attachment texture
-        let texture_color_buffer = gl.create_texture().expect("Create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture_color_buffer));
-        gl.tex_image_2d(
-            TEXTURE_2D,
-            0,
-            RGB as i32,
-            ctx.width() as i32,
-            ctx.height() as i32,
-            0,
-            RGB,
-            UNSIGNED_BYTE,
-            None,
-        );
-        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as i32);
-        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as i32);
-        gl.framebuffer_texture_2d(
-            FRAMEBUFFER,
-            COLOR_ATTACHMENT0,
-            TEXTURE_2D,
-            Some(texture_color_buffer),
-            0,
-        );
-        // create a renderbuffer object for depth and stencil attachment (we won't be sampling these)
-        let rbo = gl.create_renderbuffer().expect("Create renderbuffer");
-        gl.bind_renderbuffer(RENDERBUFFER, Some(rbo));
-        // use a single renderbuffer object for both a depth AND stencil buffer.
-        gl.renderbuffer_storage(
-            RENDERBUFFER,
-            DEPTH24_STENCIL8,
-            ctx.width() as i32,
-            ctx.height() as i32,
-        );
-        // now actually attach it
-        gl.framebuffer_renderbuffer(
-            FRAMEBUFFER,
-            DEPTH_STENCIL_ATTACHMENT,
-            RENDERBUFFER,
-            Some(rbo),
-        );
-        // check if framebuffer is complete
-        if gl.check_framebuffer_status(FRAMEBUFFER) != FRAMEBUFFER_COMPLETE {
-            log::error!("Framebuffer is not complete!");
-        }
-        gl.bind_framebuffer(FRAMEBUFFER, None);
+        // a color attachment texture and a renderbuffer object for depth and stencil attachment
+        // (we won't be sampling these), reallocated when the window is resized
+        let framebuffer = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(vec![ColorFormat::Rgba8])
+                .depth(Depth::Renderbuffer)
+                .stencil(true)
+                .build(),
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create framebuffer");
 
         // draw as wireframe
         // gl.polygon_mode(FRONT_AND_BACK, LINE);
 
         Self {
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             cube_vao,
             cube_texture,
-            plane_vbo,
+            _plane_vbo: plane_vbo,
             plane_vao,
             plane_texture,
-            quad_vbo,
+            _quad_vbo: quad_vbo,
             quad_vao,
             framebuffer,
-            texture_color_buffer,
-            rbo,
             shader,
             screen_shader,
             camera,
@@ -297,7 +255,7 @@ impl Application for App {
         // bind to framebuffer and draw to color texture as we normally
         // would, but with the view camera reversed.
         // bind to framebuffer and draw scene as we normally would to color texture
-        gl.bind_framebuffer(FRAMEBUFFER, Some(self.framebuffer));
+        self.framebuffer.bind(gl);
         gl.enable(DEPTH_TEST); // enable depth testing (is disabled for rendering screen-space quad)
 
         gl.clear_color(0.1, 0.1, 0.1, 1.0);
@@ -320,7 +278,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
 
         // cubes
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.cube_texture.bind(gl, 0);
 
         let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(-1.0, 0.0, -1.0));
@@ -332,7 +290,7 @@ impl Application for App {
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         // plane
-        gl.bind_vertex_array(Some(self.plane_vao));
+        gl.bind_vertex_array(Some(self.plane_vao.raw()));
         self.plane_texture.bind(gl, 0);
         let model = glm::Mat4::identity();
         self.shader.set_mat4(gl, "model", &model);
@@ -351,7 +309,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
 
         // cubes
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.cube_texture.bind(gl, 0);
 
         let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(-1.0, 0.0, -1.0));
@@ -363,7 +321,7 @@ impl Application for App {
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         // plane
-        gl.bind_vertex_array(Some(self.plane_vao));
+        gl.bind_vertex_array(Some(self.plane_vao.raw()));
         self.plane_texture.bind(gl, 0);
         let model = glm::Mat4::identity();
         self.shader.set_mat4(gl, "model", &model);
@@ -375,36 +333,23 @@ impl Application for App {
         // disable depth test so screen-space quad isn't discarded due to depth test.
         gl.disable(DEPTH_TEST);
         self.screen_shader.use_shader(gl);
-        gl.bind_vertex_array(Some(self.quad_vao));
+        gl.bind_vertex_array(Some(self.quad_vao.raw()));
         // use the color attachment texture as the texture of the quad plane
-        gl.bind_texture(TEXTURE_2D, Some(self.texture_color_buffer));
+        self.framebuffer.bind_color(gl, 0, 0);
         gl.draw_arrays(TRIANGLES, 0, 6);
         gl.bind_vertex_array(None);
     }
 
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        self.framebuffer
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+    }
+
     unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_buffer(self.cube_vbo);
-        gl.delete_vertex_array(self.cube_vao);
-        self.cube_texture.delete(gl);
-
-        gl.delete_buffer(self.plane_vbo);
-        gl.delete_vertex_array(self.plane_vao);
-        self.plane_texture.delete(gl);
-
-        gl.delete_buffer(self.quad_vbo);
-        gl.delete_vertex_array(self.quad_vao);
-
-        gl.delete_framebuffer(self.framebuffer);
-        gl.delete_texture(self.texture_color_buffer);
-        gl.delete_renderbuffer(self.rbo);
-    }
 }
@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedTexture, OwnedVertexArray, SharedGl};
 use crate::resources::load_binary;
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
@@ -110,13 +111,13 @@ const SKYBOX_VERTICES: [f32; 108] = [
 ];
 
 struct App {
-    cube_vbo: Buffer,
-    cube_vao: VertexArray,
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
     cube_texture: texture::Texture,
 
-    skybox_vbo: Buffer,
-    skybox_vao: VertexArray,
-    skybox_texture: Texture,
+    _skybox_vbo: OwnedBuffer,
+    skybox_vao: OwnedVertexArray,
+    skybox_texture: OwnedTexture,
 
     shader: MyShader,
     skybox_shader: MyShader,
@@ -148,14 +149,12 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         //  cube vao
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(ARRAY_BUFFER, bytemuck::cast_slice(&VERTICES), STATIC_DRAW);
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
         gl.vertex_attrib_pointer_f32(
@@ -170,18 +169,16 @@ impl Application for App {
         gl.bind_vertex_array(None);
 
         // skybox vao
-        let skybox_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(skybox_vbo));
+        let skybox_vbo = OwnedBuffer::create_buffer(gl, "skybox vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(skybox_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&SKYBOX_VERTICES),
             STATIC_DRAW,
         );
 
-        let skybox_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(skybox_vao));
+        let skybox_vao = OwnedVertexArray::create_vertex_array(gl, "skybox vao");
+        gl.bind_vertex_array(Some(skybox_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 3 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
 
@@ -211,10 +208,10 @@ impl Application for App {
         skybox_shader.set_int(gl, "skybox", 0);
 
         Self {
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             cube_vao,
             cube_texture,
-            skybox_vbo,
+            _skybox_vbo: skybox_vbo,
             skybox_vao,
             skybox_texture,
             shader,
@@ -242,7 +239,7 @@ impl Application for App {
         self.shader.set_mat4(gl, "view", &view);
 
         // cubes
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.cube_texture.bind(gl, 0);
 
         let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(-1.0, 0.0, -1.0));
@@ -261,9 +258,9 @@ impl Application for App {
         self.skybox_shader.set_mat4(gl, "view", &view);
         self.skybox_shader.set_mat4(gl, "projection", &projection);
         // skybox cube
-        gl.bind_vertex_array(Some(self.skybox_vao));
+        gl.bind_vertex_array(Some(self.skybox_vao.raw()));
         gl.active_texture(TEXTURE0);
-        gl.bind_texture(TEXTURE_CUBE_MAP, Some(self.skybox_texture));
+        gl.bind_texture(TEXTURE_CUBE_MAP, Some(self.skybox_texture.raw()));
         gl.draw_arrays(TRIANGLES, 0, 36);
 
         gl.bind_vertex_array(None);
@@ -274,25 +271,11 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_buffer(self.cube_vbo);
-        gl.delete_vertex_array(self.cube_vao);
-        self.cube_texture.delete(gl);
-
-        gl.delete_buffer(self.skybox_vbo);
-        gl.delete_vertex_array(self.skybox_vao);
-        gl.delete_texture(self.skybox_texture);
-    }
 }
 
-async unsafe fn load_cubemap(gl: &Context, faces: &[&str]) -> Texture {
-    let texture = gl.create_texture().expect("Failed to create texture");
-    gl.bind_texture(TEXTURE_CUBE_MAP, Some(texture));
+async unsafe fn load_cubemap(gl: &SharedGl, faces: &[&str]) -> OwnedTexture {
+    let texture = OwnedTexture::create_texture(gl, "texture");
+    gl.bind_texture(TEXTURE_CUBE_MAP, Some(texture.raw()));
 
     for (i, face) in faces.iter().enumerate() {
         let data = load_binary(face).await.expect("Failed to load texture");
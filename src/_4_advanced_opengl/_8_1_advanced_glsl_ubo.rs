@@ -1,5 +1,7 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
 use crate::shader::MyShader;
+use crate::uniform_buffer::{std140, UniformBuffer};
 use crate::window::{run, AppContext, Application, WindowInitInfo};
 use glow::*;
 use nalgebra_glm as glm;
@@ -60,16 +62,25 @@ const CUBE_VERTICES: [f32; 108] = [
     -0.5,  0.5, -0.5,
 ];
 
+std140! {
+    /// the `Matrices` block of `_8_1_advanced_glsl.vs`
+    struct Matrices {
+        projection: glm::Mat4,
+        view: glm::Mat4,
+    }
+}
+
 struct App {
-    cube_vbo: Buffer,
-    cube_vao: VertexArray,
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
 
     shader_red: MyShader,
     shader_green: MyShader,
     shader_blue: MyShader,
     shader_yellow: MyShader,
 
-    ubo_matrices: Buffer,
+    matrices: Matrices,
+    ubo_matrices: UniformBuffer<Matrices>,
 
     camera: Camera,
 }
@@ -112,18 +123,16 @@ impl Application for App {
         gl.enable(DEPTH_TEST);
 
         //  cube vao
-        let cube_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo));
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&CUBE_VERTICES),
             STATIC_DRAW,
         );
 
-        let cube_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(cube_vao));
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 3 * size_of::<f32>() as i32, 0);
         gl.enable_vertex_attrib_array(0);
 
@@ -131,65 +140,31 @@ impl Application for App {
 
         // configure a uniform buffer object
         // ---------------------------------
-        // first. We get the relevant block indices
-        let uniform_block_index_red = gl
-            .get_uniform_block_index(shader_red.program(), "Matrices")
-            .expect("Failed to get uniform block index");
-        let uniform_block_index_green = gl
-            .get_uniform_block_index(shader_green.program(), "Matrices")
-            .expect("Failed to get uniform block index");
-        let uniform_block_index_blue = gl
-            .get_uniform_block_index(shader_blue.program(), "Matrices")
-            .expect("Failed to get uniform block index");
-        let uniform_block_index_yellow = gl
-            .get_uniform_block_index(shader_yellow.program(), "Matrices")
-            .expect("Failed to get uniform block index");
-        // then we link each shader's uniform block to this uniform binding point
-        gl.uniform_block_binding(shader_red.program(), uniform_block_index_red, 0);
-        gl.uniform_block_binding(shader_green.program(), uniform_block_index_green, 0);
-        gl.uniform_block_binding(shader_blue.program(), uniform_block_index_blue, 0);
-        gl.uniform_block_binding(shader_yellow.program(), uniform_block_index_yellow, 0);
-
-        // Now actually create the buffer
-        let ubo_matrices = gl.create_buffer().expect("Failed to create ubo buffer");
-        gl.bind_buffer(UNIFORM_BUFFER, Some(ubo_matrices));
-        gl.buffer_data_size(
-            UNIFORM_BUFFER,
-            2 * size_of::<glm::Mat4>() as i32,
-            STATIC_DRAW,
-        );
-        gl.bind_buffer(UNIFORM_BUFFER, None);
-        // define the range of the buffer that links to a uniform binding point
-        gl.bind_buffer_range(
-            UNIFORM_BUFFER,
-            0,
-            Some(ubo_matrices),
-            0,
-            2 * size_of::<glm::Mat4>() as i32,
-        );
-
         // store the projection matrix (we only do this once now) (note: we're not using zoom anymore by changing the FoV)
-        let projection = glm::perspective(
-            ctx.width() as f32 / ctx.height() as f32,
-            45.0_f32.to_radians(),
-            0.1,
-            100.0,
-        );
-        gl.bind_buffer(UNIFORM_BUFFER, Some(ubo_matrices));
-        gl.buffer_sub_data_u8_slice(
-            UNIFORM_BUFFER,
-            0,
-            bytemuck::cast_slice(projection.as_slice()),
-        );
-        gl.bind_buffer(UNIFORM_BUFFER, None);
+        let matrices = Matrices {
+            projection: glm::perspective(
+                ctx.width() as f32 / ctx.height() as f32,
+                45.0_f32.to_radians(),
+                0.1,
+                100.0,
+            ),
+            view: camera.view_matrix(),
+        };
+        // the buffer gets its own binding point
+        let ubo_matrices = UniformBuffer::new_with(gl, &matrices);
+        // then we link each shader's uniform block to this uniform binding point
+        for shader in [&shader_red, &shader_green, &shader_blue, &shader_yellow] {
+            shader.bind_block(gl, "Matrices", &ubo_matrices);
+        }
 
         Self {
-            cube_vbo,
+            _cube_vbo: cube_vbo,
             cube_vao,
             shader_red,
             shader_green,
             shader_blue,
             shader_yellow,
+            matrices,
             ubo_matrices,
             camera,
         }
@@ -202,18 +177,12 @@ impl Application for App {
         gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
 
         // set the view and projection matrix in the uniform block - we only have to do this once per loop iteration.
-        let view = self.camera.view_matrix();
-        gl.bind_buffer(UNIFORM_BUFFER, Some(self.ubo_matrices));
-        gl.buffer_sub_data_u8_slice(
-            UNIFORM_BUFFER,
-            size_of::<glm::Mat4>() as i32,
-            bytemuck::cast_slice(view.as_slice()),
-        );
-        gl.bind_buffer(UNIFORM_BUFFER, None);
+        self.matrices.view = self.camera.view_matrix();
+        self.ubo_matrices.update(gl, &self.matrices);
 
         // draw 4 cubes
         // RED
-        gl.bind_vertex_array(Some(self.cube_vao));
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
         self.shader_red.use_shader(gl);
         let model = glm::translation(&glm::vec3(-0.75, 0.75, 0.0)); // move top-left
         self.shader_red.set_mat4(gl, "model", &model);
@@ -245,18 +214,4 @@ impl Application for App {
         self.camera.process_keyboard_with_input(input);
         self.camera.process_mouse_with_input(input, true);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader_red.delete(gl);
-        self.shader_green.delete(gl);
-        self.shader_blue.delete(gl);
-        self.shader_yellow.delete(gl);
-
-        gl.delete_buffer(self.cube_vbo);
-        gl.delete_vertex_array(self.cube_vao);
-
-        gl.delete_buffer(self.ubo_matrices);
-    }
 }
@@ -54,3 +54,6 @@ pub use _10_3_asteroids_instanced::main_4_10_3;
 
 mod _11_1_anti_aliasing_msaa;
 pub use _11_1_anti_aliasing_msaa::main_4_11_1;
+
+mod _11_2_anti_aliasing_offscreen;
+pub use _11_2_anti_aliasing_offscreen::main_4_11_2;
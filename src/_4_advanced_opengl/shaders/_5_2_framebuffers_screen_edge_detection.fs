@@ -5,7 +5,8 @@ in vec2 TexCoords;
 
 uniform sampler2D screenTexture;
 
-const float offset = 1.0 / 300.0;
+// distance to the neighbouring samples, in texture coordinates
+uniform float offset;
 
 void main()
 {
@@ -23,7 +24,7 @@ void main()
 
     float kernel[9] = float[](
         1.0, 1.0, 1.0,
-        1.0, 8.0, 1.0,
+        1.0, -8.0, 1.0,
         1.0, 1.0, 1.0
     );
 
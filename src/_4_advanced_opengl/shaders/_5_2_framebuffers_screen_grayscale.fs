@@ -4,10 +4,12 @@ out vec4 FragColor;
 in vec2 TexCoords;
 
 uniform sampler2D screenTexture;
+// 0 keeps the input, 1 is fully gray
+uniform float strength;
 
 void main()
 {
-    FragColor = texture(screenTexture, TexCoords);
-    float average = 0.2126 * FragColor.r + 0.7152 * FragColor.g + 0.0722 * FragColor.b;
-    FragColor = vec4(average, average, average, 1.0);
+    vec3 col = texture(screenTexture, TexCoords).rgb;
+    float average = 0.2126 * col.r + 0.7152 * col.g + 0.0722 * col.b;
+    FragColor = vec4(mix(col, vec3(average), strength), 1.0);
 }
\ No newline at end of file
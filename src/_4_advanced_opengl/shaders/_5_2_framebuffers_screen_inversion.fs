@@ -4,9 +4,11 @@ out vec4 FragColor;
 in vec2 TexCoords;
 
 uniform sampler2D screenTexture;
+// 0 keeps the input, 1 fully inverts it
+uniform float strength;
 
 void main()
 {
     vec3 col = texture(screenTexture, TexCoords).rgb;
-    FragColor = vec4(1.0 - col, 1.0);
+    FragColor = vec4(mix(col, 1.0 - col, strength), 1.0);
 }
\ No newline at end of file
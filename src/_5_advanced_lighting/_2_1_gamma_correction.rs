@@ -1,4 +1,5 @@
 use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedTexture, OwnedVertexArray, SharedGl};
 use crate::resources::load_binary;
 use crate::shader::MyShader;
 use crate::window::{run, AppContext, Application, WindowInitInfo};
@@ -44,10 +45,10 @@ const LIGHT_COLORS: [glm::Vec3; 4] = [
 ];
 
 struct App {
-    plane_vao: VertexArray,
-    plane_vbo: Buffer,
-    floor_texture: Texture,
-    floor_texture_gamma_corrected: Texture,
+    plane_vao: OwnedVertexArray,
+    _plane_vbo: OwnedBuffer,
+    floor_texture: OwnedTexture,
+    floor_texture_gamma_corrected: OwnedTexture,
     shader: MyShader,
     camera: Camera,
 
@@ -72,18 +73,16 @@ impl Application for App {
         gl.enable(BLEND);
         gl.blend_func(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
 
-        let plane_vbo = gl.create_buffer().expect("Cannot create vbo buffer");
-        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo));
+        let plane_vbo = OwnedBuffer::create_buffer(gl, "plane vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(plane_vbo.raw()));
         gl.buffer_data_u8_slice(
             ARRAY_BUFFER,
             bytemuck::cast_slice(&PLANE_VERTICES),
             STATIC_DRAW,
         );
 
-        let plane_vao = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(plane_vao));
+        let plane_vao = OwnedVertexArray::create_vertex_array(gl, "plane vao");
+        gl.bind_vertex_array(Some(plane_vao.raw()));
         let stride = 8 * size_of::<f32>() as i32;
         gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
         gl.enable_vertex_attrib_array(0);
@@ -108,7 +107,7 @@ impl Application for App {
 
         Self {
             plane_vao,
-            plane_vbo,
+            _plane_vbo: plane_vbo,
             floor_texture,
             floor_texture_gamma_corrected,
             shader,
@@ -152,14 +151,14 @@ impl Application for App {
             .set_int(gl, "gamma", if self.gamma_enabled { 1 } else { 0 });
 
         // floor
-        gl.bind_vertex_array(Some(self.plane_vao));
+        gl.bind_vertex_array(Some(self.plane_vao.raw()));
         gl.active_texture(TEXTURE0);
         gl.bind_texture(
             TEXTURE_2D,
             Some(if self.gamma_enabled {
-                self.floor_texture_gamma_corrected
+                self.floor_texture_gamma_corrected.raw()
             } else {
-                self.floor_texture
+                self.floor_texture.raw()
             }),
         );
         gl.draw_arrays(TRIANGLES, 0, 6);
@@ -188,22 +187,13 @@ impl Application for App {
 
         self.gamma_enabled = input.key_held(winit::keyboard::KeyCode::Space);
     }
-
-    unsafe fn exit(&mut self, ctx: &AppContext) {
-        let gl = ctx.gl();
-
-        self.shader.delete(gl);
-
-        gl.delete_vertex_array(self.plane_vao);
-        gl.delete_buffer(self.plane_vbo);
-    }
 }
 
 async fn load_texture(
-    gl: &Context,
+    gl: &SharedGl,
     file_name: &str,
     gamma_correction: bool,
-) -> anyhow::Result<Texture> {
+) -> anyhow::Result<OwnedTexture> {
     log::info!("Loading texture file_name: {}", file_name);
     let bytes = load_binary(file_name).await?;
     let img = image::load_from_memory(&bytes).expect("Failed to load texture from bytes");
@@ -214,8 +204,8 @@ async fn load_texture(
     let data_format = RGBA;
 
     let texture = unsafe {
-        let texture = gl.create_texture().expect("Create texture");
-        gl.bind_texture(TEXTURE_2D, Some(texture));
+        let texture = OwnedTexture::create_texture(gl, "texture");
+        gl.bind_texture(TEXTURE_2D, Some(texture.raw()));
         gl.tex_image_2d(
             TEXTURE_2D,
             0,
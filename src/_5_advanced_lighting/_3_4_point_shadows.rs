@@ -0,0 +1,286 @@
+use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::point_shadow::{PointShadowMap, PointShadowSettings};
+use crate::shader::MyShader;
+use crate::uniform::MissingUniformPolicy;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use crate::{resources, texture};
+use glow::*;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_3_4() {
+    let init_info = WindowInitInfo::builder()
+        .title("Point Shadows | Space shadows, F PCF, G method, V cubemap faces".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const CUBE_VERTICES: [f32; 288] = [
+    // back face
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    -1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 1.0, // top-left
+    // front face
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    -1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 1.0, // top-left
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    // left face
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    -1.0,  1.0, -1.0, -1.0,  0.0,  0.0, 1.0, 1.0, // top-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0,  1.0, -1.0,  0.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    // right face
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0, -1.0,  1.0,  0.0,  0.0, 1.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0,  1.0,  1.0,  0.0,  0.0, 0.0, 0.0, // bottom-left
+    // bottom face
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 1.0, 1.0, // top-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    -1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    // top face
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    1.0,  1.0, 1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 1.0, 1.0, // top-right
+    1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    -1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 0.0, 0.0  // bottom-left
+];
+
+struct App {
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
+    wood_texture: texture::Texture,
+    shader: MyShader,
+    shadow_map: PointShadowMap,
+    shadows: bool,
+    show_faces: bool,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let version = Some(ctx.suggested_shader_version());
+
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_3_4_point_shadows.vs"),
+            include_str!("./shaders/_3_4_point_shadows.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 3.0));
+
+        gl.enable(DEPTH_TEST);
+
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&CUBE_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
+        let stride = 8 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_f32(2, 2, FLOAT, false, stride, 6 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(2);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // load textures
+        // -------------
+        let wood_texture = resources::load_texture(gl, "textures/wood.png")
+            .await
+            .expect("Failed to load texture");
+
+        // configure depth cubemap, single pass with a geometry shader where available
+        // -------------------------------------------------------------------------------
+        let shadow_map = PointShadowMap::new(gl, PointShadowSettings::builder().build(), version)
+            .expect("Failed to create point shadow map");
+
+        // shader configuration
+        // --------------------
+        shader.use_shader(gl);
+        shader.set_int(gl, "diffuseTexture", 0);
+        shader.set_int(gl, "depthMap", 1);
+
+        Self {
+            _cube_vbo: cube_vbo,
+            cube_vao,
+            wood_texture,
+            shader,
+            shadow_map,
+            shadows: true,
+            show_faces: false,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // move light position over time
+        let light_pos = glm::vec3(0.0, 0.0, (ctx.elapsed_time_secs() * 0.5).sin() * 3.0);
+
+        // 1. render scene to depth cubemap
+        // --------------------------------
+        let cube_vao = &self.cube_vao;
+        self.shadow_map
+            .render(gl, &light_pos, |shader| render_scene(gl, shader, cube_vao));
+
+        if self.show_faces {
+            self.shadow_map.draw_debug(gl, ctx.width(), ctx.height());
+            return;
+        }
+
+        // 2. render scene as normal
+        // -------------------------
+        gl.viewport(0, 0, ctx.width() as i32, ctx.height() as i32);
+        gl.enable(DEPTH_TEST);
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        self.shader.use_shader(gl);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        // set lighting uniforms
+        self.shader.set_vec3(gl, "lightPos", &light_pos);
+        self.shader.set_vec3(gl, "viewPos", &self.camera.position());
+        self.shader.set_bool(gl, "shadows", self.shadows); // enable/disable shadows by pressing 'SPACE'
+        let settings = &self.shadow_map.settings;
+        self.shader.set_float(gl, "far_plane", settings.far_plane);
+        self.shader.set_float(gl, "bias", settings.bias);
+        self.shader.set_bool(gl, "pcf", settings.pcf);
+        self.wood_texture.bind(gl, 0);
+        self.shadow_map.bind_texture(gl, 1);
+        gl.active_texture(TEXTURE0);
+        render_scene(gl, &self.shader, &self.cube_vao);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Point shadows").show(egui_ctx, |ui| {
+            ui.checkbox(&mut self.shadows, "Shadows");
+            ui.checkbox(&mut self.show_faces, "Show cubemap faces");
+            self.shadow_map.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Point shadows").with(|| {
+            ui.checkbox_config("Shadows", &mut self.shadows).build();
+            ui.checkbox_config("Show cubemap faces", &mut self.show_faces)
+                .build();
+            self.shadow_map.show_imgui(ui);
+        });
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        if input.key_pressed(KeyCode::Space) {
+            self.shadows = !self.shadows;
+        }
+        let settings = &mut self.shadow_map.settings;
+        if input.key_pressed(KeyCode::KeyF) {
+            settings.pcf = !settings.pcf;
+        }
+        if input.key_pressed(KeyCode::KeyG) {
+            settings.method = settings.method.next();
+        }
+        if input.key_pressed(KeyCode::KeyV) {
+            self.show_faces = !self.show_faces;
+        }
+    }
+}
+
+// renders the 3D scene
+// --------------------
+unsafe fn render_scene(gl: &Context, shader: &MyShader, cube_vao: &OwnedVertexArray) {
+    // only the lighting shader flips normals, the depth shaders have no `reverse_normals`
+    let reverse_normals = |value: bool| {
+        shader.set_with_policy(gl, "reverse_normals", &value, MissingUniformPolicy::Ignore);
+    };
+    // room cube
+    let mut model = glm::Mat4::identity();
+    model = glm::scale(&model, &glm::vec3(5.0, 5.0, 5.0));
+    shader.set_mat4(gl, "model", &model);
+    // A small little hack to invert normals when drawing cube from the inside so lighting
+    // still works.
+    reverse_normals(true);
+    render_cube(gl, cube_vao);
+    reverse_normals(false);
+    // cubes
+    let cubes = [
+        (glm::vec3(4.0, -3.5, 0.0), 0.5, None),
+        (glm::vec3(2.0, 3.0, 1.0), 0.75, None),
+        (glm::vec3(-3.0, -1.0, 0.0), 0.5, None),
+        (glm::vec3(-1.5, 1.0, 1.5), 0.5, None),
+        (glm::vec3(-1.5, 2.0, -3.0), 0.75, Some(60.0_f32)),
+    ];
+    for (position, scale, angle) in cubes {
+        let mut model = glm::translate(&glm::Mat4::identity(), &position);
+        if let Some(angle) = angle {
+            model = glm::rotate(
+                &model,
+                angle.to_radians(),
+                &glm::vec3(1.0, 0.0, 1.0).normalize(),
+            );
+        }
+        model = glm::scale(&model, &glm::vec3(scale, scale, scale));
+        shader.set_mat4(gl, "model", &model);
+        render_cube(gl, cube_vao);
+    }
+}
+
+// render_cube() renders a 1x1 3D cube in NDC.
+// -------------------------------------------------
+unsafe fn render_cube(gl: &Context, cube_vao: &OwnedVertexArray) {
+    gl.bind_vertex_array(Some(cube_vao.raw()));
+    gl.draw_arrays(TRIANGLES, 0, 36);
+    gl.bind_vertex_array(None);
+}
@@ -0,0 +1,274 @@
+use crate::camera::Camera;
+use crate::cascaded_shadow::{CascadeSettings, CascadedShadowMap};
+use crate::gl_object::OwnedBuffer;
+use crate::model::Model;
+use crate::resources;
+use crate::shader::MyShader;
+use crate::uniform::MissingUniformPolicy;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use nalgebra_glm as glm;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_3_5() {
+    let init_info = WindowInitInfo::builder()
+        .title("Cascaded Shadow Mapping | Space shadows, V cascade colors".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+const ROCK_COUNT: usize = 8000;
+const NEAR_PLANE: f32 = 0.1;
+const FAR_PLANE: f32 = 1000.0;
+const LIGHT_COLOR: glm::Vec3 = glm::Vec3::new(1.0, 0.95, 0.85);
+
+struct App {
+    rock: Model,
+    planet: Model,
+    _instance_buffer: OwnedBuffer,
+    shader: MyShader,
+    instanced_shader: MyShader,
+    shadow_map: CascadedShadowMap,
+    shadows: bool,
+    show_cascades: bool,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let version = Some(ctx.suggested_shader_version());
+
+        // the same shader for the planet and the instanced rocks
+        let shader = |defines: &[(&str, &str)]| {
+            MyShader::new_from_source_with_defines(
+                gl,
+                include_str!("./shaders/_3_5_csm.vs"),
+                include_str!("./shaders/_3_5_csm.fs"),
+                version,
+                defines,
+            )
+            .expect("Failed to create program")
+        };
+        let instanced_shader = shader(&[("INSTANCED", "1")]);
+        let shader = shader(&[]);
+
+        let mut camera = Camera::new(
+            glm::vec3(0.0, 35.0, 200.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            -95.0,
+            -14.0,
+        );
+        camera.set_speed(25.0);
+
+        gl.enable(DEPTH_TEST);
+
+        // load models
+        // -----------
+        let rock = resources::load_obj(gl, "objects/rock/rock.obj")
+            .await
+            .expect("Failed to load model");
+        let planet = resources::load_obj(gl, "objects/planet/planet.obj")
+            .await
+            .expect("Failed to load model");
+
+        // the asteroid field of 4_10_3, with fewer but larger rocks so their shadows show
+        // -------------------------------------------------------------------------------
+        let model_matrices = generate_matrices(ROCK_COUNT);
+        let instance_buffer = OwnedBuffer::create_buffer(gl, "rock instances");
+        gl.bind_buffer(ARRAY_BUFFER, Some(instance_buffer.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&model_matrices),
+            STATIC_DRAW,
+        );
+        for mesh in &rock.meshes {
+            gl.bind_vertex_array(Some(mesh.vao.raw()));
+            // a mat4 takes 4 attribute locations, one per column
+            let stride = std::mem::size_of::<glm::Mat4>() as i32;
+            let vec4_size = std::mem::size_of::<glm::Vec4>() as i32;
+            for column in 0..4 {
+                gl.vertex_attrib_pointer_f32(
+                    3 + column,
+                    4,
+                    FLOAT,
+                    false,
+                    stride,
+                    column as i32 * vec4_size,
+                );
+                gl.enable_vertex_attrib_array(3 + column);
+                gl.vertex_attrib_divisor(3 + column, 1);
+            }
+            gl.bind_vertex_array(None);
+        }
+        gl.bind_buffer(ARRAY_BUFFER, None);
+
+        let shadow_map = CascadedShadowMap::new(gl, CascadeSettings::builder().build(), version)
+            .expect("Failed to create cascaded shadow map");
+
+        Self {
+            rock,
+            planet,
+            _instance_buffer: instance_buffer,
+            shader,
+            instanced_shader,
+            shadow_map,
+            shadows: true,
+            show_cascades: false,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+        let aspect = ctx.width() as f32 / ctx.height() as f32;
+        let light_dir = glm::normalize(&glm::vec3(-1.0, -0.3, -0.4));
+
+        // 1. fit the cascades to the view and render the depth of each
+        // ---------------------------------------------------------------
+        self.shadow_map
+            .update(&self.camera, aspect, NEAR_PLANE, FAR_PLANE, &light_dir);
+        let (rock, planet) = (&self.rock, &self.planet);
+        self.shadow_map.render(gl, |shader, instanced_shader| {
+            render_scene(gl, shader, instanced_shader, rock, planet);
+        });
+
+        // 2. render scene as normal using the cascades
+        // --------------------------------------------
+        gl.viewport(0, 0, ctx.width() as i32, ctx.height() as i32);
+        gl.clear_color(0.05, 0.05, 0.08, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        let projection = glm::perspective(
+            aspect,
+            self.camera.zoom().to_radians(),
+            NEAR_PLANE,
+            FAR_PLANE,
+        );
+        let view = self.camera.view_matrix();
+        for shader in [&self.shader, &self.instanced_shader] {
+            shader.use_shader(gl);
+            shader.set_mat4(gl, "projection", &projection);
+            shader.set_mat4(gl, "view", &view);
+            shader.set_vec3(gl, "viewPos", &self.camera.position());
+            shader.set_vec3(gl, "lightDir", &light_dir);
+            shader.set_vec3(gl, "lightColor", &LIGHT_COLOR);
+            shader.set_bool(gl, "shadows", self.shadows);
+            shader.set_bool(gl, "showCascades", self.show_cascades);
+            // well above the units the model textures bind from 0
+            self.shadow_map.set_uniforms(gl, shader, 5);
+        }
+        render_scene(
+            gl,
+            &self.shader,
+            &self.instanced_shader,
+            &self.rock,
+            &self.planet,
+        );
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Cascaded shadows").show(egui_ctx, |ui| {
+            ui.checkbox(&mut self.shadows, "Shadows");
+            ui.checkbox(&mut self.show_cascades, "Show cascades");
+            self.shadow_map.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Cascaded shadows").with(|| {
+            ui.checkbox_config("Shadows", &mut self.shadows).build();
+            ui.checkbox_config("Show cascades", &mut self.show_cascades)
+                .build();
+            self.shadow_map.show_imgui(ui);
+        });
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        if input.key_pressed(KeyCode::Space) {
+            self.shadows = !self.shadows;
+        }
+        if input.key_pressed(KeyCode::KeyV) {
+            self.show_cascades = !self.show_cascades;
+        }
+    }
+}
+
+/// draws the planet with `shader` and the rocks with `instanced_shader`, whatever is in use
+unsafe fn render_scene(
+    gl: &Context,
+    shader: &MyShader,
+    instanced_shader: &MyShader,
+    rock: &Model,
+    planet: &Model,
+) {
+    // draw planet
+    shader.use_shader(gl);
+    let mut model = glm::Mat4::identity();
+    model = glm::translate(&model, &glm::vec3(0.0, -3.0, 0.0));
+    model = glm::scale(&model, &glm::vec3(8.0, 8.0, 8.0));
+    shader.set_mat4(gl, "model", &model);
+    planet.draw(gl, shader);
+
+    // draw meteorites
+    instanced_shader.use_shader(gl);
+    instanced_shader.set_mat4(gl, "model", &glm::Mat4::identity());
+    gl.active_texture(TEXTURE0);
+    instanced_shader.set_with_policy(gl, "texture_diffuse1", &0, MissingUniformPolicy::Ignore);
+    gl.bind_texture(TEXTURE_2D, Some(rock.materials[0].textures[0].raw()));
+    for mesh in &rock.meshes {
+        gl.bind_vertex_array(Some(mesh.vao.raw()));
+        gl.draw_elements_instanced(
+            TRIANGLES,
+            mesh.indices.len() as i32,
+            UNSIGNED_INT,
+            0,
+            ROCK_COUNT as i32,
+        );
+        gl.bind_vertex_array(None);
+    }
+}
+
+/// the ring of 4_10_3: random positions around a circle of radius 150, random rotations, and
+/// scales of 0.2 to 1.0
+fn generate_matrices(amount: usize) -> Vec<glm::Mat4> {
+    let radius = 150.0;
+    let offset = 25.0_f32;
+    // fixed seed, so the field looks the same every run (and in golden images)
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    (0..amount)
+        .map(|i| {
+            // 1. translation: displace along circle with 'radius' in range [-offset, offset]
+            let angle = (i as f32 / amount as f32 * 360.0).to_radians();
+            let x = angle.sin() * radius + rng.gen_range(-offset..offset);
+            // keep height of asteroid field smaller compared to width of x and z
+            let y = rng.gen_range(-offset..offset) * 0.4;
+            let z = angle.cos() * radius + rng.gen_range(-offset..offset);
+            let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(x, y, z));
+
+            // 2. scale
+            let scale = rng.gen_range(0.2..1.0);
+            model = glm::scale(&model, &glm::vec3(scale, scale, scale));
+
+            // 3. rotation: add random rotation around a (semi)randomly picked rotation axis vector
+            let rot_angle = rng.gen_range(0.0..360.0_f32).to_radians();
+            glm::rotate(&model, rot_angle, &glm::vec3(0.4, 0.6, 0.8))
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+use crate::camera::Camera;
+use crate::model::Model;
+use crate::resources;
+use crate::shader::MyShader;
+use crate::texture::TextureType;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use nalgebra_glm as glm;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_4_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("Normal Mapping | N normal mapping".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+struct App {
+    shader: MyShader,
+    nanosuit: Model,
+    normal_mapping: bool,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        gl.enable(DEPTH_TEST);
+
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_4_1_normal_mapping.vs"),
+            include_str!("./shaders/_4_1_normal_mapping.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 4.5));
+
+        // the nanosuit's materials reference tangent space normal maps (`map_Bump *_ddn.png`),
+        // its tangents are generated on load
+        let nanosuit = resources::load_obj(gl, "objects/nanosuit/nanosuit.obj")
+            .await
+            .expect("Failed to load model");
+
+        Self {
+            shader,
+            nanosuit,
+            normal_mapping: true,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        // circle the light around the model, so the bumps of the normal map move with it
+        let time = ctx.elapsed_time_secs() * 0.5;
+        let light_pos = glm::vec3(time.sin() * 2.0, 0.5, time.cos() * 2.0);
+
+        self.shader.use_shader(gl);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        self.shader.set_vec3(gl, "lightPos", &light_pos);
+        self.shader.set_vec3(gl, "viewPos", &self.camera.position());
+        self.shader
+            .set_bool(gl, "normalMapping", self.normal_mapping);
+
+        let mut model = glm::Mat4::identity();
+        model = glm::translate(&model, &glm::vec3(0.0, -1.75, 0.0)); // translate it down so it's at the center of the scene
+        model = glm::scale(&model, &glm::vec3(0.2, 0.2, 0.2)); // it's a bit too big for our scene, so scale it down
+        self.shader.set_mat4(gl, "model", &model);
+
+        for mesh in &self.nanosuit.meshes {
+            let material = &self.nanosuit.materials[mesh.material_id];
+            let has = |ty: TextureType| material.textures.iter().any(|t| t.ty() == ty);
+            self.shader
+                .set_bool(gl, "hasSpecularMap", has(TextureType::Specular));
+            self.shader
+                .set_bool(gl, "hasNormalMap", has(TextureType::Normal));
+            mesh.draw(gl, &self.nanosuit.materials, &self.shader);
+        }
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Normal mapping").show(egui_ctx, |ui| {
+            ui.checkbox(&mut self.normal_mapping, "Normal mapping");
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Normal mapping").with(|| {
+            ui.checkbox_config("Normal mapping", &mut self.normal_mapping)
+                .build();
+        });
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        if input.key_pressed(KeyCode::KeyN) {
+            self.normal_mapping = !self.normal_mapping;
+            log::info!("Normal mapping: {}", self.normal_mapping);
+        }
+    }
+}
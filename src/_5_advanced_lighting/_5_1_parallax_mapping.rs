@@ -0,0 +1,266 @@
+use crate::camera::Camera;
+use crate::gl_object::SharedGl;
+use crate::mesh::{Material, Mesh, Vertex};
+use crate::model::Model;
+use crate::parallax::ParallaxSettings;
+use crate::shader::MyShader;
+use crate::tangent;
+use crate::texture::{Texture, TextureType};
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+use nalgebra_glm as glm;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_5_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("Parallax Mapping | M method, Q/E height scale, L self-shadowing".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+/// width and height of the brick textures
+const TEXTURE_SIZE: u32 = 512;
+const BRICK_ROWS: u32 = 8;
+const BRICKS_PER_ROW: u32 = 4;
+
+struct App {
+    shader: MyShader,
+    wall: Model,
+    parallax: ParallaxSettings,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        gl.enable(DEPTH_TEST);
+
+        // the vertex shader of 4_1, bringing light and view into tangent space
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_4_1_normal_mapping.vs"),
+            include_str!("./shaders/_5_1_parallax_mapping.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 3.0));
+
+        Self {
+            shader,
+            wall: brick_wall(gl),
+            parallax: ParallaxSettings::builder().build(),
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        // the light sweeps over the wall at a low angle, which shows the self-shadowing best
+        let time = ctx.elapsed_time_secs() * 0.5;
+        let light_pos = glm::vec3(time.sin() * 1.5, 0.5, 0.4);
+
+        self.shader.use_shader(gl);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        self.shader.set_vec3(gl, "lightPos", &light_pos);
+        self.shader.set_vec3(gl, "viewPos", &self.camera.position());
+        self.parallax.set_uniforms(gl, &self.shader);
+
+        // tilt the wall back, the depth shows at oblique angles
+        let model = glm::rotate(
+            &glm::Mat4::identity(),
+            (-50.0_f32).to_radians(),
+            &glm::vec3(1.0, 0.0, 0.0),
+        );
+        self.shader.set_mat4(gl, "model", &model);
+        self.wall.draw(gl, &self.shader);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Parallax mapping").show(egui_ctx, |ui| {
+            self.parallax.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Parallax mapping").with(|| {
+            self.parallax.show_imgui(ui);
+        });
+    }
+
+    unsafe fn process_input(&mut self, ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        let delta_time = ctx.real_delta_time();
+        if input.key_held(KeyCode::KeyQ) {
+            self.parallax.height_scale = (self.parallax.height_scale - 0.1 * delta_time).max(0.0);
+        }
+        if input.key_held(KeyCode::KeyE) {
+            self.parallax.height_scale = (self.parallax.height_scale + 0.1 * delta_time).min(0.3);
+        }
+        if input.key_pressed(KeyCode::KeyM) {
+            self.parallax.method = self.parallax.method.next();
+            log::info!("Parallax method: {}", self.parallax.method);
+        }
+        if input.key_pressed(KeyCode::KeyL) {
+            self.parallax.self_shadow = !self.parallax.self_shadow;
+            log::info!("Self-shadowing: {}", self.parallax.self_shadow);
+        }
+    }
+}
+
+/// A 2x2 quad in the xy plane facing +z, with diffuse, normal and height maps of bricks
+fn brick_wall(gl: &SharedGl) -> Model {
+    let corners = [(-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0), (1.0, 1.0)];
+    let mut vertices = corners
+        .iter()
+        .map(|&(x, y)| Vertex {
+            position: glm::vec3(x, y, 0.0),
+            normal: glm::vec3(0.0, 0.0, 1.0),
+            // v grows downwards, along the rows of the images
+            tex_coords: glm::vec2((x + 1.0) / 2.0, (1.0 - y) / 2.0),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let mut indices = vec![0, 1, 2, 0, 2, 3];
+    tangent::generate_mikktspace_tangents(&mut vertices, &mut indices);
+
+    let (diffuse, height) = brick_images();
+    let normal = normal_map(&height, 8.0);
+    let texture = |image: DynamicImage, name: &str, ty: TextureType| {
+        Texture::from_image(gl, &image, name, ty).expect("Failed to create texture")
+    };
+    let material = Material {
+        name: "bricks".to_string(),
+        textures: vec![
+            texture(
+                DynamicImage::ImageRgb8(diffuse),
+                "bricks diffuse",
+                TextureType::Diffuse,
+            ),
+            texture(
+                DynamicImage::ImageRgb8(normal),
+                "bricks normal",
+                TextureType::Normal,
+            ),
+            texture(
+                DynamicImage::ImageLuma8(height),
+                "bricks height",
+                TextureType::Height,
+            ),
+        ],
+    };
+    Model {
+        meshes: vec![Mesh::new(gl, "brick wall", vertices, indices, 0)],
+        materials: vec![material],
+    }
+}
+
+/// Diffuse colors and heights of bricks in a running bond: bricks of slightly varying color
+/// with rounded edges and rough faces, and mortar in the lowest quarter of the heights
+fn brick_images() -> (RgbImage, GrayImage) {
+    // fixed seed, so the wall looks the same every run (and in golden images)
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    let brick_colors = (0..BRICK_ROWS * (BRICKS_PER_ROW + 1))
+        .map(|_| {
+            let base = glm::vec3(0.55, 0.22, 0.15) * rng.gen_range(0.7..1.1);
+            base + glm::vec3(rng.gen_range(-0.04..0.04), rng.gen_range(-0.03..0.03), 0.0)
+        })
+        .collect::<Vec<_>>();
+
+    let brick_height = TEXTURE_SIZE as f32 / BRICK_ROWS as f32;
+    let brick_width = TEXTURE_SIZE as f32 / BRICKS_PER_ROW as f32;
+    let mortar = 0.06 * brick_height;
+    let bevel = 0.2 * brick_height;
+    let mut diffuse = RgbImage::new(TEXTURE_SIZE, TEXTURE_SIZE);
+    let mut height = GrayImage::new(TEXTURE_SIZE, TEXTURE_SIZE);
+    for y in 0..TEXTURE_SIZE {
+        for x in 0..TEXTURE_SIZE {
+            let row = (y as f32 / brick_height) as u32;
+            // every other row is shifted by half a brick
+            let shift = if row % 2 == 1 { brick_width / 2.0 } else { 0.0 };
+            let column = ((x as f32 + shift) / brick_width) as u32;
+            // distance to the nearest joint
+            let in_row = y as f32 - row as f32 * brick_height;
+            let in_column = x as f32 + shift - column as f32 * brick_width;
+            let edge = in_row
+                .min(brick_height - in_row)
+                .min(in_column)
+                .min(brick_width - in_column);
+
+            let noise: f32 = rng.gen_range(-1.0..1.0);
+            let (color, h) = if edge < mortar {
+                (
+                    glm::vec3(0.45, 0.43, 0.4) * (1.0 + 0.1 * noise),
+                    0.05 + 0.03 * noise,
+                )
+            } else {
+                // round off the edges, and roughen the faces a little
+                let t = ((edge - mortar) / bevel).min(1.0);
+                let rounded = 1.0 - (1.0 - t) * (1.0 - t);
+                let color = brick_colors[(row * (BRICKS_PER_ROW + 1) + column) as usize];
+                (
+                    color * (1.0 + 0.08 * noise),
+                    0.3 + 0.65 * rounded + 0.01 * noise,
+                )
+            };
+            let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            diffuse.put_pixel(
+                x,
+                y,
+                Rgb([to_byte(color.x), to_byte(color.y), to_byte(color.z)]),
+            );
+            height.put_pixel(x, y, Luma([to_byte(h)]));
+        }
+    }
+    (diffuse, height)
+}
+
+/// Tangent space normals of the height map: x along the columns, y along the rows, which
+/// `tangent::generate_mikktspace_tangents` makes the tangent and bitangent of the quad.
+/// `strength` scales the slopes
+fn normal_map(height: &GrayImage, strength: f32) -> RgbImage {
+    let (width, rows) = height.dimensions();
+    // the textures repeat, so do the differences
+    let sample = |x: i64, y: i64| {
+        let x = x.rem_euclid(width as i64) as u32;
+        let y = y.rem_euclid(rows as i64) as u32;
+        height.get_pixel(x, y).0[0] as f32 / 255.0
+    };
+    RgbImage::from_fn(width, rows, |x, y| {
+        let (x, y) = (x as i64, y as i64);
+        let du = (sample(x + 1, y) - sample(x - 1, y)) / 2.0;
+        let dv = (sample(x, y + 1) - sample(x, y - 1)) / 2.0;
+        let normal = glm::normalize(&glm::vec3(-du * strength, -dv * strength, 1.0));
+        let to_byte = |v: f32| ((v * 0.5 + 0.5) * 255.0).round() as u8;
+        Rgb([to_byte(normal.x), to_byte(normal.y), to_byte(normal.z)])
+    })
+}
@@ -0,0 +1,262 @@
+use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::hdr::{HdrPipeline, HdrSettings};
+use crate::shader::MyShader;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use crate::{resources, texture};
+use glow::*;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_6_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("HDR | Q/E exposure, T tone mapper, Space auto exposure".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const CUBE_VERTICES: [f32; 288] = [
+    // back face
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 0.0, // bottom-right         
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    -1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 1.0, // top-left
+    // front face
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    -1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 1.0, // top-left
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    // left face
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    -1.0,  1.0, -1.0, -1.0,  0.0,  0.0, 1.0, 1.0, // top-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0,  1.0, -1.0,  0.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    // right face
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0, -1.0,  1.0,  0.0,  0.0, 1.0, 1.0, // top-right         
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0,  1.0,  1.0,  0.0,  0.0, 0.0, 0.0, // bottom-left     
+    // bottom face
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 1.0, 1.0, // top-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    -1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    // top face
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    1.0,  1.0, 1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 1.0, 1.0, // top-right     
+    1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    -1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 0.0, 0.0  // bottom-left
+];
+
+/// positions and colors of the lights, one very bright at the end of the tunnel
+const LIGHTS: [(glm::Vec3, glm::Vec3); 4] = [
+    (
+        glm::Vec3::new(0.0, 0.0, 49.5),
+        glm::Vec3::new(200.0, 200.0, 200.0),
+    ),
+    (
+        glm::Vec3::new(-1.4, -1.9, 9.0),
+        glm::Vec3::new(0.1, 0.0, 0.0),
+    ),
+    (
+        glm::Vec3::new(0.0, -1.8, 4.0),
+        glm::Vec3::new(0.0, 0.0, 0.2),
+    ),
+    (
+        glm::Vec3::new(0.8, -1.7, 6.0),
+        glm::Vec3::new(0.0, 0.1, 0.0),
+    ),
+];
+
+struct App {
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
+    wood_texture: texture::Texture,
+    shader: MyShader,
+    hdr: HdrPipeline,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_6_1_lighting.vs"),
+            include_str!("./shaders/_6_1_lighting.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        // looking down the tunnel, towards the bright light at its end
+        let camera = Camera::new(
+            glm::vec3(0.0, 0.0, 5.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            90.0,
+            0.0,
+        );
+
+        gl.enable(DEPTH_TEST);
+
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&CUBE_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
+        let stride = 8 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_f32(2, 2, FLOAT, false, stride, 6 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(2);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // load textures
+        // -------------
+        let wood_texture = resources::load_texture(gl, "textures/wood.png")
+            .await
+            .expect("Failed to load texture");
+
+        // configure floating point framebuffer and tone mapping
+        // ------------------------------------------------------
+        let hdr = HdrPipeline::new(
+            gl,
+            HdrSettings::builder().build(),
+            Some(ctx.suggested_shader_version()),
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create HDR pipeline");
+
+        // shader configuration
+        // --------------------
+        shader.use_shader(gl);
+        shader.set_int(gl, "diffuseTexture", 0);
+        for (i, (position, color)) in LIGHTS.iter().enumerate() {
+            shader.set_vec3(gl, &format!("lights[{}].Position", i), position);
+            shader.set_vec3(gl, &format!("lights[{}].Color", i), color);
+        }
+
+        Self {
+            _cube_vbo: cube_vbo,
+            cube_vao,
+            wood_texture,
+            shader,
+            hdr,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // 1. render scene into floating point framebuffer
+        // -----------------------------------------------
+        self.hdr.begin(gl);
+        gl.enable(DEPTH_TEST);
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.use_shader(gl);
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        self.wood_texture.bind(gl, 0);
+        // render tunnel
+        let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, 0.0, 25.0));
+        model = glm::scale(&model, &glm::vec3(2.5, 2.5, 27.5));
+        self.shader.set_mat4(gl, "model", &model);
+        self.shader.set_bool(gl, "inverse_normals", true);
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
+        gl.draw_arrays(TRIANGLES, 0, 36);
+        gl.bind_vertex_array(None);
+
+        // 2. now render floating point color buffer to 2D quad and tonemap HDR colors to
+        // default framebuffer's (clamped) color range
+        // --------------------------------------------------------------------------------
+        self.hdr
+            .resolve(gl, ctx.render_delta_time(), None, ctx.width(), ctx.height());
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("HDR").show(egui_ctx, |ui| {
+            self.hdr.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("HDR").with(|| {
+            self.hdr.show_imgui(ui);
+        });
+    }
+
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        self.hdr
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+    }
+
+    unsafe fn process_input(&mut self, ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        let settings = &mut self.hdr.settings;
+        let delta_time = ctx.real_delta_time();
+        if input.key_held(KeyCode::KeyQ) {
+            settings.exposure = (settings.exposure - 0.5 * delta_time).max(0.01);
+        }
+        if input.key_held(KeyCode::KeyE) {
+            settings.exposure += 0.5 * delta_time;
+        }
+        if input.key_pressed(KeyCode::KeyT) {
+            settings.tone_mapper = settings.tone_mapper.next();
+        }
+        if input.key_pressed(KeyCode::Space) {
+            settings.auto_exposure = !settings.auto_exposure;
+        }
+    }
+}
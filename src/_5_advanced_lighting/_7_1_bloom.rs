@@ -0,0 +1,344 @@
+use crate::bloom::{Bloom, BloomSettings};
+use crate::camera::Camera;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::hdr::{HdrPipeline, HdrSettings};
+use crate::shader::MyShader;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use crate::{resources, texture};
+use glow::*;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_7_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("Bloom | Space bloom, B blur method, M bright pass, Q/E exposure".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const CUBE_VERTICES: [f32; 288] = [
+    // back face
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 0.0, // bottom-right         
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    -1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 1.0, // top-left
+    // front face
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    -1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 1.0, // top-left
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    // left face
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    -1.0,  1.0, -1.0, -1.0,  0.0,  0.0, 1.0, 1.0, // top-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0,  1.0, -1.0,  0.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    // right face
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0, -1.0,  1.0,  0.0,  0.0, 1.0, 1.0, // top-right         
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0,  1.0,  1.0,  0.0,  0.0, 0.0, 0.0, // bottom-left     
+    // bottom face
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 1.0, 1.0, // top-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    -1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    // top face
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    1.0,  1.0, 1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 1.0, 1.0, // top-right     
+    1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    -1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 0.0, 0.0  // bottom-left
+];
+
+/// positions and colors of the lights
+const LIGHTS: [(glm::Vec3, glm::Vec3); 4] = [
+    (glm::Vec3::new(0.0, 0.5, 1.5), glm::Vec3::new(5.0, 5.0, 5.0)),
+    (
+        glm::Vec3::new(-4.0, 0.5, -3.0),
+        glm::Vec3::new(10.0, 0.0, 0.0),
+    ),
+    (
+        glm::Vec3::new(3.0, 0.5, 1.0),
+        glm::Vec3::new(0.0, 0.0, 15.0),
+    ),
+    (
+        glm::Vec3::new(-0.8, 2.4, -1.0),
+        glm::Vec3::new(0.0, 5.0, 0.0),
+    ),
+];
+
+/// position, rotation angle in degrees around (1, 0, 1) and scale of the containers
+const CONTAINERS: [(glm::Vec3, f32, f32); 6] = [
+    (glm::Vec3::new(0.0, 1.5, 0.0), 0.0, 0.5),
+    (glm::Vec3::new(2.0, 0.0, 1.0), 0.0, 0.5),
+    (glm::Vec3::new(-1.0, -1.0, 2.0), 60.0, 1.0),
+    (glm::Vec3::new(0.0, 2.7, 4.0), 23.0, 1.25),
+    (glm::Vec3::new(-2.0, 1.0, -3.0), 124.0, 1.0),
+    (glm::Vec3::new(-3.0, 0.0, 0.0), 0.0, 0.5),
+];
+
+struct App {
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
+    wood_texture: texture::Texture,
+    container_texture: texture::Texture,
+    shader: MyShader,
+    light_shader: MyShader,
+    hdr: HdrPipeline,
+    bloom: Bloom,
+    /// take the bright parts from the second color attachment written by the scene shaders,
+    /// instead of a threshold pass
+    mrt: bool,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let version = Some(ctx.suggested_shader_version());
+
+        let shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_6_1_lighting.vs"),
+            include_str!("./shaders/_7_1_bloom.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+        let light_shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_6_1_lighting.vs"),
+            include_str!("./shaders/_7_1_light_box.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 5.0));
+
+        gl.enable(DEPTH_TEST);
+
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&CUBE_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
+        let stride = 8 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_f32(2, 2, FLOAT, false, stride, 6 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(2);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // load textures
+        // -------------
+        let wood_texture = resources::load_texture(gl, "textures/wood.png")
+            .await
+            .expect("Failed to load texture");
+        let container_texture = resources::load_texture(gl, "textures/container2.png")
+            .await
+            .expect("Failed to load texture");
+
+        // configure floating point framebuffer with a second color attachment for the bright
+        // parts, and the bloom targets
+        // ----------------------------------------------------------------------------------
+        let hdr = HdrPipeline::new_with_colors(
+            gl,
+            HdrSettings::builder().build(),
+            2,
+            version,
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create HDR pipeline");
+        let bloom = Bloom::new(
+            gl,
+            BloomSettings::builder().build(),
+            version,
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create bloom");
+
+        // shader configuration
+        // --------------------
+        shader.use_shader(gl);
+        shader.set_int(gl, "diffuseTexture", 0);
+        for (i, (position, color)) in LIGHTS.iter().enumerate() {
+            shader.set_vec3(gl, &format!("lights[{}].Position", i), position);
+            shader.set_vec3(gl, &format!("lights[{}].Color", i), color);
+        }
+
+        Self {
+            _cube_vbo: cube_vbo,
+            cube_vao,
+            wood_texture,
+            container_texture,
+            shader,
+            light_shader,
+            hdr,
+            bloom,
+            mrt: true,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // 1. render scene into floating point framebuffer
+        // -----------------------------------------------
+        self.hdr.begin(gl);
+        gl.enable(DEPTH_TEST);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.shader.use_shader(gl);
+        self.shader.set_mat4(gl, "projection", &projection);
+        self.shader.set_mat4(gl, "view", &view);
+        self.shader.set_bool(gl, "inverse_normals", false);
+        self.shader
+            .set_float(gl, "bloomThreshold", self.bloom.settings.threshold);
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
+        // create one large cube that acts as the floor
+        self.wood_texture.bind(gl, 0);
+        let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, -1.0, 0.0));
+        model = glm::scale(&model, &glm::vec3(12.5, 0.5, 12.5));
+        self.shader.set_mat4(gl, "model", &model);
+        gl.draw_arrays(TRIANGLES, 0, 36);
+        // then create multiple cubes as the scenery
+        self.container_texture.bind(gl, 0);
+        for (position, angle, scale) in CONTAINERS {
+            let mut model = glm::translate(&glm::Mat4::identity(), &position);
+            model = glm::rotate(
+                &model,
+                angle.to_radians(),
+                &glm::normalize(&glm::vec3(1.0, 0.0, 1.0)),
+            );
+            model = glm::scale(&model, &glm::vec3(scale, scale, scale));
+            self.shader.set_mat4(gl, "model", &model);
+            gl.draw_arrays(TRIANGLES, 0, 36);
+        }
+
+        // finally show all the light sources as bright cubes
+        self.light_shader.use_shader(gl);
+        self.light_shader.set_mat4(gl, "projection", &projection);
+        self.light_shader.set_mat4(gl, "view", &view);
+        for (position, color) in LIGHTS {
+            let mut model = glm::translate(&glm::Mat4::identity(), &position);
+            model = glm::scale(&model, &glm::vec3(0.25, 0.25, 0.25));
+            self.light_shader.set_mat4(gl, "model", &model);
+            self.light_shader.set_vec3(gl, "lightColor", &color);
+            gl.draw_arrays(TRIANGLES, 0, 36);
+        }
+        gl.bind_vertex_array(None);
+
+        // 2. blur the bright parts and add them to the scene
+        // --------------------------------------------------
+        let bright = self.hdr.scene().color_texture(1).filter(|_| self.mrt);
+        self.bloom.apply(gl, self.hdr.scene(), bright);
+
+        // 3. now render floating point color buffer to 2D quad and tonemap HDR colors to
+        // default framebuffer's (clamped) color range
+        // --------------------------------------------------------------------------------
+        self.hdr
+            .resolve(gl, ctx.render_delta_time(), None, ctx.width(), ctx.height());
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Bloom").show(egui_ctx, |ui| {
+            ui.checkbox(&mut self.mrt, "Bright parts from the scene shaders (MRT)");
+            self.bloom.show_egui(ui);
+        });
+        egui::Window::new("HDR").show(egui_ctx, |ui| {
+            self.hdr.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Bloom").with(|| {
+            ui.checkbox_config("Bright parts from the scene shaders (MRT)", &mut self.mrt)
+                .build();
+            self.bloom.show_imgui(ui);
+        });
+        ui.window_config("HDR").with(|| {
+            self.hdr.show_imgui(ui);
+        });
+    }
+
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        self.hdr
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+        self.bloom
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+    }
+
+    unsafe fn process_input(&mut self, ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        let bloom = &mut self.bloom.settings;
+        if input.key_pressed(KeyCode::Space) {
+            bloom.enabled = !bloom.enabled;
+        }
+        if input.key_pressed(KeyCode::KeyB) {
+            bloom.set_method(bloom.method.next());
+        }
+        if input.key_pressed(KeyCode::KeyM) {
+            self.mrt = !self.mrt;
+        }
+
+        let settings = &mut self.hdr.settings;
+        let delta_time = ctx.real_delta_time();
+        if input.key_held(KeyCode::KeyQ) {
+            settings.exposure = (settings.exposure - 0.5 * delta_time).max(0.01);
+        }
+        if input.key_held(KeyCode::KeyE) {
+            settings.exposure += 0.5 * delta_time;
+        }
+    }
+}
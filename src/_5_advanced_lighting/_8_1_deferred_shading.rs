@@ -0,0 +1,396 @@
+use crate::camera::Camera;
+use crate::deferred::{DeferredLighting, GBuffer, GBufferView, PointLight};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::model::Model;
+use crate::shader::MyShader;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use crate::{resources, texture};
+use glow::*;
+use nalgebra_glm as glm;
+use rand::{Rng, SeedableRng};
+use std::mem::size_of;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_8_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("Deferred Shading | L lighting, G g-buffer view, +/- lights".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const FLOOR_VERTICES: [f32; 48] = [
+    // positions          // normals        // texcoords
+    10.0, 0.0,  10.0,  0.0, 1.0, 0.0,  5.0, 0.0,
+    -10.0, 0.0, -10.0,  0.0, 1.0, 0.0,  0.0, 5.0,
+    -10.0, 0.0,  10.0,  0.0, 1.0, 0.0,  0.0, 0.0,
+
+    10.0, 0.0,  10.0,  0.0, 1.0, 0.0,  5.0, 0.0,
+    10.0, 0.0, -10.0,  0.0, 1.0, 0.0,  5.0, 5.0,
+    -10.0, 0.0, -10.0,  0.0, 1.0, 0.0,  0.0, 5.0
+];
+
+#[rustfmt::skip]
+const WINDOW_VERTICES: [f32; 30] = [
+    // positions  // texture Coords
+    0.0, 0.5, 0.0, 0.0, 1.0,
+    0.0, -0.5, 0.0, 0.0, 0.0,
+    1.0, -0.5, 0.0, 1.0, 0.0,
+
+    0.0, 0.5, 0.0, 0.0, 1.0,
+    1.0, -0.5, 0.0, 1.0, 0.0,
+    1.0, 0.5, 0.0 , 1.0, 1.0
+];
+
+const OBJECT_POSITIONS: [glm::Vec3; 9] = [
+    glm::Vec3::new(-3.0, -3.0, -3.0),
+    glm::Vec3::new(0.0, -3.0, -3.0),
+    glm::Vec3::new(3.0, -3.0, -3.0),
+    glm::Vec3::new(-3.0, -3.0, 0.0),
+    glm::Vec3::new(0.0, -3.0, 0.0),
+    glm::Vec3::new(3.0, -3.0, 0.0),
+    glm::Vec3::new(-3.0, -3.0, 3.0),
+    glm::Vec3::new(0.0, -3.0, 3.0),
+    glm::Vec3::new(3.0, -3.0, 3.0),
+];
+
+/// transparent windows between the rows of models, drawn forward after the lighting pass
+const WINDOW_POSITIONS: [glm::Vec3; 5] = [
+    glm::Vec3::new(-2.0, -1.5, 1.5),
+    glm::Vec3::new(1.0, -1.5, 1.5),
+    glm::Vec3::new(-0.5, -1.5, -1.5),
+    glm::Vec3::new(2.0, -2.0, -1.5),
+    glm::Vec3::new(-3.5, -2.0, -1.5),
+];
+
+const MAX_LIGHTS: usize = 1024;
+
+/// `count` lights of random colors scattered over the scene, the same for the same count
+fn random_lights(count: usize) -> Vec<PointLight> {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    // the more lights, the dimmer each, so the scene keeps about the same brightness
+    let scale = (64.0 / count.max(64) as f32).sqrt();
+    (0..count)
+        .map(|_| PointLight {
+            position: glm::vec3(
+                rng.gen_range(-5.0..5.0),
+                rng.gen_range(-3.0..1.0),
+                rng.gen_range(-5.0..5.0),
+            ),
+            // also calculate random color
+            color: glm::vec3(
+                rng.gen_range(0.5..1.0),
+                rng.gen_range(0.5..1.0),
+                rng.gen_range(0.5..1.0),
+            ) * scale,
+        })
+        .collect()
+}
+
+struct App {
+    _floor_vbo: OwnedBuffer,
+    floor_vao: OwnedVertexArray,
+    _window_vbo: OwnedBuffer,
+    window_vao: OwnedVertexArray,
+    floor_texture: texture::Texture,
+    window_texture: texture::Texture,
+    nanosuit: Model,
+    geometry_shader: MyShader,
+    window_shader: MyShader,
+    gbuffer: GBuffer,
+    lighting: DeferredLighting,
+    light_count: usize,
+    /// the light count or attenuation changed, the lights are regenerated before rendering
+    lights_changed: bool,
+    view: GBufferView,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let version = Some(ctx.suggested_shader_version());
+
+        let geometry_shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_8_1_g_buffer.vs"),
+            include_str!("./shaders/_8_1_g_buffer.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+        let window_shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_8_1_window.vs"),
+            include_str!("./shaders/_8_1_window.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+
+        // above the front row, looking down on the grid of models
+        let camera = Camera::new(
+            glm::vec3(0.0, 1.0, 9.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            -90.0,
+            -20.0,
+        );
+
+        gl.enable(DEPTH_TEST);
+
+        let floor_vbo = OwnedBuffer::create_buffer(gl, "floor vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(floor_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&FLOOR_VERTICES),
+            STATIC_DRAW,
+        );
+        let floor_vao = OwnedVertexArray::create_vertex_array(gl, "floor vao");
+        gl.bind_vertex_array(Some(floor_vao.raw()));
+        let stride = 8 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_f32(2, 2, FLOAT, false, stride, 6 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(2);
+
+        let window_vbo = OwnedBuffer::create_buffer(gl, "window vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(window_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&WINDOW_VERTICES),
+            STATIC_DRAW,
+        );
+        let window_vao = OwnedVertexArray::create_vertex_array(gl, "window vao");
+        gl.bind_vertex_array(Some(window_vao.raw()));
+        let stride = 5 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 2, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // load models and textures
+        // ------------------------
+        let nanosuit = resources::load_obj(gl, "objects/nanosuit/nanosuit.obj")
+            .await
+            .expect("Failed to load model");
+        let floor_texture = resources::load_texture(gl, "textures/wood.png")
+            .await
+            .expect("Failed to load texture");
+        let window_texture =
+            resources::load_texture(gl, "textures/blending_transparent_window.png")
+                .await
+                .expect("Failed to load texture");
+        window_texture.set_wrap_mode(gl, CLAMP_TO_EDGE as i32, CLAMP_TO_EDGE as i32);
+
+        // configure g-buffer framebuffer and lighting
+        // -------------------------------------------
+        let gbuffer = GBuffer::new(gl, version, ctx.width(), ctx.height())
+            .expect("Failed to create g-buffer");
+        let mut lighting =
+            DeferredLighting::new(gl, version).expect("Failed to create deferred lighting");
+        let light_count = 256;
+        lighting.set_lights(gl, &random_lights(light_count));
+
+        window_shader.use_shader(gl);
+        window_shader.set_int(gl, "texture1", 0);
+
+        Self {
+            _floor_vbo: floor_vbo,
+            floor_vao,
+            _window_vbo: window_vbo,
+            window_vao,
+            floor_texture,
+            window_texture,
+            nanosuit,
+            geometry_shader,
+            window_shader,
+            gbuffer,
+            lighting,
+            light_count,
+            lights_changed: false,
+            view: GBufferView::default(),
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        if self.lights_changed {
+            self.lighting
+                .set_lights(gl, &random_lights(self.light_count));
+            self.lights_changed = false;
+        }
+
+        // 1. geometry pass: render scene's geometry/color data into gbuffer
+        // -----------------------------------------------------------------
+        self.gbuffer.bind(gl);
+        gl.enable(DEPTH_TEST);
+        // keep alpha 0, it is the specular intensity of the empty parts
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT | STENCIL_BUFFER_BIT);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+        self.geometry_shader.use_shader(gl);
+        self.geometry_shader.set_mat4(gl, "projection", &projection);
+        self.geometry_shader.set_mat4(gl, "view", &view);
+        for position in OBJECT_POSITIONS {
+            let mut model = glm::translate(&glm::Mat4::identity(), &position);
+            model = glm::scale(&model, &glm::vec3(0.25, 0.25, 0.25));
+            self.geometry_shader.set_mat4(gl, "model", &model);
+            self.nanosuit.draw(gl, &self.geometry_shader);
+        }
+        // the floor takes its specular intensity from the wood as well
+        self.geometry_shader.set_int(gl, "texture_diffuse1", 0);
+        self.geometry_shader.set_int(gl, "texture_specular1", 1);
+        self.floor_texture.bind(gl, 0);
+        self.floor_texture.bind(gl, 1);
+        let model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, -3.0, 0.0));
+        self.geometry_shader.set_mat4(gl, "model", &model);
+        gl.bind_vertex_array(Some(self.floor_vao.raw()));
+        gl.draw_arrays(TRIANGLES, 0, 6);
+        gl.bind_vertex_array(None);
+        gl.active_texture(TEXTURE0);
+
+        if self.view != GBufferView::Lit {
+            self.gbuffer
+                .draw_debug(gl, self.view, ctx.width(), ctx.height());
+            return;
+        }
+
+        // 2. lighting pass: calculate lighting by iterating over a screen filled quad
+        // pixel-by-pixel using the gbuffer's content, or only the pixels covered by the
+        // light volumes
+        // -----------------------------------------------------------------------------
+        self.lighting.render(
+            gl,
+            &self.gbuffer,
+            &projection,
+            &view,
+            ctx.width(),
+            ctx.height(),
+        );
+
+        // 2.5. copy content of geometry's depth buffer to default framebuffer's depth buffer
+        // ----------------------------------------------------------------------------------
+        self.gbuffer
+            .framebuffer()
+            .blit_depth_to(gl, None, ctx.width(), ctx.height());
+
+        // 3. render lights and transparent objects on top of the scene, forward as usual
+        // -------------------------------------------------------------------------------
+        gl.enable(DEPTH_TEST);
+        self.lighting.draw_markers(gl, &projection, &view, 0.05);
+
+        let mut windows: Vec<(f32, glm::Vec3)> = WINDOW_POSITIONS
+            .iter()
+            .map(|&position| (glm::distance(&self.camera.position(), &position), position))
+            .collect();
+        windows.sort_by(|a, b| b.0.total_cmp(&a.0));
+        gl.enable(BLEND);
+        gl.blend_func(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
+        self.window_shader.use_shader(gl);
+        self.window_shader.set_mat4(gl, "projection", &projection);
+        self.window_shader.set_mat4(gl, "view", &view);
+        self.window_texture.bind(gl, 0);
+        gl.bind_vertex_array(Some(self.window_vao.raw()));
+        for (_, position) in windows {
+            let model = glm::translate(&glm::Mat4::identity(), &position);
+            self.window_shader.set_mat4(gl, "model", &model);
+            gl.draw_arrays(TRIANGLES, 0, 6);
+        }
+        gl.bind_vertex_array(None);
+        gl.disable(BLEND);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("Deferred shading").show(egui_ctx, |ui| {
+            egui::ComboBox::from_label("G-buffer view")
+                .selected_text(self.view.to_string())
+                .show_ui(ui, |ui| {
+                    for view in GBufferView::ALL {
+                        ui.selectable_value(&mut self.view, view, view.to_string());
+                    }
+                });
+            let count = ui.add(
+                egui::Slider::new(&mut self.light_count, 1..=MAX_LIGHTS)
+                    .logarithmic(true)
+                    .text("Lights"),
+            );
+            if self.lighting.show_egui(ui) || count.changed() {
+                self.lights_changed = true;
+            }
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("Deferred shading").with(|| {
+            for view in GBufferView::ALL {
+                if ui
+                    .radio_button_config(format!("{}##view", view), self.view == view)
+                    .build()
+                {
+                    self.view = view;
+                }
+            }
+            let mut count = self.light_count as i32;
+            let count_changed = ui
+                .slider_int_config("Lights", &mut count)
+                .range(1, MAX_LIGHTS as i32)
+                .build();
+            self.light_count = count as usize;
+            if self.lighting.show_imgui(ui) || count_changed {
+                self.lights_changed = true;
+            }
+        });
+    }
+
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        self.gbuffer
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        if input.key_pressed(KeyCode::KeyL) {
+            self.lighting.mode = self.lighting.mode.next();
+        }
+        if input.key_pressed(KeyCode::KeyG) {
+            self.view = self.view.next();
+        }
+        if input.key_pressed(KeyCode::Equal) {
+            self.light_count = (self.light_count * 2).min(MAX_LIGHTS);
+            self.lights_changed = true;
+        }
+        if input.key_pressed(KeyCode::Minus) {
+            self.light_count = (self.light_count / 2).max(1);
+            self.lights_changed = true;
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::camera::Camera;
+use crate::deferred::GBuffer;
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::model::Model;
+use crate::post_process::{fullscreen_shader, FullscreenQuad};
+use crate::resources;
+use crate::shader::MyShader;
+use crate::ssao::{Ssao, SsaoSettings, SsaoView};
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_5_9_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("SSAO | Space SSAO, B blur, V AO view, M model".to_string())
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+#[rustfmt::skip]
+const CUBE_VERTICES: [f32; 288] = [
+    // back face
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 0.0, // bottom-right         
+    1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 1.0, 1.0, // top-right
+    -1.0, -1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 0.0, // bottom-left
+    -1.0,  1.0, -1.0,  0.0,  0.0, -1.0, 0.0, 1.0, // top-left
+    // front face
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 1.0, 1.0, // top-right
+    -1.0,  1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 1.0, // top-left
+    -1.0, -1.0,  1.0,  0.0,  0.0,  1.0, 0.0, 0.0, // bottom-left
+    // left face
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    -1.0,  1.0, -1.0, -1.0,  0.0,  0.0, 1.0, 1.0, // top-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0, -1.0, -1.0,  0.0,  0.0, 0.0, 1.0, // bottom-left
+    -1.0, -1.0,  1.0, -1.0,  0.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0,  1.0,  1.0, -1.0,  0.0,  0.0, 1.0, 0.0, // top-right
+    // right face
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0, -1.0,  1.0,  0.0,  0.0, 1.0, 1.0, // top-right         
+    1.0, -1.0, -1.0,  1.0,  0.0,  0.0, 0.0, 1.0, // bottom-right
+    1.0,  1.0,  1.0,  1.0,  0.0,  0.0, 1.0, 0.0, // top-left
+    1.0, -1.0,  1.0,  1.0,  0.0,  0.0, 0.0, 0.0, // bottom-left     
+    // bottom face
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 1.0, 1.0, // top-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 1.0, 0.0, // bottom-left
+    -1.0, -1.0,  1.0,  0.0, -1.0,  0.0, 0.0, 0.0, // bottom-right
+    -1.0, -1.0, -1.0,  0.0, -1.0,  0.0, 0.0, 1.0, // top-right
+    // top face
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    1.0,  1.0, 1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 1.0, 1.0, // top-right     
+    1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 1.0, 0.0, // bottom-right
+    -1.0,  1.0, -1.0,  0.0,  1.0,  0.0, 0.0, 1.0, // top-left
+    -1.0,  1.0,  1.0,  0.0,  1.0,  0.0, 0.0, 0.0  // bottom-left
+];
+
+const LIGHT_POSITION: glm::Vec3 = glm::Vec3::new(2.0, 4.0, -2.0);
+const LIGHT_COLOR: glm::Vec3 = glm::Vec3::new(0.2, 0.2, 0.7);
+
+struct App {
+    _cube_vbo: OwnedBuffer,
+    cube_vao: OwnedVertexArray,
+    nanosuit: Model,
+    /// `None` if `objects/backpack/backpack.obj` is missing, it is not in the repository
+    backpack: Option<Model>,
+    show_backpack: bool,
+    geometry_shader: MyShader,
+    lighting_shader: MyShader,
+    quad: FullscreenQuad,
+    gbuffer: GBuffer,
+    ssao: Ssao,
+    view: SsaoView,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+        let version = Some(ctx.suggested_shader_version());
+
+        let geometry_shader = MyShader::new_from_source(
+            gl,
+            include_str!("./shaders/_9_1_ssao_geometry.vs"),
+            include_str!("./shaders/_9_1_ssao_geometry.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+        let lighting_shader = fullscreen_shader(
+            gl,
+            "ssao lighting",
+            include_str!("./shaders/_9_1_ssao_lighting.fs"),
+            version,
+        )
+        .expect("Failed to create program");
+
+        let camera = Camera::new(
+            glm::vec3(0.0, 2.0, 7.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            -90.0,
+            -10.0,
+        );
+
+        gl.enable(DEPTH_TEST);
+
+        let cube_vbo = OwnedBuffer::create_buffer(gl, "cube vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(cube_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&CUBE_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let cube_vao = OwnedVertexArray::create_vertex_array(gl, "cube vao");
+        gl.bind_vertex_array(Some(cube_vao.raw()));
+        let stride = 8 * size_of::<f32>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 3, FLOAT, false, stride, 3 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_f32(2, 2, FLOAT, false, stride, 6 * size_of::<f32>() as i32);
+        gl.enable_vertex_attrib_array(2);
+
+        gl.bind_buffer(ARRAY_BUFFER, None);
+        gl.bind_vertex_array(None);
+
+        // load models
+        // -----------
+        let nanosuit = resources::load_obj(gl, "objects/nanosuit/nanosuit.obj")
+            .await
+            .expect("Failed to load model");
+        let backpack = match resources::load_obj(gl, "objects/backpack/backpack.obj").await {
+            Ok(backpack) => Some(backpack),
+            Err(e) => {
+                log::warn!("Backpack not available, only showing the nanosuit: {}", e);
+                None
+            }
+        };
+
+        // configure g-buffer framebuffer and SSAO
+        // ---------------------------------------
+        let gbuffer = GBuffer::new(gl, version, ctx.width(), ctx.height())
+            .expect("Failed to create g-buffer");
+        let ssao = Ssao::new(
+            gl,
+            SsaoSettings::builder().build(),
+            version,
+            ctx.width(),
+            ctx.height(),
+        )
+        .expect("Failed to create SSAO");
+
+        // shader configuration
+        // --------------------
+        lighting_shader.use_shader(gl);
+        lighting_shader.set_int(gl, "gPosition", 0);
+        lighting_shader.set_int(gl, "gNormal", 1);
+        lighting_shader.set_int(gl, "gAlbedo", 2);
+        lighting_shader.set_int(gl, "ssao", 3);
+
+        Self {
+            _cube_vbo: cube_vbo,
+            cube_vao,
+            nanosuit,
+            backpack,
+            show_backpack: false,
+            geometry_shader,
+            lighting_shader,
+            quad: FullscreenQuad::new(gl),
+            gbuffer,
+            ssao,
+            view: SsaoView::default(),
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // 1. geometry pass: render scene's geometry/color data into gbuffer
+        // -----------------------------------------------------------------
+        self.gbuffer.bind(gl);
+        gl.enable(DEPTH_TEST);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT | STENCIL_BUFFER_BIT);
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            50.0,
+        );
+        let view = self.camera.view_matrix();
+        self.geometry_shader.use_shader(gl);
+        self.geometry_shader.set_mat4(gl, "projection", &projection);
+        self.geometry_shader.set_mat4(gl, "view", &view);
+        // room cube
+        let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, 7.0, 0.0));
+        model = glm::scale(&model, &glm::vec3(7.5, 7.5, 7.5));
+        self.geometry_shader.set_mat4(gl, "model", &model);
+        // invert normals as we're inside the cube
+        self.geometry_shader.set_bool(gl, "invertedNormals", true);
+        gl.bind_vertex_array(Some(self.cube_vao.raw()));
+        gl.draw_arrays(TRIANGLES, 0, 36);
+        gl.bind_vertex_array(None);
+        self.geometry_shader.set_bool(gl, "invertedNormals", false);
+        // model on the floor
+        match self.backpack.as_ref().filter(|_| self.show_backpack) {
+            Some(backpack) => {
+                let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, 0.5, 0.0));
+                model = glm::rotate(&model, (-90.0_f32).to_radians(), &glm::vec3(1.0, 0.0, 0.0));
+                self.geometry_shader.set_mat4(gl, "model", &model);
+                backpack.draw(gl, &self.geometry_shader);
+            }
+            None => {
+                let mut model = glm::translate(&glm::Mat4::identity(), &glm::vec3(0.0, -0.5, 0.0));
+                model = glm::scale(&model, &glm::vec3(0.25, 0.25, 0.25));
+                self.geometry_shader.set_mat4(gl, "model", &model);
+                self.nanosuit.draw(gl, &self.geometry_shader);
+            }
+        }
+
+        // 2. generate SSAO texture and blur it
+        // ------------------------------------
+        self.ssao.apply(gl, &self.gbuffer, &projection);
+
+        if self.view != SsaoView::Lit {
+            self.ssao
+                .draw_debug(gl, self.view, ctx.width(), ctx.height());
+            return;
+        }
+
+        // 3. lighting pass: traditional deferred Blinn-Phong lighting with added screen-space
+        // ambient occlusion
+        // -------------------------------------------------------------------------------------
+        gl.bind_framebuffer(FRAMEBUFFER, None);
+        gl.viewport(0, 0, ctx.width() as i32, ctx.height() as i32);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        self.lighting_shader.use_shader(gl);
+        // send light relevant uniforms
+        let light_pos_view =
+            (view * glm::vec4(LIGHT_POSITION.x, LIGHT_POSITION.y, LIGHT_POSITION.z, 1.0)).xyz();
+        self.lighting_shader
+            .set_vec3(gl, "light.Position", &light_pos_view);
+        self.lighting_shader
+            .set_vec3(gl, "light.Color", &LIGHT_COLOR);
+        // update attenuation parameters
+        self.lighting_shader.set_float(gl, "light.Linear", 0.09);
+        self.lighting_shader.set_float(gl, "light.Quadratic", 0.032);
+        self.gbuffer.bind_textures(gl);
+        gl.active_texture(TEXTURE3); // add extra SSAO texture to lighting pass
+        gl.bind_texture(TEXTURE_2D, Some(self.ssao.texture()));
+        gl.active_texture(TEXTURE0);
+        self.quad.draw(gl);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    fn ui(
+        &mut self,
+        _state: &crate::window::AppState,
+        _gl_ctx: &crate::window::GLContext,
+        egui_ctx: &egui::Context,
+    ) {
+        egui::Window::new("SSAO").show(egui_ctx, |ui| {
+            egui::ComboBox::from_label("View")
+                .selected_text(self.view.to_string())
+                .show_ui(ui, |ui| {
+                    for view in SsaoView::ALL {
+                        ui.selectable_value(&mut self.view, view, view.to_string());
+                    }
+                });
+            ui.add_enabled(
+                self.backpack.is_some(),
+                egui::Checkbox::new(&mut self.show_backpack, "Backpack"),
+            );
+            self.ssao.show_egui(ui);
+        });
+    }
+
+    #[cfg(feature = "imgui-support")]
+    fn do_ui(
+        &mut self,
+        ui: &easy_imgui_window::easy_imgui::Ui<crate::window::EasyImGuiFacade<Self>>,
+    ) {
+        ui.window_config("SSAO").with(|| {
+            for view in SsaoView::ALL {
+                if ui
+                    .radio_button_config(view.to_string(), self.view == view)
+                    .build()
+                {
+                    self.view = view;
+                }
+            }
+            if self.backpack.is_some() {
+                ui.checkbox_config("Backpack", &mut self.show_backpack)
+                    .build();
+            }
+            self.ssao.show_imgui(ui);
+        });
+    }
+
+    unsafe fn resize(&mut self, ctx: &AppContext, width: u32, height: u32) {
+        let gl = ctx.gl();
+        gl.viewport(0, 0, width as i32, height as i32);
+        self.gbuffer
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+        self.ssao
+            .resize(gl, width, height)
+            .expect("Failed to resize framebuffer");
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+
+        let settings = &mut self.ssao.settings;
+        if input.key_pressed(KeyCode::Space) {
+            settings.enabled = !settings.enabled;
+        }
+        if input.key_pressed(KeyCode::KeyB) {
+            settings.blur = !settings.blur;
+        }
+        if input.key_pressed(KeyCode::KeyV) {
+            self.view = self.view.next();
+        }
+        if input.key_pressed(KeyCode::KeyM) && self.backpack.is_some() {
+            self.show_backpack = !self.show_backpack;
+        }
+    }
+}
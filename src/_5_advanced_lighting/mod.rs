@@ -12,3 +12,27 @@ pub use _3_2_shadow_mapping_base::main_5_3_2;
 
 mod _3_3_shadow_mapping;
 pub use _3_3_shadow_mapping::main_5_3_3;
+
+mod _3_4_point_shadows;
+pub use _3_4_point_shadows::main_5_3_4;
+
+mod _3_5_cascaded_shadow_mapping;
+pub use _3_5_cascaded_shadow_mapping::main_5_3_5;
+
+mod _4_1_normal_mapping;
+pub use _4_1_normal_mapping::main_5_4_1;
+
+mod _5_1_parallax_mapping;
+pub use _5_1_parallax_mapping::main_5_5_1;
+
+mod _6_1_hdr;
+pub use _6_1_hdr::main_5_6_1;
+
+mod _7_1_bloom;
+pub use _7_1_bloom::main_5_7_1;
+
+mod _8_1_deferred_shading;
+pub use _8_1_deferred_shading::main_5_8_1;
+
+mod _9_1_ssao;
+pub use _9_1_ssao::main_5_9_1;
@@ -0,0 +1,44 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+in float ViewDepth;
+
+uniform sampler2D texture_diffuse1;
+
+// direction the sunlight travels in
+uniform vec3 lightDir;
+uniform vec3 lightColor;
+uniform vec3 viewPos;
+
+uniform bool shadows;
+// tint each pixel by its cascade
+uniform bool showCascades;
+
+#include "common/cascaded_shadows.glsl"
+
+void main()
+{
+    vec3 color = texture(texture_diffuse1, TexCoords).rgb;
+    vec3 normal = normalize(Normal);
+    vec3 toLight = normalize(-lightDir);
+    // ambient
+    vec3 ambient = 0.1 * color;
+    // diffuse
+    float diff = max(dot(toLight, normal), 0.0);
+    vec3 diffuse = diff * lightColor * color;
+    // specular
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec3 halfwayDir = normalize(toLight + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    vec3 specular = 0.2 * spec * lightColor;
+    // calculate shadow
+    float shadow = shadows ? CascadedShadowCalculation(FragPos, ViewDepth, normal, toLight) : 0.0;
+    vec3 lighting = ambient + (1.0 - shadow) * (diffuse + specular);
+    if (showCascades)
+        lighting *= CascadeDebugColor(ViewDepth);
+
+    FragColor = vec4(lighting, 1.0);
+}
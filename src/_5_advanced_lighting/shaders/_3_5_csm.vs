@@ -0,0 +1,33 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoords;
+#ifdef INSTANCED
+layout (location = 3) in mat4 aInstanceMatrix;
+#endif
+
+out vec3 FragPos;
+out vec3 Normal;
+out vec2 TexCoords;
+// distance to the camera along its view direction, picks the cascade
+out float ViewDepth;
+
+uniform mat4 projection;
+uniform mat4 view;
+uniform mat4 model;
+
+void main()
+{
+#ifdef INSTANCED
+    mat4 world = model * aInstanceMatrix;
+#else
+    mat4 world = model;
+#endif
+    vec4 worldPos = world * vec4(aPos, 1.0);
+    FragPos = worldPos.xyz;
+    Normal = transpose(inverse(mat3(world))) * aNormal;
+    TexCoords = aTexCoords;
+    vec4 viewPos = view * worldPos;
+    ViewDepth = -viewPos.z;
+    gl_Position = projection * viewPos;
+}
@@ -0,0 +1,43 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+in vec3 TangentLightPos;
+in vec3 TangentViewPos;
+in vec3 TangentFragPos;
+
+uniform sampler2D texture_diffuse1;
+uniform sampler2D texture_specular1;
+uniform sampler2D texture_normal1;
+
+// set per mesh, as not every material has all maps
+uniform bool hasSpecularMap;
+uniform bool hasNormalMap;
+uniform bool normalMapping;
+
+void main()
+{
+    // the normal of the surface is +z in tangent space, the normal map perturbs it
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+    if (normalMapping && hasNormalMap)
+    {
+        // transform normal vector to range [-1,1]
+        normal = normalize(texture(texture_normal1, TexCoords).rgb * 2.0 - 1.0);
+    }
+
+    // get diffuse color
+    vec3 color = texture(texture_diffuse1, TexCoords).rgb;
+    // ambient
+    vec3 ambient = 0.1 * color;
+    // diffuse
+    vec3 lightDir = normalize(TangentLightPos - TangentFragPos);
+    float diff = max(dot(lightDir, normal), 0.0);
+    vec3 diffuse = diff * color;
+    // specular
+    vec3 viewDir = normalize(TangentViewPos - TangentFragPos);
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    vec3 specularColor = hasSpecularMap ? texture(texture_specular1, TexCoords).rgb : vec3(0.2);
+    vec3 specular = specularColor * spec;
+    FragColor = vec4(ambient + diffuse + specular, 1.0);
+}
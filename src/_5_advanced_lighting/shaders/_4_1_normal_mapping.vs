@@ -0,0 +1,41 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoords;
+layout (location = 3) in vec3 aTangent;
+layout (location = 4) in vec3 aBitangent;
+
+out vec2 TexCoords;
+out vec3 TangentLightPos;
+out vec3 TangentViewPos;
+out vec3 TangentFragPos;
+
+uniform mat4 projection;
+uniform mat4 view;
+uniform mat4 model;
+
+uniform vec3 lightPos;
+uniform vec3 viewPos;
+
+void main()
+{
+    vec3 fragPos = vec3(model * vec4(aPos, 1.0));
+    TexCoords = aTexCoords;
+
+    mat3 normalMatrix = transpose(inverse(mat3(model)));
+    vec3 N = normalize(normalMatrix * aNormal);
+    // re-orthogonalize the tangent after the model matrix, keep the bitangent's handedness
+    vec3 T = normalize(normalMatrix * aTangent);
+    T = normalize(T - dot(T, N) * N);
+    vec3 B = cross(N, T);
+    if (dot(B, normalMatrix * aBitangent) < 0.0)
+        B = -B;
+
+    // the transpose of an orthonormal basis is its inverse: world to tangent space
+    mat3 TBN = transpose(mat3(T, B, N));
+    TangentLightPos = TBN * lightPos;
+    TangentViewPos = TBN * viewPos;
+    TangentFragPos = TBN * fragPos;
+
+    gl_Position = projection * view * model * vec4(aPos, 1.0);
+}
@@ -0,0 +1,40 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+in vec3 TangentLightPos;
+in vec3 TangentViewPos;
+in vec3 TangentFragPos;
+
+uniform sampler2D texture_diffuse1;
+uniform sampler2D texture_normal1;
+uniform sampler2D texture_height1;
+
+#include "common/parallax.glsl"
+
+void main()
+{
+    // offset texture coordinates with Parallax Mapping
+    vec3 viewDir = normalize(TangentViewPos - TangentFragPos);
+    vec2 texCoords = ParallaxMapping(texture_height1, TexCoords, viewDir);
+    // the edges of the wall would sample the repeated texture, cut them off instead
+    if (texCoords.x > 1.0 || texCoords.y > 1.0 || texCoords.x < 0.0 || texCoords.y < 0.0)
+        discard;
+
+    // then sample textures with new texture coords
+    vec3 normal = normalize(texture(texture_normal1, texCoords).rgb * 2.0 - 1.0);
+    vec3 color = texture(texture_diffuse1, texCoords).rgb;
+    // ambient
+    vec3 ambient = 0.1 * color;
+    // diffuse
+    vec3 lightDir = normalize(TangentLightPos - TangentFragPos);
+    float diff = max(dot(lightDir, normal), 0.0);
+    vec3 diffuse = diff * color;
+    // specular
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    vec3 specular = vec3(0.2) * spec;
+    // shadows of the bumps onto each other
+    float shadow = ParallaxSelfShadow(texture_height1, texCoords, lightDir);
+    FragColor = vec4(ambient + (1.0 - shadow) * (diffuse + specular), 1.0);
+}
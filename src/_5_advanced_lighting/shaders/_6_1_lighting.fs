@@ -0,0 +1,38 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+
+struct Light {
+    vec3 Position;
+    vec3 Color;
+};
+
+uniform Light lights[4];
+uniform sampler2D diffuseTexture;
+
+void main()
+{
+    // the texture is stored in sRGB, lighting is done in linear space
+    vec3 color = pow(texture(diffuseTexture, TexCoords).rgb, vec3(2.2));
+    vec3 normal = normalize(Normal);
+    // ambient
+    vec3 ambient = 0.0 * color;
+    // lighting
+    vec3 lighting = vec3(0.0);
+    for (int i = 0; i < 4; i++)
+    {
+        // diffuse
+        vec3 lightDir = normalize(lights[i].Position - FragPos);
+        float diff = max(dot(lightDir, normal), 0.0);
+        vec3 diffuse = lights[i].Color * diff * color;
+        vec3 result = diffuse;
+        // attenuation (use quadratic as we have gamma correction)
+        float distance = length(FragPos - lights[i].Position);
+        result *= 1.0 / (distance * distance);
+        lighting += result;
+    }
+    FragColor = vec4(ambient + lighting, 1.0);
+}
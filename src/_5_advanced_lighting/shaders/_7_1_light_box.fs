@@ -0,0 +1,12 @@
+#version 330 core
+layout (location = 0) out vec4 FragColor;
+layout (location = 1) out vec4 BrightColor;
+
+uniform vec3 lightColor;
+
+void main()
+{
+    // the lights are always bright enough to bloom
+    FragColor = vec4(lightColor, 1.0);
+    BrightColor = vec4(lightColor, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D texture1;
+
+void main()
+{
+    // unlit, blended over the deferred result
+    FragColor = texture(texture1, TexCoords);
+}
@@ -0,0 +1,131 @@
+use crate::compute::{memory_barrier, Barrier};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray};
+use crate::shader::MyShader;
+use crate::texture::Texture;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use glow::*;
+use std::mem::size_of;
+
+pub async unsafe fn main_8_1_1() {
+    let init_info = WindowInitInfo::builder()
+        .title("Compute Shader".to_string())
+        .major(4)
+        .minor(3)
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+// texture size
+const TEXTURE_WIDTH: u32 = 1000;
+const TEXTURE_HEIGHT: u32 = 1000;
+
+#[rustfmt::skip]
+const QUAD_VERTICES: [f32; 20] = [
+    // positions        // texture Coords
+    -1.0,  1.0, 0.0, 0.0, 1.0,
+    -1.0, -1.0, 0.0, 0.0, 0.0,
+    1.0,  1.0, 0.0, 1.0, 1.0,
+    1.0, -1.0, 0.0, 1.0, 0.0,
+];
+
+struct App {
+    // only kept to be dropped with the app
+    _quad_vbo: OwnedBuffer,
+    quad_vao: OwnedVertexArray,
+    texture: Texture,
+    screen_quad: MyShader,
+    compute_shader: MyShader,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+
+        log::info!(
+            "Max compute work groups: {:?}, work group size: {:?}, invocations: {}",
+            [0, 1, 2].map(|i| gl.get_parameter_indexed_i32(MAX_COMPUTE_WORK_GROUP_COUNT, i)),
+            [0, 1, 2].map(|i| gl.get_parameter_indexed_i32(MAX_COMPUTE_WORK_GROUP_SIZE, i)),
+            gl.get_parameter_i32(MAX_COMPUTE_WORK_GROUP_INVOCATIONS)
+        );
+
+        let screen_quad = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/_1_1_screen_quad.vs"),
+            include_str!("shaders/_1_1_screen_quad.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+        let compute_shader = MyShader::new_compute(
+            gl,
+            include_str!("shaders/_1_1_compute_shader.cs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        screen_quad.use_shader(gl);
+        screen_quad.set_int(gl, "tex", 0);
+
+        // Create texture for opengl operation
+        // -----------------------------------
+        let texture = Texture::new_storage(gl, TEXTURE_WIDTH, TEXTURE_HEIGHT, RGBA32F, "output")
+            .expect("Failed to create texture");
+        texture.bind_image(gl, 0, READ_WRITE, RGBA32F);
+
+        // screen quad vao
+        let quad_vbo = OwnedBuffer::create_buffer(gl, "quad vbo");
+        gl.bind_buffer(ARRAY_BUFFER, Some(quad_vbo.raw()));
+        gl.buffer_data_u8_slice(
+            ARRAY_BUFFER,
+            bytemuck::cast_slice(&QUAD_VERTICES),
+            STATIC_DRAW,
+        );
+
+        let quad_vao = OwnedVertexArray::create_vertex_array(gl, "quad vao");
+        gl.bind_vertex_array(Some(quad_vao.raw()));
+        gl.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 5 * size_of::<f32>() as i32, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(
+            1,
+            2,
+            FLOAT,
+            false,
+            5 * size_of::<f32>() as i32,
+            (3 * size_of::<f32>()) as i32,
+        );
+        gl.enable_vertex_attrib_array(1);
+        gl.bind_vertex_array(None);
+
+        Self {
+            _quad_vbo: quad_vbo,
+            quad_vao,
+            texture,
+            screen_quad,
+            compute_shader,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        self.compute_shader.use_shader(gl);
+        self.compute_shader
+            .set_float(gl, "t", ctx.elapsed_time_secs());
+        self.compute_shader
+            .dispatch_size(gl, TEXTURE_WIDTH, TEXTURE_HEIGHT, 1)
+            .expect("Failed to dispatch compute shader");
+
+        // make sure writing to image has finished before read
+        memory_barrier(gl, Barrier::SHADER_IMAGE_ACCESS | Barrier::TEXTURE_FETCH);
+
+        // render image to quad
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        self.screen_quad.use_shader(gl);
+        self.texture.bind(gl, 0);
+        gl.bind_vertex_array(Some(self.quad_vao.raw()));
+        gl.draw_arrays(TRIANGLE_STRIP, 0, 4);
+        gl.bind_vertex_array(None);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::camera::Camera;
+use crate::compute::{memory_barrier, Barrier, StorageBuffer};
+use crate::gl_object::OwnedVertexArray;
+use crate::shader::MyShader;
+use crate::window::{run, AppContext, Application, WindowInitInfo};
+use bytemuck::{Pod, Zeroable};
+use glow::*;
+use nalgebra_glm as glm;
+use rand::{Rng, SeedableRng};
+use std::mem::size_of;
+use winit_input_helper::WinitInputHelper;
+
+pub async unsafe fn main_8_1_2() {
+    let init_info = WindowInitInfo::builder()
+        .title("Compute Shader Particles".to_string())
+        .major(4)
+        .minor(3)
+        .build();
+    unsafe {
+        run::<App>(init_info).await;
+    }
+}
+
+const PARTICLE_COUNT: usize = 1 << 16;
+/// pull of the attractor
+const STRENGTH: f32 = 2.0;
+
+/// std430 layout of `Particle` in `_1_2_particles.cs`, w is unused
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Particle {
+    position: glm::Vec4,
+    velocity: glm::Vec4,
+}
+
+struct App {
+    particles: StorageBuffer<Particle>,
+    particle_vao: OwnedVertexArray,
+    compute_shader: MyShader,
+    render_shader: MyShader,
+    camera: Camera,
+}
+
+impl Application for App {
+    async unsafe fn new(ctx: &AppContext) -> Self {
+        let gl = ctx.gl();
+
+        let compute_shader = MyShader::new_compute(
+            gl,
+            include_str!("shaders/_1_2_particles.cs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+        let render_shader = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/_1_2_particles.vs"),
+            include_str!("shaders/_1_2_particles.fs"),
+            Some(ctx.suggested_shader_version()),
+        )
+        .expect("Failed to create program");
+
+        compute_shader.use_shader(gl);
+        compute_shader.set(gl, "count", &(PARTICLE_COUNT as u32));
+        compute_shader.set_float(gl, "strength", STRENGTH);
+        render_shader.use_shader(gl);
+        render_shader.set_float(gl, "brightness", 0.5);
+
+        let particles = StorageBuffer::new(gl, &generate_particles(PARTICLE_COUNT));
+
+        // the compute shader writes the particles, the vertex shader reads them as attributes
+        let particle_vao = OwnedVertexArray::create_vertex_array(gl, "particles");
+        gl.bind_vertex_array(Some(particle_vao.raw()));
+        gl.bind_buffer(ARRAY_BUFFER, Some(particles.buffer().raw()));
+        let stride = size_of::<Particle>() as i32;
+        gl.vertex_attrib_pointer_f32(0, 4, FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(1, 4, FLOAT, false, stride, size_of::<glm::Vec4>() as i32);
+        gl.enable_vertex_attrib_array(1);
+        gl.bind_vertex_array(None);
+
+        let camera = Camera::new_with_position(glm::vec3(0.0, 0.0, 6.0));
+
+        Self {
+            particles,
+            particle_vao,
+            compute_shader,
+            render_shader,
+            camera,
+        }
+    }
+
+    unsafe fn render(&mut self, ctx: &AppContext) {
+        let gl = ctx.gl();
+
+        // move the particles, the attractor circles around the origin
+        let time = ctx.elapsed_time_secs();
+        let attractor = glm::vec3(
+            (time * 0.5).sin() * 0.5,
+            (time * 0.5).cos() * 0.5,
+            (time * 0.3).sin() * 0.5,
+        );
+        self.compute_shader.use_shader(gl);
+        let delta_time = ctx.render_delta_time().min(0.05);
+        self.compute_shader.set_float(gl, "deltaTime", delta_time);
+        self.compute_shader.set_vec3(gl, "attractor", &attractor);
+        self.particles.bind(gl, 0);
+        self.compute_shader
+            .dispatch_size(gl, self.particles.len() as u32, 1, 1)
+            .expect("Failed to dispatch compute shader");
+
+        // the vertex shader reads what the compute shader wrote
+        memory_barrier(gl, Barrier::VERTEX_ATTRIB_ARRAY);
+
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        // additive blending, dense regions glow
+        gl.enable(BLEND);
+        gl.blend_func(ONE, ONE);
+
+        let projection = glm::perspective(
+            ctx.width() as f32 / ctx.height() as f32,
+            self.camera.zoom().to_radians(),
+            0.1,
+            100.0,
+        );
+        self.render_shader.use_shader(gl);
+        self.render_shader.set_mat4(gl, "projection", &projection);
+        let view = self.camera.view_matrix();
+        self.render_shader.set_mat4(gl, "view", &view);
+        gl.bind_vertex_array(Some(self.particle_vao.raw()));
+        gl.draw_arrays(POINTS, 0, self.particles.len() as i32);
+        gl.bind_vertex_array(None);
+
+        gl.disable(BLEND);
+    }
+
+    unsafe fn process_input(&mut self, _ctx: &AppContext, input: &WinitInputHelper) {
+        self.camera.process_keyboard_with_input(input);
+        self.camera.process_mouse_with_input(input, true);
+    }
+}
+
+/// a disc of particles orbiting the origin
+fn generate_particles(count: usize) -> Vec<Particle> {
+    // fixed seed, so every run (and golden image) starts the same
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    (0..count)
+        .map(|_| {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let radius = rng.gen_range(0.5..2.5_f32);
+            let height = rng.gen_range(-0.1..0.1);
+            let position = glm::vec3(angle.cos() * radius, angle.sin() * radius, height);
+            // about the speed of a circular orbit, the pull falls off with 1 / distance
+            let velocity = glm::vec3(-angle.sin(), angle.cos(), 0.0) * STRENGTH.sqrt();
+            Particle {
+                position: glm::vec4(position.x, position.y, position.z, 1.0),
+                velocity: glm::vec4(velocity.x, velocity.y, velocity.z, 0.0),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,5 @@
+mod _1_1_compute_shader;
+pub use _1_1_compute_shader::main_8_1_1;
+
+mod _1_2_compute_particles;
+pub use _1_2_compute_particles::main_8_1_2;
@@ -0,0 +1,12 @@
+#version 430 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D tex;
+
+void main()
+{
+    vec3 texCol = texture(tex, TexCoords).rgb;
+    FragColor = vec4(texCol, 1.0);
+}
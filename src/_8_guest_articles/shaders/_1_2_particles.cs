@@ -0,0 +1,35 @@
+#version 430 core
+
+layout (local_size_x = 256) in;
+
+struct Particle
+{
+    vec4 position;
+    vec4 velocity;
+};
+
+layout (std430, binding = 0) buffer Particles
+{
+    Particle particles[];
+};
+
+uniform uint count;
+uniform float deltaTime;
+uniform vec3 attractor;
+uniform float strength;
+
+void main()
+{
+    uint i = gl_GlobalInvocationID.x;
+    if (i >= count)
+        return;
+
+    Particle p = particles[i];
+    vec3 toAttractor = attractor - p.position.xyz;
+    // softened so particles passing right through the attractor don't explode
+    float distanceSquared = dot(toAttractor, toAttractor) + 0.1;
+    vec3 acceleration = strength * toAttractor / distanceSquared;
+    p.velocity.xyz = (p.velocity.xyz + acceleration * deltaTime) * 0.998;
+    p.position.xyz += p.velocity.xyz * deltaTime;
+    particles[i] = p;
+}
@@ -0,0 +1,11 @@
+#version 430 core
+out vec4 FragColor;
+
+in vec3 Color;
+
+uniform float brightness;
+
+void main()
+{
+    FragColor = vec4(Color * brightness, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 430 core
+layout (location = 0) in vec4 aPos;
+layout (location = 1) in vec4 aVelocity;
+
+out vec3 Color;
+
+uniform mat4 projection;
+uniform mat4 view;
+
+void main()
+{
+    float speed = length(aVelocity.xyz);
+    Color = mix(vec3(0.1, 0.3, 1.0), vec3(1.0, 0.5, 0.1), clamp(speed / 4.0, 0.0, 1.0));
+    gl_Position = projection * view * vec4(aPos.xyz, 1.0);
+}
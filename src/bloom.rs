@@ -0,0 +1,344 @@
+use crate::framebuffer::{
+    hdr_color_format, Framebuffer, FramebufferDesc, FramebufferError, FramebufferSize,
+};
+use crate::gl_object::SharedGl;
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// levels of the mip chain, the smallest is 1/64 of the window
+const MIP_LEVELS: usize = 6;
+
+/// How the bright parts are spread
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BloomMethod {
+    /// separable Gaussian blur, horizontal and vertical passes in turn at full size, as in
+    /// the learnopengl bloom chapter
+    #[default]
+    Gaussian,
+    /// downsampled through a chain of half sized targets and upsampled back with a tent
+    /// filter, as in the physically based bloom guest article
+    MipChain,
+}
+
+impl BloomMethod {
+    pub const ALL: [BloomMethod; 2] = [BloomMethod::Gaussian, BloomMethod::MipChain];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&m| m == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// the Gaussian blur is added to the scene, the mip chain mixed in, which needs far less
+    pub fn default_intensity(self) -> f32 {
+        match self {
+            BloomMethod::Gaussian => 1.0,
+            BloomMethod::MipChain => 0.04,
+        }
+    }
+}
+
+impl fmt::Display for BloomMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomMethod::Gaussian => write!(f, "Gaussian"),
+            BloomMethod::MipChain => write!(f, "Mip chain"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct BloomSettings {
+    #[builder(default = true)]
+    pub enabled: bool,
+    #[builder(default)]
+    pub method: BloomMethod,
+    /// luminance from which colors bloom, for the threshold pass and scene shaders writing
+    /// the bright parts themselves
+    #[builder(default = 1.0)]
+    pub threshold: f32,
+    /// how much of the bloom is added to the scene. The mip chain blends it in, so it wants
+    /// small values like 0.04. Reset by `set_method`
+    #[builder(default = method.default_intensity())]
+    pub intensity: f32,
+    /// horizontal and vertical passes of the Gaussian blur, each widens it
+    #[builder(default = 5)]
+    pub blur_passes: u32,
+    /// radius of the tent filter of the mip chain, in texture coordinates
+    #[builder(default = 0.005)]
+    pub filter_radius: f32,
+}
+
+impl BloomSettings {
+    /// switches the method, with its default intensity
+    pub fn set_method(&mut self, method: BloomMethod) {
+        if method != self.method {
+            self.method = method;
+            self.intensity = method.default_intensity();
+        }
+    }
+}
+
+/// Adds a glow around the bright parts of an HDR scene, before it is tone mapped
+///
+/// The bright parts are either extracted by a threshold pass or come from a second color
+/// attachment the scene shaders wrote them to (MRT), then get blurred with one of the
+/// `BloomMethod`s and added to the scene. Targets are float where supported, see
+/// `framebuffer::hdr_color_format`.
+pub struct Bloom {
+    pub settings: BloomSettings,
+    bright: Framebuffer,
+    ping_pong: [Framebuffer; 2],
+    /// half the window size, then halved each level
+    mips: Vec<Framebuffer>,
+    threshold_shader: MyShader,
+    blur_shader: MyShader,
+    downsample_shader: MyShader,
+    upsample_shader: MyShader,
+    composite_shader: MyShader,
+    quad: FullscreenQuad,
+}
+
+impl Bloom {
+    pub fn new(
+        gl: &SharedGl,
+        settings: BloomSettings,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        let shader = |name: &str, fragment_shader: &str| {
+            fullscreen_shader(gl, name, fragment_shader, shader_version)
+        };
+        let threshold_shader = shader(
+            "bloom threshold",
+            include_str!("shaders/post/bloom_threshold.fs"),
+        )?;
+        let blur_shader = shader("bloom blur", include_str!("shaders/post/bloom_blur.fs"))?;
+        let downsample_shader = shader(
+            "bloom downsample",
+            include_str!("shaders/post/bloom_downsample.fs"),
+        )?;
+        let upsample_shader = shader(
+            "bloom upsample",
+            include_str!("shaders/post/bloom_upsample.fs"),
+        )?;
+        let composite_shader = shader("bloom composite", include_str!("shaders/post/copy.fs"))?;
+
+        let format = hdr_color_format(gl);
+        let target = |label: String, scale: f32| {
+            let desc = FramebufferDesc::builder()
+                .colors(vec![format])
+                .size(FramebufferSize::Window(scale))
+                .label(label)
+                .build();
+            Framebuffer::new(gl, desc, window_width, window_height)
+        };
+        let bright = target("bloom bright".to_string(), 1.0)?;
+        let ping_pong = [
+            target("bloom ping".to_string(), 1.0)?,
+            target("bloom pong".to_string(), 1.0)?,
+        ];
+        let mips = (0..MIP_LEVELS)
+            .map(|i| target(format!("bloom mip {}", i), 0.5_f32.powi(i as i32 + 1)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            settings,
+            bright,
+            ping_pong,
+            mips,
+            threshold_shader,
+            blur_shader,
+            downsample_shader,
+            upsample_shader,
+            composite_shader,
+            quad: FullscreenQuad::new(gl),
+        })
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        self.bright.resize(gl, width, height)?;
+        for target in self.ping_pong.iter_mut().chain(&mut self.mips) {
+            target.resize(gl, width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Blooms the first color attachment of `scene` and adds the result to it. `bright` are
+    /// the bright parts written by the scene shaders, otherwise the threshold pass extracts
+    /// them. Depth testing is left disabled and `scene` bound.
+    pub fn apply(&self, gl: &Context, scene: &Framebuffer, bright: Option<glow::Texture>) {
+        if !self.settings.enabled {
+            return;
+        }
+        unsafe {
+            gl.disable(glow::DEPTH_TEST);
+            gl.active_texture(glow::TEXTURE0);
+
+            let bright = match bright {
+                Some(bright) => bright,
+                None => {
+                    self.bright.bind(gl);
+                    self.threshold_shader.use_shader(gl);
+                    self.threshold_shader.set_int(gl, "hdrBuffer", 0);
+                    self.threshold_shader
+                        .set_float(gl, "threshold", self.settings.threshold);
+                    scene.bind_color(gl, 0, 0);
+                    self.quad.draw(gl);
+                    self.bright.color_texture(0).unwrap()
+                }
+            };
+
+            let bloom = match self.settings.method {
+                BloomMethod::Gaussian => self.gaussian_blur(gl, bright),
+                BloomMethod::MipChain => self.mip_chain_blur(gl, bright),
+            };
+
+            // add it to the scene: scene + bloom * intensity, or blend between them for the
+            // mip chain, which keeps the energy of the scene. The composite writes one color, the
+            // other attachments of a scene rendered with MRT are left alone
+            scene.bind(gl);
+            scene.set_draw_colors(gl, Some(1));
+            gl.enable(glow::BLEND);
+            gl.blend_color(0.0, 0.0, 0.0, self.settings.intensity);
+            match self.settings.method {
+                BloomMethod::Gaussian => gl.blend_func(glow::CONSTANT_ALPHA, glow::ONE),
+                BloomMethod::MipChain => {
+                    gl.blend_func(glow::CONSTANT_ALPHA, glow::ONE_MINUS_CONSTANT_ALPHA)
+                }
+            }
+            self.composite_shader.use_shader(gl);
+            gl.bind_texture(glow::TEXTURE_2D, Some(bloom));
+            self.quad.draw(gl);
+            gl.disable(glow::BLEND);
+            gl.blend_func(glow::ONE, glow::ZERO);
+            scene.set_draw_colors(gl, None);
+        }
+    }
+
+    /// blurs horizontally and vertically in turn, returns the result
+    unsafe fn gaussian_blur(&self, gl: &Context, bright: glow::Texture) -> glow::Texture {
+        self.blur_shader.use_shader(gl);
+        self.blur_shader.set_int(gl, "image", 0);
+        let mut source = bright;
+        for i in 0..self.settings.blur_passes.max(1) * 2 {
+            let horizontal = i % 2 == 0;
+            let target = &self.ping_pong[i as usize % 2];
+            target.bind(gl);
+            self.blur_shader.set_bool(gl, "horizontal", horizontal);
+            gl.bind_texture(glow::TEXTURE_2D, Some(source));
+            self.quad.draw(gl);
+            source = target.color_texture(0).unwrap();
+        }
+        source
+    }
+
+    /// downsamples through the mips and adds each level to the next larger one on the way
+    /// back up, returns the largest
+    unsafe fn mip_chain_blur(&self, gl: &Context, bright: glow::Texture) -> glow::Texture {
+        self.downsample_shader.use_shader(gl);
+        self.downsample_shader.set_int(gl, "srcTexture", 0);
+        let mut source = bright;
+        for (i, mip) in self.mips.iter().enumerate() {
+            mip.bind(gl);
+            self.downsample_shader.set_bool(gl, "firstLevel", i == 0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(source));
+            self.quad.draw(gl);
+            source = mip.color_texture(0).unwrap();
+        }
+
+        self.upsample_shader.use_shader(gl);
+        self.upsample_shader.set_int(gl, "srcTexture", 0);
+        self.upsample_shader
+            .set_float(gl, "filterRadius", self.settings.filter_radius);
+        gl.enable(glow::BLEND);
+        gl.blend_func(glow::ONE, glow::ONE);
+        for pair in self.mips.windows(2).rev() {
+            let (larger, smaller) = (&pair[0], &pair[1]);
+            larger.bind(gl);
+            gl.bind_texture(glow::TEXTURE_2D, smaller.color_texture(0));
+            self.quad.draw(gl);
+        }
+        gl.disable(glow::BLEND);
+        self.mips[0].color_texture(0).unwrap()
+    }
+
+    /// Method, threshold, intensity and radius
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.checkbox(&mut settings.enabled, "Bloom");
+        egui::ComboBox::from_label("Method")
+            .selected_text(settings.method.to_string())
+            .show_ui(ui, |ui| {
+                for method in BloomMethod::ALL {
+                    if ui
+                        .selectable_label(settings.method == method, method.to_string())
+                        .clicked()
+                    {
+                        settings.set_method(method);
+                    }
+                }
+            });
+        ui.add(egui::Slider::new(&mut settings.threshold, 0.0..=5.0).text("Threshold"));
+        ui.add(
+            egui::Slider::new(&mut settings.intensity, 0.0..=2.0)
+                .logarithmic(true)
+                .text("Intensity"),
+        );
+        match settings.method {
+            BloomMethod::Gaussian => {
+                ui.add(egui::Slider::new(&mut settings.blur_passes, 1..=20).text("Blur passes"))
+            }
+            BloomMethod::MipChain => ui.add(
+                egui::Slider::new(&mut settings.filter_radius, 0.0..=0.02).text("Filter radius"),
+            ),
+        };
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let settings = &mut self.settings;
+        ui.checkbox_config("Bloom", &mut settings.enabled).build();
+        for method in BloomMethod::ALL {
+            if ui
+                .radio_button_config(method.to_string(), settings.method == method)
+                .build()
+            {
+                settings.set_method(method);
+            }
+        }
+        ui.slider_float_config("Threshold", &mut settings.threshold)
+            .range(0.0, 5.0)
+            .build();
+        ui.slider_float_config("Intensity", &mut settings.intensity)
+            .range(0.0, 2.0)
+            .build();
+        match settings.method {
+            BloomMethod::Gaussian => {
+                let mut passes = settings.blur_passes as i32;
+                if ui
+                    .slider_int_config("Blur passes", &mut passes)
+                    .range(1, 20)
+                    .build()
+                {
+                    settings.blur_passes = passes as u32;
+                }
+            }
+            BloomMethod::MipChain => {
+                ui.slider_float_config("Filter radius", &mut settings.filter_radius)
+                    .range(0.0, 0.02)
+                    .build();
+            }
+        }
+    }
+}
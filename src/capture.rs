@@ -0,0 +1,240 @@
+use glow::{Context, HasContext, PixelPackData};
+use std::path::PathBuf;
+use typed_builder::TypedBuilder;
+
+/// where captured images are written, relative to the working directory
+pub const CAPTURE_DIR: &str = "screenshots";
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CaptureFormat {
+    /// 8 bits per channel, values outside [0, 1] are clamped
+    #[default]
+    Png,
+    /// OpenEXR, 32 bit float per channel, for float (HDR) targets
+    Exr,
+    /// Radiance RGBE, float values in a smaller file. No tutorial picks it yet
+    #[allow(dead_code)]
+    Hdr,
+}
+
+impl CaptureFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Exr => "exr",
+            CaptureFormat::Hdr => "hdr",
+        }
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        !matches!(self, CaptureFormat::Png)
+    }
+}
+
+/// which buffer of a framebuffer to read
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Attachment {
+    /// `COLOR_ATTACHMENT0 + n`
+    Color(u32),
+    /// the depth buffer, written as grayscale. Not readable on WebGL2
+    Depth,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CaptureSource {
+    /// what the tutorial rendered to the default framebuffer, without the egui/imgui overlay
+    #[default]
+    Screen,
+    Framebuffer {
+        framebuffer: glow::Framebuffer,
+        attachment: Attachment,
+        width: u32,
+        height: u32,
+    },
+    /// a 2D texture, e.g. a shadow map or the color buffer of an offscreen pass.
+    /// Use `Attachment::Depth` for depth textures
+    Texture {
+        texture: glow::Texture,
+        attachment: Attachment,
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(TypedBuilder, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CaptureRequest {
+    #[builder(default)]
+    pub source: CaptureSource,
+    #[builder(default)]
+    pub format: CaptureFormat,
+    /// file name prefix, a timestamp and the extension are appended
+    #[builder(default = "screenshot".to_string(), setter(into))]
+    pub name: String,
+}
+
+/// Reads back the color of the framebuffer bound to `READ_FRAMEBUFFER`. GL rows start at the
+/// bottom, so they are flipped to get the usual top-down image.
+pub unsafe fn read_rgb8(gl: &Context, x: i32, y: i32, width: u32, height: u32) -> image::RgbImage {
+    // RGBA/UNSIGNED_BYTE is the one combination every implementation (including WebGL2) must accept
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+    gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+    gl.read_pixels(
+        x,
+        y,
+        width as i32,
+        height as i32,
+        glow::RGBA,
+        glow::UNSIGNED_BYTE,
+        PixelPackData::Slice(&mut data),
+    );
+    gl.pixel_store_i32(glow::PACK_ALIGNMENT, 4);
+
+    let rgba = image::RgbaImage::from_raw(width, height, data).unwrap();
+    // alpha of the default framebuffer is never shown, drop it
+    image::DynamicImage::ImageRgba8(image::imageops::flip_vertical(&rgba)).to_rgb8()
+}
+
+/// Same as `read_rgb8`, but keeps values outside [0, 1] of float color buffers
+pub unsafe fn read_rgb32f(
+    gl: &Context,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> image::Rgb32FImage {
+    let mut data = vec![0f32; width as usize * height as usize * 4];
+    gl.read_pixels(
+        x,
+        y,
+        width as i32,
+        height as i32,
+        glow::RGBA,
+        glow::FLOAT,
+        PixelPackData::Slice(bytemuck::cast_slice_mut(&mut data)),
+    );
+
+    let rgba = image::Rgba32FImage::from_raw(width, height, data).unwrap();
+    image::DynamicImage::ImageRgba32F(image::imageops::flip_vertical(&rgba)).to_rgb32f()
+}
+
+/// Reads back the depth buffer of the framebuffer bound to `READ_FRAMEBUFFER` as grayscale
+pub unsafe fn read_depth(
+    gl: &Context,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> image::Rgb32FImage {
+    let mut data = vec![0f32; width as usize * height as usize];
+    gl.read_pixels(
+        x,
+        y,
+        width as i32,
+        height as i32,
+        glow::DEPTH_COMPONENT,
+        glow::FLOAT,
+        PixelPackData::Slice(bytemuck::cast_slice_mut(&mut data)),
+    );
+
+    let rgb = data.iter().flat_map(|d| [*d, *d, *d]).collect::<Vec<_>>();
+    let image = image::Rgb32FImage::from_raw(width, height, rgb).unwrap();
+    image::imageops::flip_vertical(&image)
+}
+
+/// Reads `attachment` of `framebuffer` (`None` for the default one) as 8 bit or float image
+pub unsafe fn read_framebuffer(
+    gl: &Context,
+    framebuffer: Option<glow::Framebuffer>,
+    attachment: Attachment,
+    width: u32,
+    height: u32,
+    hdr: bool,
+) -> image::DynamicImage {
+    gl.bind_framebuffer(glow::READ_FRAMEBUFFER, framebuffer);
+    let image = match attachment {
+        Attachment::Depth => image::DynamicImage::ImageRgb32F(read_depth(gl, 0, 0, width, height)),
+        Attachment::Color(n) => {
+            // the default framebuffer has no color attachments, it reads from the back buffer
+            let other_attachment = framebuffer.is_some() && n != 0;
+            if other_attachment {
+                gl.read_buffer(glow::COLOR_ATTACHMENT0 + n);
+            }
+            let image = if hdr {
+                image::DynamicImage::ImageRgb32F(read_rgb32f(gl, 0, 0, width, height))
+            } else {
+                image::DynamicImage::ImageRgb8(read_rgb8(gl, 0, 0, width, height))
+            };
+            if other_attachment {
+                gl.read_buffer(glow::COLOR_ATTACHMENT0);
+            }
+            image
+        }
+    };
+    gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+    image
+}
+
+/// Reads a 2D texture by attaching it to a temporary framebuffer
+pub unsafe fn read_texture(
+    gl: &Context,
+    texture: glow::Texture,
+    attachment: Attachment,
+    width: u32,
+    height: u32,
+    hdr: bool,
+) -> anyhow::Result<image::DynamicImage> {
+    let fbo = gl.create_framebuffer().map_err(|e| anyhow::anyhow!(e))?;
+    gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
+    let (gl_attachment, read_attachment) = match attachment {
+        Attachment::Depth => (glow::DEPTH_ATTACHMENT, attachment),
+        Attachment::Color(_) => (glow::COLOR_ATTACHMENT0, Attachment::Color(0)),
+    };
+    gl.framebuffer_texture_2d(
+        glow::READ_FRAMEBUFFER,
+        gl_attachment,
+        glow::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    if matches!(attachment, Attachment::Depth) {
+        // a framebuffer with only a depth attachment is incomplete with the default read buffer
+        gl.read_buffer(glow::NONE);
+    }
+
+    let status = gl.check_framebuffer_status(glow::READ_FRAMEBUFFER);
+    let result = if status == glow::FRAMEBUFFER_COMPLETE {
+        Ok(read_framebuffer(
+            gl,
+            Some(fbo),
+            read_attachment,
+            width,
+            height,
+            hdr,
+        ))
+    } else {
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+        Err(anyhow::anyhow!(
+            "Texture cannot be read back, framebuffer status {:#x}",
+            status
+        ))
+    };
+    gl.delete_framebuffer(fbo);
+    result
+}
+
+/// Writes `image` to `CAPTURE_DIR` as `<name>_<timestamp>.<ext>` and returns the path
+pub fn save(
+    image: &image::DynamicImage,
+    name: &str,
+    format: CaptureFormat,
+) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(CAPTURE_DIR)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S_%3f");
+    let path =
+        PathBuf::from(CAPTURE_DIR).join(format!("{}_{}.{}", name, timestamp, format.extension()));
+    match format {
+        CaptureFormat::Png => image.to_rgb8().save(&path)?,
+        CaptureFormat::Exr | CaptureFormat::Hdr => image.to_rgb32f().save(&path)?,
+    }
+    Ok(path)
+}
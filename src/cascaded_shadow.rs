@@ -0,0 +1,410 @@
+use crate::camera::Camera;
+use crate::framebuffer::{FramebufferError, FramebufferStatus};
+use crate::gl_object::{OwnedFramebuffer, OwnedTexture, SharedGl};
+use crate::post_process::PostProcessError;
+use crate::shader::MyShader;
+use crate::shader_error::ShaderError;
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use typed_builder::TypedBuilder;
+
+/// `MAX_CASCADES` in `common/cascaded_shadows.glsl`
+pub const MAX_CASCADES: usize = 4;
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CascadeSettings {
+    #[builder(default = 4)]
+    pub cascade_count: usize,
+    /// width and height of each cascade
+    #[builder(default = 2048)]
+    pub resolution: u32,
+    /// blend between logarithmic (1) and uniform (0) splits, the practical split scheme
+    #[builder(default = 0.75)]
+    pub lambda: f32,
+    /// shadows end at this view distance, or the far plane if it is closer
+    #[builder(default = 500.0)]
+    pub max_distance: f32,
+    /// how far behind each cascade casters are still rendered, along the light direction
+    #[builder(default = 100.0)]
+    pub caster_distance: f32,
+    /// fraction of each cascade over which it is blended into the next one
+    #[builder(default = 0.1)]
+    pub blend: f32,
+    /// fit every cascade into a sphere and snap it to whole texels, so shadow edges do not
+    /// shimmer when the camera moves or turns. Otherwise the cascades fit the frustum tightly.
+    #[builder(default = true)]
+    pub stabilize: bool,
+    /// along the normal, in texels of the cascade
+    #[builder(default = 1.0)]
+    pub normal_offset: f32,
+    /// in shadow map depth, see `common/cascaded_shadows.glsl`
+    #[builder(default = 0.002)]
+    pub bias: f32,
+}
+
+/// One cascade, fitted to a slice of the view frustum
+#[derive(Debug, Copy, Clone)]
+pub struct Cascade {
+    /// far end of the slice, as view space depth
+    pub split: f32,
+    /// projection and view of the light
+    pub matrix: glm::Mat4,
+    /// world space size of a texel
+    pub texel_size: f32,
+}
+
+/// Shadows of a directional light over a large view distance
+///
+/// The view frustum is split into slices, shorter close to the camera, each with its own
+/// orthographic shadow map in a layer of a depth texture array. Shaders pick the layer by view
+/// depth through `common/cascaded_shadows.glsl`, whose uniforms `set_uniforms` sets.
+pub struct CascadedShadowMap {
+    pub settings: CascadeSettings,
+    /// (cascade count, resolution) of the texture array
+    allocated: (usize, u32),
+    cascades: Vec<Cascade>,
+    depth_array: OwnedTexture,
+    fbo: OwnedFramebuffer,
+    depth_shader: MyShader,
+    instanced_depth_shader: MyShader,
+}
+
+impl CascadedShadowMap {
+    pub fn new(
+        gl: &SharedGl,
+        settings: CascadeSettings,
+        shader_version: Option<&str>,
+    ) -> Result<Self, PostProcessError> {
+        let depth_shader = |name: &str, defines: &[(&str, &str)]| {
+            let mut shader = MyShader::new_from_source_with_defines(
+                gl,
+                include_str!("shaders/shadow/cascade_depth.vs"),
+                include_str!("shaders/shadow/cascade_depth.fs"),
+                shader_version,
+                defines,
+            )?;
+            shader.set_name(name);
+            Ok::<_, ShaderError>(shader)
+        };
+        let label = "cascaded shadow map";
+        let mut shadow_map = Self {
+            allocated: (0, 0),
+            settings,
+            cascades: Vec::new(),
+            depth_array: OwnedTexture::create_texture(gl, label),
+            fbo: OwnedFramebuffer::create_framebuffer(gl, label),
+            depth_shader: depth_shader("cascade depth", &[])?,
+            instanced_depth_shader: depth_shader("cascade depth instanced", &[("INSTANCED", "1")])?,
+        };
+        shadow_map.allocate(gl);
+
+        // only a depth attachment
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(shadow_map.fbo.raw()));
+            shadow_map.attach_layer(gl, 0);
+            gl.draw_buffers(&[glow::NONE]);
+            gl.read_buffer(glow::NONE);
+            let status = FramebufferStatus::from_gl(gl.check_framebuffer_status(glow::FRAMEBUFFER));
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if let Some(status) = status {
+                return Err(FramebufferError::Incomplete {
+                    label: label.to_string(),
+                    status,
+                }
+                .into());
+            }
+        }
+        Ok(shadow_map)
+    }
+
+    fn cascade_count(&self) -> usize {
+        self.settings.cascade_count.clamp(1, MAX_CASCADES)
+    }
+
+    /// (Re)specifies a layer per cascade at `settings.resolution`
+    fn allocate(&mut self, gl: &Context) {
+        let layers = self.cascade_count();
+        let size = self.settings.resolution.max(1) as i32;
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(self.depth_array.raw()));
+            gl.tex_image_3d(
+                glow::TEXTURE_2D_ARRAY,
+                0,
+                glow::DEPTH_COMPONENT32F as i32,
+                size,
+                size,
+                layers as i32,
+                0,
+                glow::DEPTH_COMPONENT,
+                glow::FLOAT,
+                None,
+            );
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::NEAREST),
+                (glow::TEXTURE_MAG_FILTER, glow::NEAREST),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D_ARRAY, parameter, value as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, None);
+        }
+        self.allocated = (layers, self.settings.resolution);
+    }
+
+    unsafe fn attach_layer(&self, gl: &Context, layer: usize) {
+        gl.framebuffer_texture_layer(
+            glow::FRAMEBUFFER,
+            glow::DEPTH_ATTACHMENT,
+            Some(self.depth_array.raw()),
+            0,
+            layer as i32,
+        );
+    }
+
+    /// View depths splitting `near` to `far` into `count` slices with the practical split
+    /// scheme: `lambda` blends between logarithmic and uniform splits. Returns the far end of
+    /// each slice.
+    pub fn split_distances(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+        (1..=count)
+            .map(|i| {
+                let p = i as f32 / count as f32;
+                let log = near * (far / near).powf(p);
+                let uniform = near + (far - near) * p;
+                lambda * log + (1.0 - lambda) * uniform
+            })
+            .collect()
+    }
+
+    /// Fits the cascades to the view frustum of `camera`, with an aspect ratio of `aspect` and
+    /// clip planes `near` and `far`, for a light shining along `light_dir`
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        aspect: f32,
+        near: f32,
+        far: f32,
+        light_dir: &glm::Vec3,
+    ) {
+        let far = far.min(self.settings.max_distance).max(near * 2.0);
+        let splits = Self::split_distances(near, far, self.cascade_count(), self.settings.lambda);
+        let view = camera.view_matrix();
+        let fovy = camera.zoom().to_radians();
+        let light_dir = glm::normalize(light_dir);
+        let mut slice_near = near;
+        self.cascades = splits
+            .into_iter()
+            .map(|split| {
+                let projection = glm::perspective(aspect, fovy, slice_near, split);
+                let corners = frustum_corners(&(projection * view));
+                slice_near = split;
+                self.fit(&corners, &light_dir, split)
+            })
+            .collect();
+    }
+
+    /// an orthographic light matrix around the world space `corners` of a frustum slice
+    fn fit(&self, corners: &[glm::Vec3; 8], light_dir: &glm::Vec3, split: f32) -> Cascade {
+        let resolution = self.settings.resolution.max(1) as f32;
+        let center = corners.iter().sum::<glm::Vec3>() / 8.0;
+        let mut radius = corners
+            .iter()
+            .map(|corner| glm::distance(corner, &center))
+            .fold(0.0, f32::max);
+        // round up so the size only changes in steps, as the radius wobbles with rounding
+        // errors while the camera turns
+        radius = (radius * 16.0).ceil() / 16.0;
+        let up = if light_dir.y.abs() > 0.99 {
+            glm::vec3(0.0, 0.0, 1.0)
+        } else {
+            glm::vec3(0.0, 1.0, 0.0)
+        };
+        let eye_distance = radius + self.settings.caster_distance;
+        let light_view = glm::look_at(&(center - light_dir * eye_distance), &center, &up);
+        let depth = eye_distance + radius;
+
+        let (mut projection, extent) = if self.settings.stabilize {
+            let projection = glm::ortho(-radius, radius, -radius, radius, 0.0, depth);
+            (projection, 2.0 * radius)
+        } else {
+            // the light space bounds of the corners
+            let (mut min, mut max) = (glm::vec2(f32::MAX, f32::MAX), glm::vec2(f32::MIN, f32::MIN));
+            for corner in corners {
+                let p = (light_view * glm::vec4(corner.x, corner.y, corner.z, 1.0)).xy();
+                min = glm::min2(&min, &p);
+                max = glm::max2(&max, &p);
+            }
+            let projection = glm::ortho(min.x, max.x, min.y, max.y, 0.0, depth);
+            (projection, (max.x - min.x).max(max.y - min.y))
+        };
+
+        if self.settings.stabilize {
+            // move the projection by less than a texel so the world origin lands on a texel
+            // corner, then the texels stay put while the camera moves
+            let origin = projection * light_view * glm::vec4(0.0, 0.0, 0.0, 1.0);
+            let origin = origin.xy() * resolution / 2.0;
+            let offset = (glm::round(&origin) - origin) * 2.0 / resolution;
+            projection[(0, 3)] += offset.x;
+            projection[(1, 3)] += offset.y;
+        }
+
+        Cascade {
+            split,
+            matrix: projection * light_view,
+            texel_size: extent / resolution,
+        }
+    }
+
+    /// Renders the depths of every cascade fitted by `update`. `draw_scene` draws the casters
+    /// once per cascade with one of the two depth shaders in use, setting its `model` matrix:
+    /// the first for plain meshes, the second takes per instance model matrices at attribute
+    /// locations 3 to 6 like `4_10_3`. Both already have the matrix of the cascade. The default
+    /// framebuffer is bound afterwards, with the viewport still at the cascade size.
+    pub fn render(&mut self, gl: &Context, mut draw_scene: impl FnMut(&MyShader, &MyShader)) {
+        if self.allocated != (self.cascade_count(), self.settings.resolution) {
+            self.allocate(gl);
+        }
+        let size = self.settings.resolution.max(1) as i32;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.fbo.raw()));
+            gl.viewport(0, 0, size, size);
+            gl.enable(glow::DEPTH_TEST);
+            for (layer, cascade) in self.cascades.iter().enumerate() {
+                self.attach_layer(gl, layer);
+                gl.clear(glow::DEPTH_BUFFER_BIT);
+                for shader in [&self.instanced_depth_shader, &self.depth_shader] {
+                    shader.use_shader(gl);
+                    shader.set_mat4(gl, "lightSpaceMatrix", &cascade.matrix);
+                }
+                draw_scene(&self.depth_shader, &self.instanced_depth_shader);
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Binds the texture array to `unit` and sets the uniforms of
+    /// `common/cascaded_shadows.glsl` in `shader`, which has to be in use. Texture unit `unit`
+    /// is left active.
+    pub fn set_uniforms(&self, gl: &Context, shader: &MyShader, unit: u32) {
+        let mut matrices = [glm::Mat4::identity(); MAX_CASCADES];
+        let mut splits = [0.0; MAX_CASCADES];
+        let mut texel_sizes = [0.0; MAX_CASCADES];
+        for (i, cascade) in self.cascades.iter().enumerate() {
+            matrices[i] = cascade.matrix;
+            splits[i] = cascade.split;
+            texel_sizes[i] = cascade.texel_size;
+        }
+        unsafe {
+            gl.active_texture(glow::TEXTURE0 + unit);
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(self.depth_array.raw()));
+        }
+        shader.set_int(gl, "cascadeShadowMap", unit as i32);
+        shader.set(gl, "cascadeMatrices", &matrices);
+        shader.set(gl, "cascadeSplits", &splits);
+        shader.set(gl, "cascadeTexelSizes", &texel_sizes);
+        shader.set_int(gl, "cascadeCount", self.cascades.len() as i32);
+        shader.set_float(gl, "cascadeBlend", self.settings.blend);
+        shader.set_float(gl, "cascadeNormalOffset", self.settings.normal_offset);
+        shader.set_float(gl, "cascadeBias", self.settings.bias);
+    }
+
+    /// Cascades, split scheme, stabilization, blending and biases
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.add(egui::Slider::new(&mut settings.cascade_count, 1..=MAX_CASCADES).text("Cascades"));
+        ui.add(
+            egui::Slider::new(&mut settings.resolution, 256..=4096)
+                .logarithmic(true)
+                .text("Resolution"),
+        );
+        ui.add(egui::Slider::new(&mut settings.lambda, 0.0..=1.0).text("Split lambda"));
+        ui.add(
+            egui::Slider::new(&mut settings.max_distance, 10.0..=1000.0)
+                .logarithmic(true)
+                .text("Max distance"),
+        );
+        ui.add(
+            egui::Slider::new(&mut settings.caster_distance, 0.0..=500.0).text("Caster distance"),
+        );
+        ui.add(egui::Slider::new(&mut settings.blend, 0.0..=0.5).text("Blend"));
+        ui.checkbox(&mut settings.stabilize, "Stabilize");
+        ui.add(egui::Slider::new(&mut settings.normal_offset, 0.0..=4.0).text("Normal offset"));
+        ui.add(
+            egui::Slider::new(&mut settings.bias, 0.0..=0.01)
+                .logarithmic(true)
+                .text("Bias"),
+        );
+        for (i, cascade) in self.cascades.iter().enumerate() {
+            ui.label(format!(
+                "Cascade {}: to {:.1}, texel {:.3}",
+                i, cascade.split, cascade.texel_size
+            ));
+        }
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let settings = &mut self.settings;
+        let mut cascade_count = settings.cascade_count as i32;
+        if ui
+            .slider_int_config("Cascades", &mut cascade_count)
+            .range(1, MAX_CASCADES as i32)
+            .build()
+        {
+            settings.cascade_count = cascade_count as usize;
+        }
+        let mut resolution = settings.resolution as i32;
+        if ui
+            .slider_int_config("Resolution", &mut resolution)
+            .range(256, 4096)
+            .build()
+        {
+            settings.resolution = resolution as u32;
+        }
+        ui.slider_float_config("Split lambda", &mut settings.lambda)
+            .range(0.0, 1.0)
+            .build();
+        ui.slider_float_config("Max distance", &mut settings.max_distance)
+            .range(10.0, 1000.0)
+            .build();
+        ui.slider_float_config("Caster distance", &mut settings.caster_distance)
+            .range(0.0, 500.0)
+            .build();
+        ui.slider_float_config("Blend", &mut settings.blend)
+            .range(0.0, 0.5)
+            .build();
+        ui.checkbox_config("Stabilize", &mut settings.stabilize)
+            .build();
+        ui.slider_float_config("Normal offset", &mut settings.normal_offset)
+            .range(0.0, 4.0)
+            .build();
+        ui.slider_float_config("Bias", &mut settings.bias)
+            .range(0.0, 0.01)
+            .build();
+        for (i, cascade) in self.cascades.iter().enumerate() {
+            ui.text(&format!(
+                "Cascade {}: to {:.1}, texel {:.3}",
+                i, cascade.split, cascade.texel_size
+            ));
+        }
+    }
+}
+
+/// the world space corners of the frustum whose projection times view is `view_projection`
+fn frustum_corners(view_projection: &glm::Mat4) -> [glm::Vec3; 8] {
+    let inverse = glm::inverse(view_projection);
+    let mut corners = [glm::Vec3::zeros(); 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let ndc = glm::vec4(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+            1.0,
+        );
+        let p = inverse * ndc;
+        *corner = p.xyz() / p.w;
+    }
+    corners
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Where `AppState` gets the current time from. Tutorials only see the time through
+/// `AppContext::elapsed_time` and the delta times, so swapping the clock pauses, slows down
+/// or fast-forwards every animation at once.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+    /// called once at the start of every rendered frame
+    fn tick(&mut self) {}
+    /// speed compared to the wall clock, 0 when time does not move on its own
+    fn scale(&self) -> f64 {
+        1.0
+    }
+}
+
+/// the wall clock
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// advances by `step` every frame, however long the frame took. For headless runs and recordings
+#[derive(Debug, Copy, Clone)]
+pub struct FixedClock {
+    now: DateTime<Utc>,
+    step: chrono::Duration,
+}
+
+impl FixedClock {
+    pub fn new(start: DateTime<Utc>, step: Duration) -> Self {
+        Self {
+            now: start,
+            step: chrono::Duration::from_std(step).unwrap(),
+        }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    fn tick(&mut self) {
+        self.now += self.step;
+    }
+
+    fn scale(&self) -> f64 {
+        0.0
+    }
+}
+
+/// runs `scale` times as fast as the wall clock, starting from `start`
+#[derive(Debug, Copy, Clone)]
+pub struct ScaledClock {
+    start: DateTime<Utc>,
+    real_start: DateTime<Utc>,
+    scale: f64,
+}
+
+impl ScaledClock {
+    pub fn new(start: DateTime<Utc>, scale: f64) -> Self {
+        Self {
+            start,
+            real_start: Utc::now(),
+            scale,
+        }
+    }
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> DateTime<Utc> {
+        let real_elapsed = (Utc::now() - self.real_start)
+            .num_microseconds()
+            .unwrap_or(0);
+        self.start + chrono::Duration::microseconds((real_elapsed as f64 * self.scale) as i64)
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// stands still at `now`
+#[derive(Debug, Copy, Clone)]
+pub struct PausedClock {
+    now: DateTime<Utc>,
+}
+
+impl PausedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now }
+    }
+}
+
+impl Clock for PausedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    fn scale(&self) -> f64 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::AppState;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn state() -> AppState {
+        let mut state = AppState::new("#version 330", 800, 600, 1.0);
+        state.start = start();
+        state.set_clock(FixedClock::new(start(), Duration::from_millis(10)));
+        state
+    }
+
+    #[test]
+    fn fixed_clock_moves_one_step_per_tick() {
+        let mut clock = FixedClock::new(start(), Duration::from_millis(16));
+        assert_eq!(clock.now(), start());
+        clock.tick();
+        clock.tick();
+        assert_eq!(clock.now(), start() + chrono::Duration::milliseconds(32));
+        assert_eq!(clock.scale(), 0.0);
+    }
+
+    #[test]
+    fn paused_time_only_moves_by_steps() {
+        let mut state = state();
+        state.clock.tick();
+        state.pause();
+        assert!(state.is_paused());
+        state.clock.tick();
+        assert_eq!(state.elapsed_time(), chrono::Duration::milliseconds(10));
+
+        state.step(Duration::from_millis(250));
+        assert!(state.is_paused());
+        assert_eq!(state.elapsed_time(), chrono::Duration::milliseconds(260));
+
+        state.resume();
+        assert!(!state.is_paused());
+        assert!(state.elapsed_time() >= chrono::Duration::milliseconds(260));
+    }
+
+    #[test]
+    fn step_does_nothing_while_running() {
+        let mut state = state();
+        state.step(Duration::from_secs(1));
+        assert_eq!(state.elapsed_time(), chrono::Duration::zero());
+    }
+
+    #[test]
+    fn time_scale_is_kept_over_a_pause() {
+        let mut state = state();
+        state.set_time_scale(0.5);
+        assert_eq!(state.time_scale(), 0.5);
+
+        state.pause();
+        assert_eq!(state.time_scale(), 0.5);
+        state.set_time_scale(2.0);
+        assert!(state.is_paused());
+        state.resume();
+        assert_eq!(state.time_scale(), 2.0);
+        assert_eq!(state.clock.scale(), 2.0);
+    }
+
+    #[test]
+    fn scaled_clock_without_speed_stands_still() {
+        let clock = ScaledClock::new(start(), 0.0);
+        assert_eq!(clock.now(), start());
+    }
+}
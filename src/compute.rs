@@ -0,0 +1,73 @@
+use crate::gl_object::{Borrowed, OwnedBuffer, SharedGl};
+use bytemuck::Pod;
+use glow::{Buffer, Context, HasContext};
+use std::marker::PhantomData;
+use std::ops::BitOr;
+
+/// What has to see the writes of earlier compute dispatches, see `memory_barrier`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Barrier(u32);
+
+impl Barrier {
+    /// vertex attributes read from buffers written as storage buffers
+    pub const VERTEX_ATTRIB_ARRAY: Self = Self(glow::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    /// samplers reading textures written as images
+    pub const TEXTURE_FETCH: Self = Self(glow::TEXTURE_FETCH_BARRIER_BIT);
+    /// image load/store in later dispatches or draws
+    pub const SHADER_IMAGE_ACCESS: Self = Self(glow::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+}
+
+impl BitOr for Barrier {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Waits for the writes of earlier dispatches before the reads listed in `barrier`
+pub fn memory_barrier(gl: &Context, barrier: Barrier) {
+    unsafe { gl.memory_barrier(barrier.0) };
+}
+
+/// A shader storage buffer holding an array of `T`, laid out as std430 by the shader. `T`
+/// has to match that layout, e.g. use vec4 instead of vec3 members.
+pub struct StorageBuffer<T> {
+    buffer: OwnedBuffer,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new(gl: &SharedGl, data: &[T]) -> Self {
+        let buffer = OwnedBuffer::create_buffer(gl, "storage buffer");
+        gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(buffer.raw()));
+        gl.buffer_data_u8_slice(
+            glow::SHADER_STORAGE_BUFFER,
+            bytemuck::cast_slice(data),
+            glow::DYNAMIC_COPY,
+        );
+        gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
+        Self {
+            buffer,
+            len: data.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// binds the buffer to `layout(std430, binding = index)`
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn bind(&self, gl: &Context, index: u32) {
+        gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, index, Some(self.buffer.raw()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// the buffer, e.g. to also bind it as vertex buffer
+    pub fn buffer(&self) -> Borrowed<'_, Buffer> {
+        self.buffer.borrow()
+    }
+}
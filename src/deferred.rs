@@ -0,0 +1,537 @@
+use crate::framebuffer::{
+    hdr_color_format, ColorFormat, Depth, Framebuffer, FramebufferDesc, FramebufferError,
+};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray, SharedGl};
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use std::f32::consts::PI;
+use std::fmt;
+use std::mem::size_of;
+
+/// lights per draw of the full-screen lighting pass, `MAX_LIGHTS` in `deferred/lighting.fs`
+const LIGHTS_PER_BATCH: usize = 32;
+
+/// A point light of the deferred renderer
+#[derive(Debug, Copy, Clone)]
+pub struct PointLight {
+    pub position: glm::Vec3,
+    pub color: glm::Vec3,
+}
+
+/// Attenuation `1 / (1 + linear * d + quadratic * d * d)` of the point lights, the constant term
+/// is 1
+#[derive(Debug, Copy, Clone)]
+pub struct Attenuation {
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            linear: 0.7,
+            quadratic: 1.8,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Distance at which the brightest channel of `color` has faded to 5/256, below what an 8
+    /// bit target shows. Lights are culled beyond it.
+    pub fn radius(&self, color: &glm::Vec3) -> f32 {
+        let max_brightness = color.x.max(color.y).max(color.z);
+        let constant = 1.0 - (256.0 / 5.0) * max_brightness;
+        (-self.linear + (self.linear * self.linear - 4.0 * self.quadratic * constant).sqrt())
+            / (2.0 * self.quadratic)
+    }
+}
+
+/// What `GBuffer::draw_debug` shows
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GBufferView {
+    /// the lit scene, nothing is drawn
+    #[default]
+    Lit,
+    Position,
+    Normal,
+    Albedo,
+    Specular,
+    /// the four above, one in each quarter of the screen
+    All,
+}
+
+impl GBufferView {
+    pub const ALL: [GBufferView; 6] = [
+        GBufferView::Lit,
+        GBufferView::Position,
+        GBufferView::Normal,
+        GBufferView::Albedo,
+        GBufferView::Specular,
+        GBufferView::All,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&v| v == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for GBufferView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GBufferView::Lit => write!(f, "Lit"),
+            GBufferView::Position => write!(f, "Position"),
+            GBufferView::Normal => write!(f, "Normal"),
+            GBufferView::Albedo => write!(f, "Albedo"),
+            GBufferView::Specular => write!(f, "Specular"),
+            GBufferView::All => write!(f, "All"),
+        }
+    }
+}
+
+/// The geometry buffer of a deferred renderer
+///
+/// Color attachments are `gPosition` (0) and `gNormal` (1) as float, and `gAlbedoSpec` (2) with
+/// the specular intensity in alpha. Depth and stencil are `DEPTH24_STENCIL8` like the default
+/// framebuffer, so they can be blitted to it for forward rendering on top.
+pub struct GBuffer {
+    framebuffer: Framebuffer,
+    debug_shader: MyShader,
+    quad: FullscreenQuad,
+}
+
+impl GBuffer {
+    pub fn new(
+        gl: &SharedGl,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        // positions are useless in 8 bit, but that is all WebGL2 without float targets has
+        let float = hdr_color_format(gl);
+        let framebuffer = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(vec![float, float, ColorFormat::Rgba8])
+                .depth(Depth::Texture)
+                .stencil(true)
+                .label("g-buffer")
+                .build(),
+            window_width,
+            window_height,
+        )?;
+        let debug_shader = fullscreen_shader(
+            gl,
+            "g-buffer debug",
+            include_str!("shaders/deferred/gbuffer_debug.fs"),
+            shader_version,
+        )?;
+        debug_shader.use_shader(gl);
+        debug_shader.set_int(gl, "gPosition", 0);
+        debug_shader.set_int(gl, "gNormal", 1);
+        debug_shader.set_int(gl, "gAlbedoSpec", 2);
+        Ok(Self {
+            framebuffer,
+            debug_shader,
+            quad: FullscreenQuad::new(gl),
+        })
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    /// Binds the framebuffer for the geometry pass, which still has to be cleared
+    pub fn bind(&self, gl: &Context) {
+        self.framebuffer.bind(gl);
+    }
+
+    /// binds position, normal and albedo to texture units 0, 1 and 2
+    pub fn bind_textures(&self, gl: &Context) {
+        for i in 0..3 {
+            self.framebuffer.bind_color(gl, i, i as u32);
+        }
+        unsafe { gl.active_texture(glow::TEXTURE0) };
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+    ) -> Result<bool, FramebufferError> {
+        self.framebuffer.resize(gl, width, height)
+    }
+
+    /// Shows `view` in the default framebuffer of `width` x `height`, does nothing for
+    /// `GBufferView::Lit`. Depth testing is left disabled.
+    pub fn draw_debug(&self, gl: &Context, view: GBufferView, width: u32, height: u32) {
+        let (w, h) = (width as i32, height as i32);
+        let viewports = match view {
+            GBufferView::Lit => return,
+            GBufferView::Position => vec![(0, 0, 0, w, h)],
+            GBufferView::Normal => vec![(1, 0, 0, w, h)],
+            GBufferView::Albedo => vec![(2, 0, 0, w, h)],
+            GBufferView::Specular => vec![(3, 0, 0, w, h)],
+            GBufferView::All => vec![
+                (0, 0, h / 2, w / 2, h / 2),
+                (1, w / 2, h / 2, w / 2, h / 2),
+                (2, 0, 0, w / 2, h / 2),
+                (3, w / 2, 0, w / 2, h / 2),
+            ],
+        };
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.disable(glow::DEPTH_TEST);
+            self.bind_textures(gl);
+            self.debug_shader.use_shader(gl);
+            for (attachment, x, y, w, h) in viewports {
+                gl.viewport(x, y, w, h);
+                self.debug_shader.set_int(gl, "attachment", attachment);
+                self.quad.draw(gl);
+            }
+            gl.viewport(0, 0, width as i32, height as i32);
+        }
+    }
+}
+
+/// How `DeferredLighting` shades the G-buffer
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LightingMode {
+    /// a full-screen pass per batch of 32 lights, every pixel loops over every light
+    FullScreen,
+    /// a sphere per light, scaled to its radius, so only the pixels it can reach are shaded
+    #[default]
+    Volumes,
+}
+
+impl LightingMode {
+    pub const ALL: [LightingMode; 2] = [LightingMode::FullScreen, LightingMode::Volumes];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&m| m == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for LightingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LightingMode::FullScreen => write!(f, "Full-screen passes"),
+            LightingMode::Volumes => write!(f, "Light volumes"),
+        }
+    }
+}
+
+/// Per instance data of the light volumes and markers, attributes 1 to 3
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightInstance {
+    position: glm::Vec3,
+    color: glm::Vec3,
+    radius: f32,
+}
+
+/// The lighting pass of the deferred renderer, for any number of point lights
+pub struct DeferredLighting {
+    pub mode: LightingMode,
+    pub attenuation: Attenuation,
+    /// of the albedo, added once
+    pub ambient: f32,
+    lights: Vec<PointLight>,
+    fullscreen_shader: MyShader,
+    volume_shader: MyShader,
+    marker_shader: MyShader,
+    quad: FullscreenQuad,
+    _sphere_vbo: OwnedBuffer,
+    _sphere_ebo: OwnedBuffer,
+    sphere_index_count: i32,
+    instance_vbo: OwnedBuffer,
+    sphere_vao: OwnedVertexArray,
+}
+
+impl DeferredLighting {
+    pub fn new(gl: &SharedGl, shader_version: Option<&str>) -> Result<Self, PostProcessError> {
+        let fullscreen_shader = fullscreen_shader(
+            gl,
+            "deferred lighting",
+            include_str!("shaders/deferred/lighting.fs"),
+            shader_version,
+        )?;
+        let mut volume_shader = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/deferred/light_volume.vs"),
+            include_str!("shaders/deferred/light_volume.fs"),
+            shader_version,
+        )?;
+        volume_shader.set_name("deferred light volumes");
+        let mut marker_shader = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/deferred/light_marker.vs"),
+            include_str!("shaders/deferred/light_marker.fs"),
+            shader_version,
+        )?;
+        marker_shader.set_name("deferred light markers");
+        for shader in [&fullscreen_shader, &volume_shader] {
+            shader.use_shader(gl);
+            shader.set_int(gl, "gPosition", 0);
+            shader.set_int(gl, "gNormal", 1);
+            shader.set_int(gl, "gAlbedoSpec", 2);
+        }
+
+        let (vertices, indices) = unit_sphere(16, 12);
+        let sphere_vbo = OwnedBuffer::create_buffer(gl, "light volume vbo");
+        let sphere_ebo = OwnedBuffer::create_buffer(gl, "light volume ebo");
+        let instance_vbo = OwnedBuffer::create_buffer(gl, "light volume instances");
+        let sphere_vao = OwnedVertexArray::create_vertex_array(gl, "light volume vao");
+        unsafe {
+            gl.bind_vertex_array(Some(sphere_vao.raw()));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(sphere_vbo.raw()));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(&vertices),
+                glow::STATIC_DRAW,
+            );
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 0, 0);
+            gl.enable_vertex_attrib_array(0);
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(sphere_ebo.raw()));
+            gl.buffer_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                bytemuck::cast_slice(&indices),
+                glow::STATIC_DRAW,
+            );
+
+            // set the lights as instance vertex attributes (with divisor 1)
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(instance_vbo.raw()));
+            let stride = size_of::<LightInstance>() as i32;
+            let float = size_of::<f32>() as i32;
+            for (location, size, offset) in [(1, 3, 0), (2, 3, 3 * float), (3, 1, 6 * float)] {
+                gl.vertex_attrib_pointer_f32(location, size, glow::FLOAT, false, stride, offset);
+                gl.enable_vertex_attrib_array(location);
+                gl.vertex_attrib_divisor(location, 1);
+            }
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+
+        Ok(Self {
+            mode: LightingMode::default(),
+            attenuation: Attenuation::default(),
+            ambient: 0.1,
+            lights: Vec::new(),
+            fullscreen_shader,
+            volume_shader,
+            marker_shader,
+            quad: FullscreenQuad::new(gl),
+            _sphere_vbo: sphere_vbo,
+            _sphere_ebo: sphere_ebo,
+            sphere_index_count: indices.len() as i32,
+            instance_vbo,
+            sphere_vao,
+        })
+    }
+
+    /// Replaces the lights, their radii come from `attenuation`, so call it again after
+    /// changing that
+    pub fn set_lights(&mut self, gl: &Context, lights: &[PointLight]) {
+        self.lights = lights.to_vec();
+        let instances: Vec<LightInstance> = lights
+            .iter()
+            .map(|light| LightInstance {
+                position: light.position,
+                color: light.color,
+                radius: self.attenuation.radius(&light.color),
+            })
+            .collect();
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.instance_vbo.raw()));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(&instances),
+                glow::DYNAMIC_DRAW,
+            );
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+
+    /// Shades `gbuffer` into the default framebuffer of `width` x `height`. Depth testing is
+    /// left disabled.
+    pub fn render(
+        &self,
+        gl: &Context,
+        gbuffer: &GBuffer,
+        projection: &glm::Mat4,
+        view: &glm::Mat4,
+        width: u32,
+        height: u32,
+    ) {
+        // the camera position, translation of the inverse view matrix
+        let view_pos = &glm::inverse(view).column(3).xyz();
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(0, 0, width as i32, height as i32);
+            gl.disable(glow::DEPTH_TEST);
+            gbuffer.bind_textures(gl);
+
+            let shader = &self.fullscreen_shader;
+            shader.use_shader(gl);
+            shader.set_vec3(gl, "viewPos", view_pos);
+            shader.set_float(gl, "lightLinear", self.attenuation.linear);
+            shader.set_float(gl, "lightQuadratic", self.attenuation.quadratic);
+            shader.set_float(gl, "ambient", self.ambient);
+            shader.set_int(gl, "lightCount", 0);
+            let batches: Vec<&[PointLight]> = match self.mode {
+                LightingMode::FullScreen => self.lights.chunks(LIGHTS_PER_BATCH).collect(),
+                LightingMode::Volumes => Vec::new(),
+            };
+            if batches.is_empty() {
+                // only the ambient term
+                self.quad.draw(gl);
+            }
+            for (i, batch) in batches.iter().enumerate() {
+                if i == 1 {
+                    // add the other batches on top of the first
+                    gl.enable(glow::BLEND);
+                    gl.blend_func(glow::ONE, glow::ONE);
+                    shader.set_float(gl, "ambient", 0.0);
+                }
+                // whole arrays at once, which also keeps them out of the uniforms window
+                let positions: Vec<glm::Vec3> = batch.iter().map(|l| l.position).collect();
+                let colors: Vec<glm::Vec3> = batch.iter().map(|l| l.color).collect();
+                let radii: Vec<f32> = batch
+                    .iter()
+                    .map(|l| self.attenuation.radius(&l.color))
+                    .collect();
+                shader.set_int(gl, "lightCount", batch.len() as i32);
+                shader.set(gl, "lightPositions", positions.as_slice());
+                shader.set(gl, "lightColors", colors.as_slice());
+                shader.set(gl, "lightRadii", radii.as_slice());
+                self.quad.draw(gl);
+            }
+
+            if self.mode == LightingMode::Volumes && !self.lights.is_empty() {
+                // draw the back faces, so a volume the camera is inside still covers the
+                // screen, and add up the lights without depth testing
+                gl.enable(glow::BLEND);
+                gl.blend_func(glow::ONE, glow::ONE);
+                gl.enable(glow::CULL_FACE);
+                gl.cull_face(glow::FRONT);
+                let shader = &self.volume_shader;
+                shader.use_shader(gl);
+                shader.set_mat4(gl, "projection", projection);
+                shader.set_mat4(gl, "view", view);
+                shader.set_vec3(gl, "viewPos", view_pos);
+                shader.set_float(gl, "lightLinear", self.attenuation.linear);
+                shader.set_float(gl, "lightQuadratic", self.attenuation.quadratic);
+                shader.set(gl, "screenSize", &glm::vec2(width as f32, height as f32));
+                self.draw_spheres(gl);
+                gl.cull_face(glow::BACK);
+                gl.disable(glow::CULL_FACE);
+            }
+            gl.disable(glow::BLEND);
+        }
+    }
+
+    /// Draws every light as a small sphere of its color, into the bound framebuffer with
+    /// depth testing, e.g. after blitting the depth of the G-buffer
+    pub fn draw_markers(&self, gl: &Context, projection: &glm::Mat4, view: &glm::Mat4, size: f32) {
+        if self.lights.is_empty() {
+            return;
+        }
+        self.marker_shader.use_shader(gl);
+        self.marker_shader.set_mat4(gl, "projection", projection);
+        self.marker_shader.set_mat4(gl, "view", view);
+        self.marker_shader.set_float(gl, "size", size);
+        self.draw_spheres(gl);
+    }
+
+    fn draw_spheres(&self, gl: &Context) {
+        unsafe {
+            gl.bind_vertex_array(Some(self.sphere_vao.raw()));
+            gl.draw_elements_instanced(
+                glow::TRIANGLES,
+                self.sphere_index_count,
+                glow::UNSIGNED_INT,
+                0,
+                self.lights.len() as i32,
+            );
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    /// Mode, ambient and attenuation. Changed attenuation is applied by calling `set_lights`.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) -> bool {
+        egui::ComboBox::from_label("Lighting")
+            .selected_text(self.mode.to_string())
+            .show_ui(ui, |ui| {
+                for mode in LightingMode::ALL {
+                    ui.selectable_value(&mut self.mode, mode, mode.to_string());
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.ambient, 0.0..=1.0).text("Ambient"));
+        let linear = ui.add(
+            egui::Slider::new(&mut self.attenuation.linear, 0.0..=2.0).text("Linear attenuation"),
+        );
+        let quadratic = ui.add(
+            egui::Slider::new(&mut self.attenuation.quadratic, 0.01..=5.0)
+                .text("Quadratic attenuation"),
+        );
+        linear.changed() || quadratic.changed()
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) -> bool {
+        for mode in LightingMode::ALL {
+            if ui
+                .radio_button_config(mode.to_string(), self.mode == mode)
+                .build()
+            {
+                self.mode = mode;
+            }
+        }
+        ui.slider_float_config("Ambient", &mut self.ambient)
+            .range(0.0, 1.0)
+            .build();
+        let linear = ui
+            .slider_float_config("Linear attenuation", &mut self.attenuation.linear)
+            .range(0.0, 2.0)
+            .build();
+        let quadratic = ui
+            .slider_float_config("Quadratic attenuation", &mut self.attenuation.quadratic)
+            .range(0.01, 5.0)
+            .build();
+        linear || quadratic
+    }
+}
+
+/// Positions and counter-clockwise indices of a sphere around the origin. Its flat faces are
+/// pushed out to enclose the unit sphere, so the volume never cuts a light short.
+fn unit_sphere(segments: u32, rings: u32) -> (Vec<f32>, Vec<u32>) {
+    let scale = 1.0 / ((PI / segments as f32).cos() * (PI / (2 * rings) as f32).cos());
+    let mut vertices = Vec::new();
+    for ring in 0..=rings {
+        let phi = PI * ring as f32 / rings as f32;
+        for segment in 0..=segments {
+            let theta = 2.0 * PI * segment as f32 / segments as f32;
+            vertices.extend_from_slice(&[
+                scale * phi.sin() * theta.cos(),
+                scale * phi.cos(),
+                scale * phi.sin() * theta.sin(),
+            ]);
+        }
+    }
+    let mut indices = Vec::new();
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let current = ring * (segments + 1) + segment;
+            let below = current + segments + 1;
+            indices.extend_from_slice(&[current, current + 1, below]);
+            indices.extend_from_slice(&[current + 1, below + 1, below]);
+        }
+    }
+    (vertices, indices)
+}
@@ -0,0 +1,606 @@
+use crate::gl_object::{OwnedFramebuffer, OwnedRenderbuffer, OwnedTexture, SharedGl};
+use glow::{Context, HasContext};
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// Pixel format of a color attachment. The float formats need `EXT_color_buffer_float` on
+/// WebGL2.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorFormat {
+    Rgba8,
+    Rgba16F,
+    /// offered by the builder, no tutorial needs the full precision yet
+    #[allow(dead_code)]
+    Rgba32F,
+    /// a single float channel, e.g. luminance
+    R16F,
+    /// two full precision float channels, e.g. the depth moments of a variance shadow map.
+    /// Linear filtering needs `OES_texture_float_linear` on WebGL2, see `float_linear_supported`
+    Rg32F,
+    /// unsigned integers, e.g. object ids for picking. Read with `usampler2D`, not filterable
+    R32Ui,
+}
+
+impl ColorFormat {
+    fn internal_format(self) -> u32 {
+        match self {
+            ColorFormat::Rgba8 => glow::RGBA8,
+            ColorFormat::Rgba16F => glow::RGBA16F,
+            ColorFormat::Rgba32F => glow::RGBA32F,
+            ColorFormat::R16F => glow::R16F,
+            ColorFormat::Rg32F => glow::RG32F,
+            ColorFormat::R32Ui => glow::R32UI,
+        }
+    }
+
+    /// format and type for `tex_image_2d`
+    fn format_and_type(self) -> (u32, u32) {
+        match self {
+            ColorFormat::Rgba8 => (glow::RGBA, glow::UNSIGNED_BYTE),
+            ColorFormat::Rgba16F | ColorFormat::Rgba32F => (glow::RGBA, glow::FLOAT),
+            ColorFormat::R16F => (glow::RED, glow::FLOAT),
+            ColorFormat::Rg32F => (glow::RG, glow::FLOAT),
+            ColorFormat::R32Ui => (glow::RED_INTEGER, glow::UNSIGNED_INT),
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        self == ColorFormat::R32Ui
+    }
+}
+
+/// Whether float color attachments can be rendered to: always on desktop GL, with
+/// `EXT_color_buffer_float` (enabled by glow when available) on WebGL2
+pub fn float_targets_supported(gl: &Context) -> bool {
+    !gl.version().is_embedded || gl.supported_extensions().contains("EXT_color_buffer_float")
+}
+
+/// Whether 32-bit float textures can be filtered linearly: always on desktop GL, with
+/// `OES_texture_float_linear` on WebGL2
+pub fn float_linear_supported(gl: &Context) -> bool {
+    !gl.version().is_embedded
+        || gl
+            .supported_extensions()
+            .contains("OES_texture_float_linear")
+}
+
+/// `Rgba16F`, or `Rgba8` where float targets are not supported. Colors above 1 are then
+/// clipped, which is logged once.
+pub fn hdr_color_format(gl: &Context) -> ColorFormat {
+    if float_targets_supported(gl) {
+        return ColorFormat::Rgba16F;
+    }
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        log::warn!("EXT_color_buffer_float is not available, HDR targets fall back to RGBA8");
+    });
+    ColorFormat::Rgba8
+}
+
+/// How the depth (and stencil) buffer is stored
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Depth {
+    #[default]
+    None,
+    /// a texture that can be sampled later, like a shadow map
+    Texture,
+    /// only used for depth testing while rendering
+    Renderbuffer,
+}
+
+/// How big the attachments are
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FramebufferSize {
+    Fixed(u32, u32),
+    /// the window size times the factor, reallocated by `Framebuffer::resize`
+    Window(f32),
+}
+
+impl Default for FramebufferSize {
+    fn default() -> Self {
+        FramebufferSize::Window(1.0)
+    }
+}
+
+impl FramebufferSize {
+    fn resolve(self, window_width: u32, window_height: u32) -> (u32, u32) {
+        match self {
+            FramebufferSize::Fixed(width, height) => (width, height),
+            FramebufferSize::Window(scale) => (
+                ((window_width as f32 * scale).round() as u32).max(1),
+                ((window_height as f32 * scale).round() as u32).max(1),
+            ),
+        }
+    }
+}
+
+/// What `Framebuffer::new` creates
+#[derive(TypedBuilder, Debug, Clone, PartialEq)]
+pub struct FramebufferDesc {
+    /// `COLOR_ATTACHMENT0..`, all drawn to
+    #[builder(default)]
+    pub colors: Vec<ColorFormat>,
+    #[builder(default)]
+    pub depth: Depth,
+    /// adds a stencil buffer, packed with the depth buffer (a renderbuffer if `depth` is none)
+    #[builder(default)]
+    pub stencil: bool,
+    /// MSAA samples, 0 for none. Multisampled attachments are all renderbuffers, resolve them
+    /// with `blit_to` into a framebuffer with textures
+    #[builder(default)]
+    pub samples: u32,
+    #[builder(default)]
+    pub size: FramebufferSize,
+    /// names the framebuffer in errors and leak reports
+    #[builder(default = "framebuffer".to_string(), setter(into))]
+    pub label: String,
+}
+
+/// `glCheckFramebufferStatus` results other than complete
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FramebufferStatus {
+    IncompleteAttachment,
+    MissingAttachment,
+    IncompleteDimensions,
+    IncompleteMultisample,
+    /// the combination of formats is not supported, e.g. float colors without
+    /// `EXT_color_buffer_float`
+    Unsupported,
+    Other(u32),
+}
+
+impl FramebufferStatus {
+    pub(crate) fn from_gl(status: u32) -> Option<Self> {
+        Some(match status {
+            glow::FRAMEBUFFER_COMPLETE => return None,
+            glow::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => FramebufferStatus::IncompleteAttachment,
+            glow::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => FramebufferStatus::MissingAttachment,
+            glow::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => FramebufferStatus::IncompleteDimensions,
+            glow::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => FramebufferStatus::IncompleteMultisample,
+            glow::FRAMEBUFFER_UNSUPPORTED => FramebufferStatus::Unsupported,
+            other => FramebufferStatus::Other(other),
+        })
+    }
+}
+
+/// Why a `Framebuffer` could not be created
+#[derive(Debug)]
+pub enum FramebufferError {
+    Create {
+        label: String,
+        message: String,
+    },
+    Incomplete {
+        label: String,
+        status: FramebufferStatus,
+    },
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramebufferError::Create { label, message } => {
+                write!(f, "cannot create framebuffer {}: {}", label, message)
+            }
+            FramebufferError::Incomplete { label, status } => {
+                write!(f, "framebuffer {} is not complete: {:?}", label, status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FramebufferError {}
+
+enum Attachment {
+    Texture(OwnedTexture),
+    // only kept to be deleted with the framebuffer
+    Renderbuffer(#[allow(dead_code)] OwnedRenderbuffer),
+}
+
+impl Attachment {
+    fn texture(&self) -> Option<glow::Texture> {
+        match self {
+            Attachment::Texture(texture) => Some(texture.raw()),
+            Attachment::Renderbuffer(_) => None,
+        }
+    }
+}
+
+/// A framebuffer with its attachments, all deleted when dropped. Built from a
+/// `FramebufferDesc`, e.g.
+/// `Framebuffer::new(gl, FramebufferDesc::builder().colors(vec![ColorFormat::Rgba8]).depth(Depth::Renderbuffer).build(), ctx.width(), ctx.height())`
+pub struct Framebuffer {
+    desc: FramebufferDesc,
+    width: u32,
+    height: u32,
+    colors: Vec<Attachment>,
+    depth: Option<Attachment>,
+    fbo: OwnedFramebuffer,
+}
+
+impl Framebuffer {
+    /// Creates the attachments for a window of `window_width` x `window_height`, which only
+    /// matters with `FramebufferSize::Window`
+    pub fn new(
+        gl: &SharedGl,
+        desc: FramebufferDesc,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, FramebufferError> {
+        let (width, height) = desc.size.resolve(window_width, window_height);
+        let create_error = |message: String| FramebufferError::Create {
+            label: desc.label.clone(),
+            message,
+        };
+        unsafe {
+            let fbo = gl.create_framebuffer().map_err(create_error)?;
+            let fbo = OwnedFramebuffer::new(gl, fbo, &desc.label);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo.raw()));
+
+            let mut colors = Vec::new();
+            for (i, format) in desc.colors.iter().enumerate() {
+                let attachment_point = glow::COLOR_ATTACHMENT0 + i as u32;
+                let attachment = if desc.samples > 0 {
+                    let rbo =
+                        Self::renderbuffer(gl, &desc, format.internal_format(), width, height)
+                            .map_err(create_error)?;
+                    gl.framebuffer_renderbuffer(
+                        glow::FRAMEBUFFER,
+                        attachment_point,
+                        glow::RENDERBUFFER,
+                        Some(rbo.raw()),
+                    );
+                    Attachment::Renderbuffer(rbo)
+                } else {
+                    let texture = Self::color_attachment(gl, &desc, *format, width, height)
+                        .map_err(create_error)?;
+                    gl.framebuffer_texture_2d(
+                        glow::FRAMEBUFFER,
+                        attachment_point,
+                        glow::TEXTURE_2D,
+                        Some(texture.raw()),
+                        0,
+                    );
+                    Attachment::Texture(texture)
+                };
+                colors.push(attachment);
+            }
+            if colors.is_empty() {
+                // depth only, like a shadow map
+                gl.draw_buffers(&[glow::NONE]);
+                gl.read_buffer(glow::NONE);
+            } else {
+                let draw_buffers: Vec<u32> = (0..colors.len() as u32)
+                    .map(|i| glow::COLOR_ATTACHMENT0 + i)
+                    .collect();
+                gl.draw_buffers(&draw_buffers);
+            }
+
+            let depth = Self::depth_attachment(gl, &desc, width, height).map_err(create_error)?;
+
+            let status = FramebufferStatus::from_gl(gl.check_framebuffer_status(glow::FRAMEBUFFER));
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if let Some(status) = status {
+                return Err(FramebufferError::Incomplete {
+                    label: desc.label.clone(),
+                    status,
+                });
+            }
+
+            Ok(Self {
+                desc,
+                width,
+                height,
+                colors,
+                depth,
+                fbo,
+            })
+        }
+    }
+
+    unsafe fn color_attachment(
+        gl: &SharedGl,
+        desc: &FramebufferDesc,
+        format: ColorFormat,
+        width: u32,
+        height: u32,
+    ) -> Result<OwnedTexture, String> {
+        let texture = OwnedTexture::new(gl, gl.create_texture()?, &desc.label);
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture.raw()));
+        let (pixel_format, ty) = format.format_and_type();
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            format.internal_format() as i32,
+            width as i32,
+            height as i32,
+            0,
+            pixel_format,
+            ty,
+            None,
+        );
+        // integer textures are incomplete with linear filtering
+        let filter = if format.is_integer() {
+            glow::NEAREST
+        } else {
+            glow::LINEAR
+        };
+        Self::set_texture_parameters(gl, filter);
+        Ok(texture)
+    }
+
+    unsafe fn renderbuffer(
+        gl: &SharedGl,
+        desc: &FramebufferDesc,
+        internal_format: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<OwnedRenderbuffer, String> {
+        let rbo = OwnedRenderbuffer::new(gl, gl.create_renderbuffer()?, &desc.label);
+        gl.bind_renderbuffer(glow::RENDERBUFFER, Some(rbo.raw()));
+        if desc.samples > 0 {
+            gl.renderbuffer_storage_multisample(
+                glow::RENDERBUFFER,
+                desc.samples as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+        } else {
+            gl.renderbuffer_storage(
+                glow::RENDERBUFFER,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+        }
+        gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+        Ok(rbo)
+    }
+
+    unsafe fn depth_attachment(
+        gl: &SharedGl,
+        desc: &FramebufferDesc,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<Attachment>, String> {
+        let (internal_format, attachment_point) = if desc.stencil {
+            (glow::DEPTH24_STENCIL8, glow::DEPTH_STENCIL_ATTACHMENT)
+        } else {
+            (glow::DEPTH_COMPONENT24, glow::DEPTH_ATTACHMENT)
+        };
+        let depth = match desc.depth {
+            Depth::None if !desc.stencil => return Ok(None),
+            // multisampled textures are not available on WebGL2
+            Depth::Texture if desc.samples == 0 => {
+                let texture = OwnedTexture::new(gl, gl.create_texture()?, &desc.label);
+                gl.bind_texture(glow::TEXTURE_2D, Some(texture.raw()));
+                let (format, ty) = if desc.stencil {
+                    (glow::DEPTH_STENCIL, glow::UNSIGNED_INT_24_8)
+                } else {
+                    (glow::DEPTH_COMPONENT, glow::UNSIGNED_INT)
+                };
+                gl.tex_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    internal_format as i32,
+                    width as i32,
+                    height as i32,
+                    0,
+                    format,
+                    ty,
+                    None,
+                );
+                Self::set_texture_parameters(gl, glow::NEAREST);
+                gl.framebuffer_texture_2d(
+                    glow::FRAMEBUFFER,
+                    attachment_point,
+                    glow::TEXTURE_2D,
+                    Some(texture.raw()),
+                    0,
+                );
+                Attachment::Texture(texture)
+            }
+            _ => {
+                let rbo = Self::renderbuffer(gl, desc, internal_format, width, height)?;
+                gl.framebuffer_renderbuffer(
+                    glow::FRAMEBUFFER,
+                    attachment_point,
+                    glow::RENDERBUFFER,
+                    Some(rbo.raw()),
+                );
+                Attachment::Renderbuffer(rbo)
+            }
+        };
+        Ok(Some(depth))
+    }
+
+    /// filtering and clamping of the texture bound to `TEXTURE_2D`, which gets unbound
+    unsafe fn set_texture_parameters(gl: &Context, filter: u32) {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, filter as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, filter as i32);
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_S,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_T,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        gl.bind_texture(glow::TEXTURE_2D, None);
+    }
+
+    /// Reallocates the attachments for the new window size if they follow it. Returns whether
+    /// they were, textures from `color_texture` and `depth_texture` have to be fetched again then.
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<bool, FramebufferError> {
+        if self.desc.size.resolve(window_width, window_height) == (self.width, self.height) {
+            return Ok(false);
+        }
+        *self = Self::new(gl, self.desc.clone(), window_width, window_height)?;
+        Ok(true)
+    }
+
+    /// binds the framebuffer and sets the viewport to its size
+    pub fn bind(&self, gl: &Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.fbo.raw()));
+            gl.viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// draws only to the first `count` color attachments of the bound framebuffer, or to all of
+    /// them for `None` as after `new`
+    pub fn set_draw_colors(&self, gl: &Context, count: Option<usize>) {
+        let count = count.map_or(self.colors.len(), |count| count.min(self.colors.len()));
+        let draw_buffers: Vec<u32> = (0..count as u32)
+            .map(|i| glow::COLOR_ATTACHMENT0 + i)
+            .collect();
+        unsafe {
+            gl.draw_buffers(if draw_buffers.is_empty() {
+                &[glow::NONE]
+            } else {
+                &draw_buffers
+            });
+        }
+    }
+
+    pub fn raw(&self) -> glow::Framebuffer {
+        self.fbo.raw()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// the texture of `COLOR_ATTACHMENT0 + index`, `None` when multisampled
+    pub fn color_texture(&self, index: usize) -> Option<glow::Texture> {
+        self.colors.get(index).and_then(Attachment::texture)
+    }
+
+    /// `None` unless created with `Depth::Texture` and without MSAA
+    pub fn depth_texture(&self) -> Option<glow::Texture> {
+        self.depth.as_ref().and_then(Attachment::texture)
+    }
+
+    /// binds the texture of `COLOR_ATTACHMENT0 + index` to texture unit `unit`
+    pub fn bind_color(&self, gl: &Context, index: usize, unit: u32) {
+        let texture = self
+            .color_texture(index)
+            .expect("Color attachment is not a texture");
+        unsafe {
+            gl.active_texture(glow::TEXTURE0 + unit);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        }
+    }
+
+    /// Copies every color attachment to the one with the same index of `target`, and the depth
+    /// buffer too if both have one, which resolves MSAA. `None` is the default framebuffer of
+    /// `target_width` x `target_height`, which only gets color 0. Leaves no framebuffer bound.
+    pub fn blit_to(
+        &self,
+        gl: &Context,
+        target: Option<&Framebuffer>,
+        target_width: u32,
+        target_height: u32,
+    ) {
+        let (target_width, target_height) =
+            target.map_or((target_width, target_height), |t| t.size());
+        let same_size = (self.width, self.height) == (target_width, target_height);
+        // MSAA can only be resolved into the same size, and integer formats copied, without
+        // filtering
+        let integer = self.desc.colors.iter().any(|format| format.is_integer());
+        let filter = if same_size || self.desc.samples > 0 || integer {
+            glow::NEAREST
+        } else {
+            glow::LINEAR
+        };
+        let blit = |mask: u32, filter: u32| unsafe {
+            gl.blit_framebuffer(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                0,
+                0,
+                target_width as i32,
+                target_height as i32,
+                mask,
+                filter,
+            );
+        };
+        unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.fbo.raw()));
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, target.map(Framebuffer::raw));
+            match target {
+                Some(target) => {
+                    let count = self.colors.len().min(target.colors.len());
+                    for i in 0..count as u32 {
+                        // blit from one attachment to the same one, all others are off
+                        let mut draw_buffers = vec![glow::NONE; i as usize + 1];
+                        draw_buffers[i as usize] = glow::COLOR_ATTACHMENT0 + i;
+                        gl.read_buffer(glow::COLOR_ATTACHMENT0 + i);
+                        gl.draw_buffers(&draw_buffers);
+                        blit(glow::COLOR_BUFFER_BIT, filter);
+                    }
+                    target.set_draw_colors(gl, None);
+                    if self.depth.is_some() && target.depth.is_some() {
+                        let mut mask = glow::DEPTH_BUFFER_BIT;
+                        if self.desc.stencil && target.desc.stencil {
+                            mask |= glow::STENCIL_BUFFER_BIT;
+                        }
+                        blit(mask, glow::NEAREST);
+                    }
+                }
+                None if !self.colors.is_empty() => {
+                    gl.read_buffer(glow::COLOR_ATTACHMENT0);
+                    blit(glow::COLOR_BUFFER_BIT, filter);
+                }
+                None => {}
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Copies the depth (and stencil, if it has one) buffer to `target` or the default
+    /// framebuffer of `target_width` x `target_height`, so forward rendered geometry is depth
+    /// tested against what was drawn here. The formats have to match, the default framebuffer
+    /// is usually `DEPTH24_STENCIL8`. Leaves no framebuffer bound.
+    pub fn blit_depth_to(
+        &self,
+        gl: &Context,
+        target: Option<&Framebuffer>,
+        target_width: u32,
+        target_height: u32,
+    ) {
+        let (target_width, target_height) =
+            target.map_or((target_width, target_height), |t| t.size());
+        let mut mask = glow::DEPTH_BUFFER_BIT;
+        if self.desc.stencil {
+            mask |= glow::STENCIL_BUFFER_BIT;
+        }
+        unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.fbo.raw()));
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, target.map(Framebuffer::raw));
+            gl.blit_framebuffer(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                0,
+                0,
+                target_width as i32,
+                target_height as i32,
+                mask,
+                glow::NEAREST,
+            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use glow::HasContext;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The context as shared by `AppContext` and every `Owned` object. imgui's renderer wants an
+/// `Rc`, everything else an `Arc`.
+#[cfg(feature = "imgui-support")]
+pub type SharedGl = std::rc::Rc<glow::Context>;
+#[cfg(not(feature = "imgui-support"))]
+pub type SharedGl = std::sync::Arc<glow::Context>;
+
+/// A GL object type that `Owned` can free
+pub trait GlObject: Copy + fmt::Debug {
+    /// shown in leak reports
+    const KIND: &'static str;
+
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn delete(self, gl: &glow::Context);
+}
+
+// `glow::Buffer` and friends are associated types, which impls can't tell apart, so the
+// concrete handle types of each backend are used
+macro_rules! impl_gl_object {
+    ($($native:ident, $web:ident, $kind:literal => $delete:ident;)*) => {
+        $(
+            #[cfg(not(target_arch = "wasm32"))]
+            impl GlObject for glow::$native {
+                const KIND: &'static str = $kind;
+
+                unsafe fn delete(self, gl: &glow::Context) {
+                    gl.$delete(self);
+                }
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            impl GlObject for glow::$web {
+                const KIND: &'static str = $kind;
+
+                unsafe fn delete(self, gl: &glow::Context) {
+                    gl.$delete(self);
+                }
+            }
+        )*
+    };
+}
+
+impl_gl_object! {
+    NativeBuffer, WebBufferKey, "buffer" => delete_buffer;
+    NativeVertexArray, WebVertexArrayKey, "vertex array" => delete_vertex_array;
+    NativeTexture, WebTextureKey, "texture" => delete_texture;
+    NativeFramebuffer, WebFramebufferKey, "framebuffer" => delete_framebuffer;
+    NativeRenderbuffer, WebRenderbufferKey, "renderbuffer" => delete_renderbuffer;
+    NativeProgram, WebProgramKey, "program" => delete_program;
+    NativeSampler, WebSamplerKey, "sampler" => delete_sampler;
+}
+
+/// A GL object deleted when dropped. It keeps the context it was created with alive, but the
+/// context has to be current when it is dropped, which `window::run` makes sure of for
+/// everything owned by the `Application`.
+///
+/// `borrow` hands out a non-owning `Borrowed` handle that can't outlive it. `raw` is the plain
+/// glow handle to pass to GL calls, it must not be deleted and dangles once this is dropped.
+pub struct Owned<T: GlObject> {
+    gl: SharedGl,
+    raw: T,
+    #[cfg(debug_assertions)]
+    serial: u64,
+}
+
+pub type OwnedBuffer = Owned<glow::Buffer>;
+pub type OwnedVertexArray = Owned<glow::VertexArray>;
+pub type OwnedTexture = Owned<glow::Texture>;
+pub type OwnedFramebuffer = Owned<glow::Framebuffer>;
+pub type OwnedRenderbuffer = Owned<glow::Renderbuffer>;
+pub type OwnedProgram = Owned<glow::Program>;
+pub type OwnedSampler = Owned<glow::Sampler>;
+
+impl<T: GlObject> Owned<T> {
+    /// Takes ownership of `raw`, `label` names it in leak reports
+    pub fn new(gl: &SharedGl, raw: T, label: &str) -> Self {
+        Self {
+            gl: gl.clone(),
+            raw,
+            #[cfg(debug_assertions)]
+            serial: leaks::track(T::KIND, format!("{:?}", raw), label),
+        }
+    }
+
+    pub fn raw(&self) -> T {
+        self.raw
+    }
+
+    pub fn borrow(&self) -> Borrowed<'_, T> {
+        Borrowed {
+            raw: self.raw,
+            _owner: PhantomData,
+        }
+    }
+}
+
+impl Owned<glow::Buffer> {
+    pub fn create_buffer(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_buffer() }.expect("Cannot create buffer");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl Owned<glow::VertexArray> {
+    pub fn create_vertex_array(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_vertex_array() }.expect("Cannot create vertex array");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl Owned<glow::Texture> {
+    pub fn create_texture(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_texture() }.expect("Cannot create texture");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl Owned<glow::Framebuffer> {
+    pub fn create_framebuffer(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_framebuffer() }.expect("Cannot create framebuffer");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl Owned<glow::Program> {
+    pub fn create_program(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_program() }.expect("Cannot create program");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl Owned<glow::Sampler> {
+    pub fn create_sampler(gl: &SharedGl, label: &str) -> Self {
+        let raw = unsafe { gl.create_sampler() }.expect("Cannot create sampler");
+        Self::new(gl, raw, label)
+    }
+}
+
+impl<T: GlObject> Drop for Owned<T> {
+    fn drop(&mut self) {
+        unsafe { self.raw.delete(&self.gl) };
+        #[cfg(debug_assertions)]
+        leaks::untrack(self.serial);
+    }
+}
+
+/// A GL object owned by someone else, see `Owned::borrow`
+#[derive(Debug, Copy, Clone)]
+pub struct Borrowed<'a, T: GlObject> {
+    raw: T,
+    _owner: PhantomData<&'a Owned<T>>,
+}
+
+impl<T: GlObject> Borrowed<'_, T> {
+    pub fn raw(self) -> T {
+        self.raw
+    }
+}
+
+impl<T: GlObject> fmt::Debug for Owned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.raw.fmt(f)
+    }
+}
+
+/// Logs the GL objects still alive, called by `window::run` once the `Application` is dropped.
+/// Only debug builds keep track of them.
+pub fn report_leaks() {
+    #[cfg(debug_assertions)]
+    leaks::report();
+}
+
+#[cfg(debug_assertions)]
+mod leaks {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    struct Live {
+        kind: &'static str,
+        raw: String,
+        label: String,
+    }
+
+    #[derive(Default)]
+    struct Tracker {
+        next_serial: u64,
+        live: BTreeMap<u64, Live>,
+    }
+
+    thread_local! {
+        static TRACKER: RefCell<Tracker> = RefCell::new(Tracker::default());
+    }
+
+    pub fn track(kind: &'static str, raw: String, label: &str) -> u64 {
+        TRACKER.with(|tracker| {
+            let mut tracker = tracker.borrow_mut();
+            let serial = tracker.next_serial;
+            tracker.next_serial += 1;
+            let label = label.to_string();
+            tracker.live.insert(serial, Live { kind, raw, label });
+            serial
+        })
+    }
+
+    pub fn untrack(serial: u64) {
+        TRACKER.with(|tracker| tracker.borrow_mut().live.remove(&serial));
+    }
+
+    pub fn report() {
+        TRACKER.with(|tracker| {
+            let tracker = tracker.borrow();
+            if tracker.live.is_empty() {
+                return;
+            }
+            log::warn!("{} GL objects were not dropped:", tracker.live.len());
+            for live in tracker.live.values() {
+                log::warn!("  {} {} ({})", live.kind, live.raw, live.label);
+            }
+        });
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{run_tutorial_headless, TUTORIAL_IDS};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use typed_builder::TypedBuilder;
+
+/// Golden-image regression checks: a tutorial is rendered headless for a fixed number of frames
+/// (fixed time step, untouched camera) and its final frame is compared with a stored reference.
+#[derive(TypedBuilder, Debug, Clone)]
+pub struct GoldenOptions {
+    /// frames rendered before the read back, enough for animated tutorials to move away from t = 0
+    #[builder(default = 10)]
+    pub frames: u32,
+    /// largest per-channel difference for a pixel to still count as matching
+    #[builder(default = 3)]
+    pub tolerance: u8,
+    /// fraction of mismatching pixels allowed before a tutorial fails
+    #[builder(default = 0.001)]
+    pub max_mismatch_ratio: f64,
+    /// overwrite the references with the new renders instead of comparing
+    #[builder(default)]
+    pub update: bool,
+    #[builder(default = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("misc/golden"))]
+    pub reference_dir: PathBuf,
+    /// where the actual and diff images of failed tutorials are written
+    #[builder(default = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden"))]
+    pub output_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenResult {
+    Passed,
+    Updated,
+    MissingReference,
+    /// the tutorial did not produce a frame, e.g. unknown id or no EGL device
+    NotRendered,
+    /// the tutorial panicked, e.g. a resource is missing or a shader does not compile
+    Panicked(String),
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    Failed {
+        mismatched_pixels: usize,
+        max_delta: u8,
+    },
+}
+
+impl GoldenResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, GoldenResult::Passed | GoldenResult::Updated)
+    }
+}
+
+pub struct Comparison {
+    pub mismatched_pixels: usize,
+    pub max_delta: u8,
+    /// mismatching pixels in red over a dimmed grayscale copy of the actual image
+    pub diff: image::RgbImage,
+}
+
+/// Compares two images pixel by pixel, `None` if their sizes differ.
+pub fn compare(
+    expected: &image::RgbImage,
+    actual: &image::RgbImage,
+    tolerance: u8,
+) -> Option<Comparison> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+    let mut diff = image::RgbImage::new(actual.width(), actual.height());
+    let mut mismatched_pixels = 0;
+    let mut max_delta = 0;
+    for ((e, a), d) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let delta =
+            e.0.iter()
+                .zip(a.0.iter())
+                .map(|(e, a)| e.abs_diff(*a))
+                .max()
+                .unwrap_or(0);
+        max_delta = max_delta.max(delta);
+        if delta > tolerance {
+            mismatched_pixels += 1;
+            *d = image::Rgb([255, 0, 0]);
+        } else {
+            let luma = (a[0] as u32 * 299 + a[1] as u32 * 587 + a[2] as u32 * 114) / 1000;
+            let dimmed = (luma / 4) as u8;
+            *d = image::Rgb([dimmed, dimmed, dimmed]);
+        }
+    }
+    Some(Comparison {
+        mismatched_pixels,
+        max_delta,
+        diff,
+    })
+}
+
+/// Renders and checks one tutorial.
+#[allow(clippy::missing_safety_doc)]
+pub async unsafe fn check(
+    tutorial_id: &str,
+    options: &GoldenOptions,
+) -> anyhow::Result<GoldenResult> {
+    let Some(actual) = run_tutorial_headless(tutorial_id.to_string(), options.frames).await else {
+        return Ok(GoldenResult::NotRendered);
+    };
+    let reference_path = options.reference_dir.join(format!("{}.png", tutorial_id));
+
+    if options.update {
+        std::fs::create_dir_all(&options.reference_dir)?;
+        actual.save(&reference_path)?;
+        return Ok(GoldenResult::Updated);
+    }
+
+    if !reference_path.exists() {
+        return Ok(GoldenResult::MissingReference);
+    }
+    let expected = image::open(&reference_path)?.to_rgb8();
+    let Some(comparison) = compare(&expected, &actual, options.tolerance) else {
+        return Ok(GoldenResult::SizeMismatch {
+            expected: expected.dimensions(),
+            actual: actual.dimensions(),
+        });
+    };
+    let pixel_count = (actual.width() * actual.height()) as f64;
+    if comparison.mismatched_pixels as f64 / pixel_count <= options.max_mismatch_ratio {
+        return Ok(GoldenResult::Passed);
+    }
+
+    std::fs::create_dir_all(&options.output_dir)?;
+    actual.save(
+        options
+            .output_dir
+            .join(format!("{}.actual.png", tutorial_id)),
+    )?;
+    comparison
+        .diff
+        .save(options.output_dir.join(format!("{}.diff.png", tutorial_id)))?;
+    Ok(GoldenResult::Failed {
+        mismatched_pixels: comparison.mismatched_pixels,
+        max_delta: comparison.max_delta,
+    })
+}
+
+/// tutorials that create their window by hand instead of going through `window::run`
+const WINDOW_ONLY: &[&str] = &["1_1_1"];
+
+/// tutorials loading the backpack model, which is not in the repository
+const MISSING_RESOURCES: &[&str] = &["3_1_1", "4_9_3"];
+
+/// every tutorial that can be rendered headless from the repository alone
+pub fn default_tutorial_ids() -> Vec<&'static str> {
+    TUTORIAL_IDS
+        .iter()
+        .copied()
+        .filter(|id| !WINDOW_ONLY.contains(id) && !MISSING_RESOURCES.contains(id))
+        .collect()
+}
+
+/// Checks every tutorial in `tutorial_ids`, logging each result. A panicking tutorial is reported
+/// as such and does not stop the others. Returns the ids that did not pass.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn check_all(tutorial_ids: &[&str], options: &GoldenOptions) -> Vec<String> {
+    let mut failures = Vec::new();
+    for tutorial_id in tutorial_ids {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            pollster::block_on(check(tutorial_id, options))
+        }))
+        .unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default();
+            // anyhow errors passed to `expect` carry a backtrace, the first line is enough here
+            let message = message.lines().next().unwrap_or_default().to_string();
+            Ok(GoldenResult::Panicked(message))
+        });
+        match &result {
+            Ok(r) if r.is_ok() => log::info!("golden {}: {:?}", tutorial_id, r),
+            Ok(r) => log::error!("golden {}: {:?}", tutorial_id, r),
+            Err(e) => log::error!("golden {}: {}", tutorial_id, e),
+        }
+        if !matches!(result, Ok(r) if r.is_ok()) {
+            failures.push(tutorial_id.to_string());
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, value: u8) -> image::RgbImage {
+        image::RgbImage::from_pixel(width, height, image::Rgb([value, value, value]))
+    }
+
+    #[test]
+    fn differences_within_tolerance_match() {
+        let mut actual = image(4, 4, 100);
+        actual.put_pixel(1, 2, image::Rgb([103, 97, 100]));
+        let comparison = compare(&image(4, 4, 100), &actual, 3).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_delta, 3);
+    }
+
+    #[test]
+    fn differences_over_tolerance_are_counted_and_marked() {
+        let mut actual = image(4, 4, 100);
+        actual.put_pixel(1, 2, image::Rgb([100, 110, 100]));
+        actual.put_pixel(3, 0, image::Rgb([0, 100, 100]));
+        let comparison = compare(&image(4, 4, 100), &actual, 3).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 2);
+        assert_eq!(comparison.max_delta, 100);
+        assert_eq!(*comparison.diff.get_pixel(1, 2), image::Rgb([255, 0, 0]));
+        assert_ne!(*comparison.diff.get_pixel(0, 0), image::Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn size_mismatch_is_not_compared() {
+        assert!(compare(&image(8, 8, 0), &image(4, 8, 0), 3).is_none());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::framebuffer::{
+    float_targets_supported, hdr_color_format, ColorFormat, Depth, Framebuffer, FramebufferDesc,
+    FramebufferError, FramebufferSize,
+};
+use crate::gl_object::SharedGl;
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// size of the log luminance target, a power of two so every mip level halves it exactly
+const LUMINANCE_SIZE: u32 = 256;
+/// the histogram is built from the 16x16 mip level
+const HISTOGRAM_LEVEL: i32 = 4;
+
+/// Curve mapping HDR colors to [0, 1], in the order `tone_map.fs` expects
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ToneMapper {
+    /// no curve, values above 1 are clipped
+    Clamp,
+    Reinhard,
+    /// `1 - exp(-color)`, the curve of the learnopengl HDR chapter
+    #[default]
+    Exposure,
+    AcesFilmic,
+    Uncharted2,
+}
+
+impl ToneMapper {
+    pub const ALL: [ToneMapper; 5] = [
+        ToneMapper::Clamp,
+        ToneMapper::Reinhard,
+        ToneMapper::Exposure,
+        ToneMapper::AcesFilmic,
+        ToneMapper::Uncharted2,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&t| t == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for ToneMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToneMapper::Clamp => write!(f, "Clamp"),
+            ToneMapper::Reinhard => write!(f, "Reinhard"),
+            ToneMapper::Exposure => write!(f, "Exposure"),
+            ToneMapper::AcesFilmic => write!(f, "ACES Filmic"),
+            ToneMapper::Uncharted2 => write!(f, "Uncharted 2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct HdrSettings {
+    #[builder(default)]
+    pub tone_mapper: ToneMapper,
+    /// the exposure, or a factor of the automatic one
+    #[builder(default = 1.0)]
+    pub exposure: f32,
+    /// whether the output is encoded as sRGB, for a default framebuffer that is not
+    #[builder(default = true)]
+    pub srgb: bool,
+    /// exposure from a luminance histogram of the scene
+    #[builder(default = false)]
+    pub auto_exposure: bool,
+    /// luminance the average luminance is mapped to by auto exposure, 0.18 is middle gray
+    #[builder(default = 0.18)]
+    pub key: f32,
+    /// how fast auto exposure adapts, per second
+    #[builder(default = 1.5)]
+    pub adaptation_speed: f32,
+    /// fractions of the histogram, the darkest and brightest parts are ignored
+    #[builder(default = 0.5)]
+    pub low_percent: f32,
+    #[builder(default = 0.95)]
+    pub high_percent: f32,
+}
+
+/// Renders a scene to a floating-point framebuffer and tone maps it for display
+///
+/// Draw the scene after `begin`, then `resolve` it. Auto exposure renders the log luminance of
+/// the scene to a texture whose mip chain averages it down to 16x16 texels, builds a histogram
+/// of those and eases the exposure towards the one mapping their average to `key`, all on the
+/// GPU. Without float targets (WebGL2 lacking `EXT_color_buffer_float`) the scene is 8 bit
+/// and auto exposure is not available.
+pub struct HdrPipeline {
+    pub settings: HdrSettings,
+    scene: Framebuffer,
+    auto_exposure: Option<AutoExposure>,
+    tone_map_shader: MyShader,
+    quad: FullscreenQuad,
+}
+
+impl HdrPipeline {
+    pub fn new(
+        gl: &SharedGl,
+        settings: HdrSettings,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        Self::new_with_colors(gl, settings, 1, shader_version, window_width, window_height)
+    }
+
+    /// With `colors` color attachments in the scene framebuffer, e.g. 2 for a shader that
+    /// writes the bright parts for bloom to the second. Only the first is tone mapped.
+    pub fn new_with_colors(
+        gl: &SharedGl,
+        settings: HdrSettings,
+        colors: usize,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        let tone_map_shader = fullscreen_shader(
+            gl,
+            "hdr tone map",
+            include_str!("shaders/post/tone_map.fs"),
+            shader_version,
+        )?;
+
+        let format = hdr_color_format(gl);
+        let scene = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(vec![format; colors])
+                .depth(Depth::Renderbuffer)
+                .label("hdr scene")
+                .build(),
+            window_width,
+            window_height,
+        )?;
+
+        let auto_exposure = if float_targets_supported(gl) {
+            Some(AutoExposure::new(gl, shader_version)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            settings,
+            scene,
+            auto_exposure,
+            tone_map_shader,
+            quad: FullscreenQuad::new(gl),
+        })
+    }
+
+    /// the floating-point framebuffer the scene is drawn to
+    pub fn scene(&self) -> &Framebuffer {
+        &self.scene
+    }
+
+    /// Binds the scene framebuffer, which still has to be cleared
+    pub fn begin(&self, gl: &Context) {
+        self.scene.bind(gl);
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        self.scene.resize(gl, width, height)?;
+        Ok(())
+    }
+
+    /// Tone maps the scene to `output` or the default framebuffer of `width` x `height`.
+    /// `delta_time` is the time since the last frame, for exposure adaptation. Depth testing
+    /// is left disabled.
+    pub fn resolve(
+        &mut self,
+        gl: &Context,
+        delta_time: f32,
+        output: Option<&Framebuffer>,
+        width: u32,
+        height: u32,
+    ) {
+        unsafe {
+            gl.disable(glow::DEPTH_TEST);
+            let exposure_texture = match &mut self.auto_exposure {
+                Some(auto_exposure) if self.settings.auto_exposure => {
+                    let scene = &self.scene;
+                    auto_exposure.update(gl, scene, &self.quad, &self.settings, delta_time);
+                    Some(auto_exposure.texture())
+                }
+                Some(auto_exposure) => {
+                    // adapt from scratch when it is turned on again
+                    auto_exposure.valid = false;
+                    None
+                }
+                None => None,
+            };
+
+            match output {
+                Some(output) => output.bind(gl),
+                None => {
+                    gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                    gl.viewport(0, 0, width as i32, height as i32);
+                }
+            }
+            let shader = &self.tone_map_shader;
+            shader.use_shader(gl);
+            shader.set_int(gl, "hdrBuffer", 0);
+            shader.set_int(gl, "exposureTexture", 1);
+            shader.set_bool(gl, "autoExposure", exposure_texture.is_some());
+            shader.set_float(gl, "exposure", self.settings.exposure);
+            let tone_mapper = ToneMapper::ALL
+                .iter()
+                .position(|&t| t == self.settings.tone_mapper)
+                .unwrap();
+            shader.set_int(gl, "toneMapper", tone_mapper as i32);
+            shader.set_bool(gl, "srgb", self.settings.srgb);
+            self.scene.bind_color(gl, 0, 0);
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, exposure_texture);
+            gl.active_texture(glow::TEXTURE0);
+            self.quad.draw(gl);
+        }
+    }
+
+    /// Tone mapper, exposure and auto exposure settings
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        egui::ComboBox::from_label("Tone mapper")
+            .selected_text(settings.tone_mapper.to_string())
+            .show_ui(ui, |ui| {
+                for tone_mapper in ToneMapper::ALL {
+                    ui.selectable_value(
+                        &mut settings.tone_mapper,
+                        tone_mapper,
+                        tone_mapper.to_string(),
+                    );
+                }
+            });
+        ui.add(
+            egui::Slider::new(&mut settings.exposure, 0.01..=10.0)
+                .logarithmic(true)
+                .text("Exposure"),
+        );
+        ui.checkbox(&mut settings.srgb, "sRGB output");
+        ui.add_enabled(
+            self.auto_exposure.is_some(),
+            egui::Checkbox::new(&mut settings.auto_exposure, "Auto exposure"),
+        );
+        ui.add_enabled_ui(settings.auto_exposure, |ui| {
+            ui.add(egui::Slider::new(&mut settings.key, 0.01..=1.0).text("Key"));
+            ui.add(
+                egui::Slider::new(&mut settings.adaptation_speed, 0.1..=10.0)
+                    .text("Adaptation speed"),
+            );
+            ui.add(egui::Slider::new(&mut settings.low_percent, 0.0..=1.0).text("Low percent"));
+            ui.add(egui::Slider::new(&mut settings.high_percent, 0.0..=1.0).text("High percent"));
+        });
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let settings = &mut self.settings;
+        for tone_mapper in ToneMapper::ALL {
+            if ui
+                .radio_button_config(tone_mapper.to_string(), settings.tone_mapper == tone_mapper)
+                .build()
+            {
+                settings.tone_mapper = tone_mapper;
+            }
+        }
+        ui.slider_float_config("Exposure", &mut settings.exposure)
+            .range(0.01, 10.0)
+            .build();
+        ui.checkbox_config("sRGB output", &mut settings.srgb)
+            .build();
+        if self.auto_exposure.is_some() {
+            ui.checkbox_config("Auto exposure", &mut settings.auto_exposure)
+                .build();
+        }
+        if settings.auto_exposure {
+            ui.slider_float_config("Key", &mut settings.key)
+                .range(0.01, 1.0)
+                .build();
+            ui.slider_float_config("Adaptation speed", &mut settings.adaptation_speed)
+                .range(0.1, 10.0)
+                .build();
+            ui.slider_float_config("Low percent", &mut settings.low_percent)
+                .range(0.0, 1.0)
+                .build();
+            ui.slider_float_config("High percent", &mut settings.high_percent)
+                .range(0.0, 1.0)
+                .build();
+        }
+    }
+}
+
+/// Targets and shaders of auto exposure, see `HdrPipeline`
+struct AutoExposure {
+    luminance: Framebuffer,
+    /// this and last frame's exposure, used in turn
+    exposure: [Framebuffer; 2],
+    current: usize,
+    /// false until the exposure has been computed once, then it adapts
+    valid: bool,
+    luminance_shader: MyShader,
+    exposure_shader: MyShader,
+}
+
+impl AutoExposure {
+    fn new(gl: &SharedGl, shader_version: Option<&str>) -> Result<Self, PostProcessError> {
+        let luminance_shader = fullscreen_shader(
+            gl,
+            "hdr luminance",
+            include_str!("shaders/post/luminance.fs"),
+            shader_version,
+        )?;
+        let exposure_shader = fullscreen_shader(
+            gl,
+            "hdr exposure",
+            include_str!("shaders/post/exposure.fs"),
+            shader_version,
+        )?;
+
+        let target = |label: &str, size: u32| {
+            let desc = FramebufferDesc::builder()
+                .colors(vec![ColorFormat::R16F])
+                .size(FramebufferSize::Fixed(size, size))
+                .label(label)
+                .build();
+            Framebuffer::new(gl, desc, size, size)
+        };
+        let luminance = target("hdr luminance", LUMINANCE_SIZE)?;
+        let exposure = [target("hdr exposure 0", 1)?, target("hdr exposure 1", 1)?];
+        unsafe {
+            // storage for the mip chain, filled by `generate_mipmap` every frame
+            gl.bind_texture(glow::TEXTURE_2D, luminance.color_texture(0));
+            gl.generate_mipmap(glow::TEXTURE_2D);
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::NEAREST_MIPMAP_NEAREST as i32,
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            // the first frame mixes with the last one's exposure, by a factor of 0
+            for target in &exposure {
+                target.bind(gl);
+                gl.clear_color(1.0, 0.0, 0.0, 1.0);
+                gl.clear(glow::COLOR_BUFFER_BIT);
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+
+        Ok(Self {
+            luminance,
+            exposure,
+            current: 0,
+            valid: false,
+            luminance_shader,
+            exposure_shader,
+        })
+    }
+
+    fn texture(&self) -> glow::Texture {
+        self.exposure[self.current].color_texture(0).unwrap()
+    }
+
+    unsafe fn update(
+        &mut self,
+        gl: &Context,
+        scene: &Framebuffer,
+        quad: &FullscreenQuad,
+        settings: &HdrSettings,
+        delta_time: f32,
+    ) {
+        // log luminance, averaged down by the mip chain
+        self.luminance.bind(gl);
+        self.luminance_shader.use_shader(gl);
+        self.luminance_shader.set_int(gl, "hdrBuffer", 0);
+        scene.bind_color(gl, 0, 0);
+        quad.draw(gl);
+        gl.bind_texture(glow::TEXTURE_2D, self.luminance.color_texture(0));
+        gl.generate_mipmap(glow::TEXTURE_2D);
+
+        // histogram of a mip level and the exposure it asks for, eased from the last one
+        let previous = self.current;
+        self.current = 1 - previous;
+        self.exposure[self.current].bind(gl);
+        let adaptation = if self.valid {
+            1.0 - (-delta_time * settings.adaptation_speed).exp()
+        } else {
+            1.0
+        };
+        let shader = &self.exposure_shader;
+        shader.use_shader(gl);
+        shader.set_int(gl, "luminance", 0);
+        shader.set_int(gl, "previousExposure", 1);
+        shader.set_int(gl, "level", HISTOGRAM_LEVEL);
+        shader.set_float(gl, "minLogLuminance", -10.0);
+        shader.set_float(gl, "maxLogLuminance", 6.0);
+        shader.set_float(gl, "lowPercent", settings.low_percent);
+        shader.set_float(gl, "highPercent", settings.high_percent);
+        shader.set_float(gl, "key", settings.key);
+        shader.set_float(gl, "adaptation", adaptation);
+        self.luminance.bind_color(gl, 0, 0);
+        gl.active_texture(glow::TEXTURE1);
+        gl.bind_texture(glow::TEXTURE_2D, self.exposure[previous].color_texture(0));
+        gl.active_texture(glow::TEXTURE0);
+        quad.draw(gl);
+        self.valid = true;
+    }
+}
@@ -0,0 +1,332 @@
+use crate::clock::FixedClock;
+use crate::gl_object::SharedGl;
+use crate::recording::Recording;
+use crate::window::{
+    finish_recording, restore_gl_states, start_recording, step_fixed_frame, AppContext, AppState,
+    Application, GLContext, GlState, WindowInitInfo, TIME_STEP,
+};
+use glow::{Context, HasContext};
+use glutin::api::egl::{
+    context::PossiblyCurrentContext, device::Device, display::Display, surface::Surface,
+};
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder, GlConfig};
+use glutin::context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext};
+use glutin::display::GlDisplay;
+use glutin::surface::{PbufferSurface, SurfaceAttributesBuilder};
+use std::cell::RefCell;
+use std::num::NonZeroU32;
+
+thread_local! {
+    static LAST_FRAME: RefCell<Option<image::RgbImage>> = const { RefCell::new(None) };
+}
+
+/// the final frame of the last headless run on this thread, read back before `Application::exit`
+pub fn take_last_frame() -> Option<image::RgbImage> {
+    LAST_FRAME.with(|f| f.take())
+}
+
+/// What the frames are rendered into.
+pub enum Target {
+    /// The pbuffer is the default framebuffer, so tutorials that bind framebuffer `None`
+    /// to present their result still end up in it. Only held to keep the surface alive.
+    Pbuffer(#[allow(dead_code)] Surface<PbufferSurface>),
+    /// The driver has no pbuffer configs, so the context is made current without any surface and
+    /// frames go into our own framebuffer object. Tutorials that bind framebuffer `None` render
+    /// nothing in this mode.
+    Surfaceless {
+        fbo: glow::Framebuffer,
+        color: glow::Renderbuffer,
+        depth_stencil: glow::Renderbuffer,
+    },
+}
+
+pub struct HeadlessContext {
+    pub gl: Context,
+    pub target: Target,
+    // keep the context alive as long as the gl functions are used
+    _gl_context: PossiblyCurrentContext,
+}
+
+impl Target {
+    /// framebuffer to bind in place of the default one
+    pub fn framebuffer(&self) -> Option<glow::Framebuffer> {
+        match self {
+            Target::Pbuffer(_) => None,
+            Target::Surfaceless { fbo, .. } => Some(*fbo),
+        }
+    }
+
+    unsafe fn delete(&self, gl: &Context) {
+        if let Target::Surfaceless {
+            fbo,
+            color,
+            depth_stencil,
+        } = self
+        {
+            gl.delete_framebuffer(*fbo);
+            gl.delete_renderbuffer(*color);
+            gl.delete_renderbuffer(*depth_stencil);
+        }
+    }
+}
+
+/// Creates a GL context on the first EGL device that can provide one. Mesa lists its
+/// software rasterizer (llvmpipe) as a device too, so this works on machines without a GPU.
+pub unsafe fn create_context(init_info: &WindowInitInfo) -> anyhow::Result<HeadlessContext> {
+    let devices =
+        Device::query_devices().map_err(|e| anyhow::anyhow!("Cannot query EGL devices: {}", e))?;
+
+    let mut last_error = anyhow::anyhow!("No EGL device found");
+    for device in devices {
+        log::info!(
+            "Trying EGL device name: {:?}, vendor: {:?}",
+            device.name(),
+            device.vendor()
+        );
+        match create_context_on_device(&device, init_info) {
+            Ok(ctx) => return Ok(ctx),
+            Err(e) => {
+                log::warn!("EGL device rejected: {}", e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+unsafe fn create_context_on_device(
+    device: &Device,
+    init_info: &WindowInitInfo,
+) -> anyhow::Result<HeadlessContext> {
+    let display = Display::with_device(device, None)?;
+
+    let mut template = ConfigTemplateBuilder::new().with_surface_type(ConfigSurfaceTypes::PBUFFER);
+    if init_info.num_samples > 1 {
+        template = template.with_multisampling(init_info.num_samples);
+    }
+    let pbuffer_config = display
+        .find_configs(template.build())
+        .ok()
+        .and_then(|configs| configs.reduce(pick_config));
+    let config = match pbuffer_config {
+        Some(config) => config,
+        // any config will do for a surfaceless context
+        None => display
+            .find_configs(
+                ConfigTemplateBuilder::new()
+                    .with_surface_type(ConfigSurfaceTypes::empty())
+                    .build(),
+            )?
+            .reduce(pick_config)
+            .ok_or_else(|| anyhow::anyhow!("No EGL config"))?,
+    };
+
+    #[allow(unused_mut)]
+    let mut context_attributes = ContextAttributesBuilder::new().with_context_api(
+        ContextApi::OpenGl(Some(glutin::context::Version {
+            major: init_info.major,
+            minor: init_info.minor,
+        })),
+    );
+    #[cfg(debug_assertions)]
+    {
+        context_attributes = context_attributes.with_debug(true);
+    }
+    let not_current_gl_context =
+        display.create_context(&config, &context_attributes.build(None))?;
+
+    let (width, height) = (
+        NonZeroU32::new(init_info.width).unwrap_or(NonZeroU32::MIN),
+        NonZeroU32::new(init_info.height).unwrap_or(NonZeroU32::MIN),
+    );
+    let pbuffer = if config
+        .config_surface_types()
+        .contains(ConfigSurfaceTypes::PBUFFER)
+    {
+        let attrs = SurfaceAttributesBuilder::<PbufferSurface>::new().build(width, height);
+        display.create_pbuffer_surface(&config, &attrs).ok()
+    } else {
+        None
+    };
+
+    let (gl_context, pbuffer) = match pbuffer {
+        Some(pbuffer) => (
+            not_current_gl_context.make_current(&pbuffer)?,
+            Some(pbuffer),
+        ),
+        None => (not_current_gl_context.make_current_surfaceless()?, None),
+    };
+
+    #[allow(unused_mut)]
+    let mut gl = Context::from_loader_function_cstr(|s| display.get_proc_address(s));
+    crate::reflection::load_functions(|s| display.get_proc_address(s));
+    init_info
+        .check_context_version(&gl)
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    #[cfg(debug_assertions)]
+    {
+        crate::window::set_debug_callback(&mut gl);
+    }
+
+    log::info!(
+        "Headless context: {} ({})",
+        gl.get_parameter_string(glow::RENDERER),
+        gl.get_parameter_string(glow::VERSION)
+    );
+
+    let target = match pbuffer {
+        Some(pbuffer) => Target::Pbuffer(pbuffer),
+        None => create_surfaceless_target(&gl, width.get() as i32, height.get() as i32)?,
+    };
+
+    Ok(HeadlessContext {
+        gl,
+        target,
+        _gl_context: gl_context,
+    })
+}
+
+fn pick_config<C: GlConfig>(accum: C, config: C) -> C {
+    if config.num_samples() > accum.num_samples() {
+        config
+    } else {
+        accum
+    }
+}
+
+unsafe fn create_surfaceless_target(
+    gl: &Context,
+    width: i32,
+    height: i32,
+) -> anyhow::Result<Target> {
+    let map_err = |e: String| anyhow::anyhow!(e);
+    let fbo = gl.create_framebuffer().map_err(map_err)?;
+    gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+
+    let color = gl.create_renderbuffer().map_err(map_err)?;
+    gl.bind_renderbuffer(glow::RENDERBUFFER, Some(color));
+    gl.renderbuffer_storage(glow::RENDERBUFFER, glow::RGBA8, width, height);
+    gl.framebuffer_renderbuffer(
+        glow::FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT0,
+        glow::RENDERBUFFER,
+        Some(color),
+    );
+
+    let depth_stencil = gl.create_renderbuffer().map_err(map_err)?;
+    gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth_stencil));
+    gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH24_STENCIL8, width, height);
+    gl.framebuffer_renderbuffer(
+        glow::FRAMEBUFFER,
+        glow::DEPTH_STENCIL_ATTACHMENT,
+        glow::RENDERBUFFER,
+        Some(depth_stencil),
+    );
+    gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+
+    if gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+        return Err(anyhow::anyhow!("Headless framebuffer is not complete"));
+    }
+
+    Ok(Target::Surfaceless {
+        fbo,
+        color,
+        depth_stencil,
+    })
+}
+
+/// Drives `App` for `frames` frames without a window. Time is stepped by a fixed amount per frame,
+/// with `UPDATE_PER_SECOND / FPS` updates in between, as the game loop would do at full speed.
+/// With `recording`, every frame is written out too, and its `frames` are rendered instead if set.
+pub async unsafe fn run<App: Application>(
+    init_info: WindowInitInfo,
+    frames: u32,
+    recording: Option<Recording>,
+) {
+    let HeadlessContext {
+        gl,
+        target,
+        _gl_context,
+    } = match create_context(&init_info) {
+        Ok(context) => context,
+        Err(e) => {
+            log::error!("Cannot create headless GL context: {}", e);
+            return;
+        }
+    };
+
+    #[allow(clippy::arc_with_non_send_sync)]
+    let gl = SharedGl::new(gl);
+
+    let mut app_state = AppState::new(
+        init_info.desktop_shader_version(),
+        init_info.width,
+        init_info.height,
+        1.0,
+    );
+    let start = app_state.start;
+    app_state.set_clock(FixedClock::new(start, TIME_STEP));
+
+    let mut ctx = AppContext {
+        #[cfg(feature = "imgui-support")]
+        imgui_renderer: {
+            let mut r = easy_imgui_window::easy_imgui_renderer::Renderer::new(gl.clone()).unwrap();
+            r.set_background_color(None);
+            r
+        },
+        #[cfg(feature = "imgui-support")]
+        imgui_status: easy_imgui_window::MainWindowStatus::default(),
+        gl_context: GLContext {
+            gl,
+            gl_surface: None,
+        },
+        app_state,
+        #[cfg(feature = "egui-support")]
+        egui_glow: None,
+        gl_state: GlState::default(),
+        capture_requests: Default::default(),
+    };
+
+    let mut app = App::new(&ctx).await;
+
+    let gl = ctx.gl_context.gl.clone();
+    gl.bind_framebuffer(glow::FRAMEBUFFER, target.framebuffer());
+    app.resize(&ctx, init_info.width, init_info.height);
+
+    let frames = recording
+        .as_ref()
+        .and_then(|recording| recording.frames)
+        .unwrap_or(frames);
+    let mut recorder = recording
+        .and_then(|recording| start_recording(&recording, init_info.width, init_info.height));
+    for _ in 0..frames {
+        step_fixed_frame(&mut app, &mut ctx.app_state);
+
+        gl.bind_framebuffer(glow::FRAMEBUFFER, target.framebuffer());
+        restore_gl_states(&gl, &ctx.gl_state.states);
+        app.render(&ctx);
+        ctx.process_capture_requests();
+        if let Some(r) = &mut recorder {
+            if let Err(e) =
+                r.record_frame(&gl, target.framebuffer(), init_info.width, init_info.height)
+            {
+                log::error!("Recording stopped: {}", e);
+                finish_recording(recorder.take());
+            }
+        }
+        gl.finish();
+    }
+    finish_recording(recorder);
+    log::info!("Rendered {} frames headless", frames);
+
+    gl.bind_framebuffer(glow::READ_FRAMEBUFFER, target.framebuffer());
+    let frame = crate::capture::read_rgb8(&gl, 0, 0, init_info.width, init_info.height);
+    LAST_FRAME.with(|f| *f.borrow_mut() = Some(frame));
+
+    app.exit(&ctx);
+    // the context has to be current while the app's objects are deleted
+    drop(app);
+    target.delete(&gl);
+    crate::gl_object::report_leaks();
+}
@@ -0,0 +1,238 @@
+use crate::gl_object::SharedGl;
+use crate::resources;
+use crate::shader::MyShader;
+use crate::shader_error::ShaderError;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+
+/// how often the shader files are checked for changes
+const POLL_INTERVAL_MS: i64 = 500;
+
+thread_local! {
+    /// compile and link logs of reloadable shaders whose last reload failed, by shader name
+    static ERRORS: RefCell<BTreeMap<String, String>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// A `MyShader` loaded from files instead of `include_str!`, recompiled when the files change.
+/// The program is only swapped once the new sources compile and link, until then the last good
+/// one keeps being used and the log is shown in the egui overlay.
+///
+/// Paths are the ones of `resources::load_string`, tutorial shaders are available as
+/// `shaders/<chapter>/<file>`. On desktop the files in the source tree are watched, on the web
+/// they are fetched again from the server.
+pub struct ReloadableShader {
+    shader: MyShader,
+    name: String,
+    paths: Vec<String>,
+    shader_version: Option<String>,
+    sources: Vec<String>,
+    last_poll: chrono::DateTime<chrono::Utc>,
+    #[cfg(not(target_arch = "wasm32"))]
+    modified: Vec<Option<std::time::SystemTime>>,
+    #[cfg(target_arch = "wasm32")]
+    fetched: std::rc::Rc<RefCell<Option<Vec<String>>>>,
+    #[cfg(target_arch = "wasm32")]
+    fetching: std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+impl ReloadableShader {
+    pub async fn new(
+        gl: &SharedGl,
+        vertex_path: &str,
+        fragment_path: &str,
+        shader_version: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        Self::from_paths(gl, &[vertex_path, fragment_path], shader_version).await
+    }
+
+    async fn from_paths(
+        gl: &SharedGl,
+        paths: &[&str],
+        shader_version: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        let mut sources = Vec::new();
+        for path in paths {
+            let source = resources::load_string(path)
+                .await
+                .map_err(|e| ShaderError::Load {
+                    path: path.to_string(),
+                    message: e.to_string(),
+                })?;
+            sources.push(source);
+        }
+        let name = paths[0]
+            .rsplit('/')
+            .next()
+            .and_then(|file| file.rsplit_once('.'))
+            .map_or(paths[0], |(stem, _)| stem)
+            .to_string();
+
+        let mut shader = Self::compile(gl, &sources, shader_version)?;
+        shader.set_name(&name);
+
+        Ok(Self {
+            shader,
+            name,
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            shader_version: shader_version.map(str::to_string),
+            sources,
+            last_poll: chrono::Utc::now(),
+            #[cfg(not(target_arch = "wasm32"))]
+            modified: paths.iter().map(|p| Self::modified(p)).collect(),
+            #[cfg(target_arch = "wasm32")]
+            fetched: Default::default(),
+            #[cfg(target_arch = "wasm32")]
+            fetching: Default::default(),
+        })
+    }
+
+    fn compile(
+        gl: &SharedGl,
+        sources: &[String],
+        shader_version: Option<&str>,
+    ) -> Result<MyShader, ShaderError> {
+        match sources {
+            [vertex, fragment] => MyShader::new_from_source(gl, vertex, fragment, shader_version),
+            [vertex, fragment, geometry] => MyShader::new_with_geometry_from_source(
+                gl,
+                vertex,
+                fragment,
+                geometry,
+                shader_version,
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks the files for changes (at most every `POLL_INTERVAL_MS`) and swaps in the new
+    /// program if they compile. Call it once per frame before using the shader.
+    /// Returns true when the program was replaced: uniforms that are only set once, like
+    /// texture units, have to be set again.
+    pub fn reload_if_changed(&mut self, gl: &SharedGl) -> bool {
+        let now = chrono::Utc::now();
+        if (now - self.last_poll).num_milliseconds() < POLL_INTERVAL_MS {
+            return false;
+        }
+        self.last_poll = now;
+
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                self.poll_server(gl)
+            } else {
+                self.poll_files(gl)
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn modified(path: &str) -> Option<std::time::SystemTime> {
+        std::fs::metadata(resources::source_path(path))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_files(&mut self, gl: &SharedGl) -> bool {
+        let modified = self
+            .paths
+            .iter()
+            .map(|p| Self::modified(p))
+            .collect::<Vec<_>>();
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+
+        let sources = self
+            .paths
+            .iter()
+            .map(|p| std::fs::read_to_string(resources::source_path(p)))
+            .collect::<Result<Vec<_>, _>>();
+        match sources {
+            Ok(sources) => self.reload(gl, sources),
+            // editors may replace the file in several steps, try again on the next change
+            Err(e) => {
+                log::warn!("Cannot read shader {}: {}", self.name, e);
+                false
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn poll_server(&mut self, gl: &SharedGl) -> bool {
+        if let Some(sources) = self.fetched.take() {
+            return self.reload(gl, sources);
+        }
+        if !self.fetching.replace(true) {
+            let paths = self.paths.clone();
+            let fetched = self.fetched.clone();
+            let fetching = self.fetching.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let mut sources = Vec::new();
+                for path in &paths {
+                    match resources::load_string(path).await {
+                        Ok(source) => sources.push(source),
+                        Err(_) => break,
+                    }
+                }
+                // keep the current program if the server cannot be reached
+                if sources.len() == paths.len() {
+                    *fetched.borrow_mut() = Some(sources);
+                }
+                fetching.set(false);
+            });
+        }
+        false
+    }
+
+    fn reload(&mut self, gl: &SharedGl, sources: Vec<String>) -> bool {
+        if sources == self.sources {
+            return false;
+        }
+        self.sources = sources;
+
+        match Self::compile(gl, &self.sources, self.shader_version.as_deref()) {
+            Ok(mut shader) => {
+                shader.set_name(&self.name);
+                self.shader = shader;
+                ERRORS.with(|errors| errors.borrow_mut().remove(&self.name));
+                log::info!("Reloaded shader {}", self.name);
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to reload shader {}: {}", self.name, e);
+                ERRORS.with(|errors| errors.borrow_mut().insert(self.name.clone(), e.to_string()));
+                false
+            }
+        }
+    }
+}
+
+/// forgets the failed reload, the program deletes itself
+impl Drop for ReloadableShader {
+    fn drop(&mut self) {
+        ERRORS.with(|errors| errors.borrow_mut().remove(&self.name));
+    }
+}
+
+impl Deref for ReloadableShader {
+    type Target = MyShader;
+
+    fn deref(&self) -> &MyShader {
+        &self.shader
+    }
+}
+
+/// Shows a window with the log of every shader that failed to reload.
+#[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+pub fn show_errors(egui_ctx: &egui::Context) {
+    ERRORS.with(|errors| {
+        for (name, log) in errors.borrow().iter() {
+            egui::Window::new(format!("Shader error: {}", name)).show(egui_ctx, |ui| {
+                ui.label("Still using the last program that compiled");
+                ui.monospace(log);
+            });
+        }
+    });
+}
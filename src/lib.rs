@@ -1,9 +1,36 @@
+mod bloom;
 mod camera;
+mod capture;
+mod cascaded_shadow;
+mod clock;
+mod compute;
+mod deferred;
+mod framebuffer;
+mod gl_object;
+#[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+pub mod golden;
+mod hdr;
+#[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+mod headless;
+mod hot_reload;
 mod mesh;
 mod model;
+mod parallax;
+mod point_shadow;
+mod post_process;
+mod preprocessor;
+mod reflection;
+pub mod recording;
 mod resources;
 mod shader;
+mod shader_error;
+mod soft_shadow;
+mod ssao;
+mod tangent;
 mod texture;
+mod tweaks;
+mod uniform;
+mod uniform_buffer;
 mod window;
 
 mod _1_getting_started;
@@ -11,97 +38,164 @@ mod _2_lighting;
 mod _3_model_loading;
 mod _4_advanced_opengl;
 mod _5_advanced_lighting;
+mod _8_guest_articles;
 
 use _1_getting_started::*;
 use _2_lighting::*;
 use _3_model_loading::*;
 use _4_advanced_opengl::*;
 use _5_advanced_lighting::*;
+use _8_guest_articles::*;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+macro_rules! tutorials {
+    ($($id:literal => $main:ident,)*) => {
+        /// every tutorial id `run_tutorial` knows about
+        pub const TUTORIAL_IDS: &[&str] = &[$($id),*];
+
+        #[allow(clippy::missing_safety_doc)]
+        #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+        pub async unsafe fn run_tutorial(tutorial_id: String) {
+            log::info!("Running tutorial {}", tutorial_id);
+            // headless runs render what the tutorial sets, without the saved tweaks
+            if !matches!(window::backend_override(), Some(window::Backend::Headless { .. })) {
+                tweaks::load(&tutorial_id);
+            }
+            match tutorial_id.as_str() {
+                $($id => $main().await,)*
+                _ => log::error!("Unknown tutorial id: {}", tutorial_id),
+            }
+        }
+    };
+}
+
+tutorials! {
+    "1_1_1" => main_1_1_1,
+    "1_1_2" => main_1_1_2,
+    "1_2_1" => main_1_2_1,
+    "1_2_2" => main_1_2_2,
+    "1_2_3" => main_1_2_3,
+    "1_2_4" => main_1_2_4,
+    "1_2_5" => main_1_2_5,
+    "1_3_1" => main_1_3_1,
+    "1_3_2" => main_1_3_2,
+    "1_3_3" => main_1_3_3,
+    "1_3_4" => main_1_3_4,
+    "1_3_5" => main_1_3_5,
+    "1_3_6" => main_1_3_6,
+    "1_4_1" => main_1_4_1,
+    "1_4_2" => main_1_4_2,
+    "1_4_3" => main_1_4_3,
+    "1_4_4" => main_1_4_4,
+    "1_4_5" => main_1_4_5,
+    "1_4_6" => main_1_4_6,
+    "1_5_1" => main_1_5_1,
+    "1_5_2" => main_1_5_2,
+    "1_5_3" => main_1_5_3,
+    "1_6_1" => main_1_6_1,
+    "1_6_2" => main_1_6_2,
+    "1_6_3" => main_1_6_3,
+    "1_6_4" => main_1_6_4,
+    "1_7_1" => main_1_7_1,
+    "1_7_2" => main_1_7_2,
+    "1_7_3" => main_1_7_3,
+    "1_7_4" => main_1_7_4,
+    "1_7_5" => main_1_7_5,
+    "1_7_6" => main_1_7_6,
+    "2_1_1" => main_2_1_1,
+    "2_2_1" => main_2_2_1,
+    "2_2_2" => main_2_2_2,
+    "2_2_3" => main_2_2_3,
+    "2_2_4" => main_2_2_4,
+    "2_2_5" => main_2_2_5,
+    "2_3_1" => main_2_3_1,
+    "2_3_2" => main_2_3_2,
+    "2_4_1" => main_2_4_1,
+    "2_4_2" => main_2_4_2,
+    "2_4_3" => main_2_4_3,
+    "2_4_4" => main_2_4_4,
+    "2_4_5" => main_2_4_5,
+    "2_5_1" => main_2_5_1,
+    "2_5_2" => main_2_5_2,
+    "2_5_3" => main_2_5_3,
+    "2_5_4" => main_2_5_4,
+    "2_6_1" => main_2_6_1,
+    "2_6_2" => main_2_6_2,
+    "3_1_1" => main_3_1_1,
+    "4_1_1" => main_4_1_1,
+    "4_1_2" => main_4_1_2,
+    "4_2_1" => main_4_2_1,
+    "4_3_1" => main_4_3_1,
+    "4_3_2" => main_4_3_2,
+    "4_4_1" => main_4_4_1,
+    "4_5_1" => main_4_5_1,
+    "4_5_2" => main_4_5_2,
+    "4_5_3" => main_4_5_3,
+    "4_6_1" => main_4_6_1,
+    "4_6_2" => main_4_6_2,
+    "4_8_1" => main_4_8_1,
+    "4_9_1" => main_4_9_1,
+    "4_9_2" => main_4_9_2,
+    "4_9_3" => main_4_9_3,
+    "4_10_1" => main_4_10_1,
+    "4_10_2" => main_4_10_2,
+    "4_10_3" => main_4_10_3,
+    "4_11_1" => main_4_11_1,
+    "4_11_2" => main_4_11_2,
+    "5_1_1" => main_5_1_1,
+    "5_2_1" => main_5_2_1,
+    "5_3_1" => main_5_3_1,
+    "5_3_2" => main_5_3_2,
+    "5_3_3" => main_5_3_3,
+    "5_3_4" => main_5_3_4,
+    "5_3_5" => main_5_3_5,
+    "5_4_1" => main_5_4_1,
+    "5_5_1" => main_5_5_1,
+    "5_6_1" => main_5_6_1,
+    "5_7_1" => main_5_7_1,
+    "5_8_1" => main_5_8_1,
+    "5_9_1" => main_5_9_1,
+    "8_1_1" => main_8_1_1,
+    "8_1_2" => main_8_1_2,
+}
+
+/// Runs a tutorial for `frames` frames without opening a window, see `window::Backend::Headless`.
+/// Returns the last frame, or `None` if nothing was rendered.
+#[allow(clippy::missing_safety_doc)]
+#[cfg(not(target_arch = "wasm32"))]
+pub async unsafe fn run_tutorial_headless(
+    tutorial_id: String,
+    frames: u32,
+) -> Option<image::RgbImage> {
+    window::set_backend_override(Some(window::Backend::Headless { frames }));
+    run_tutorial(tutorial_id).await;
+    window::set_backend_override(None);
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "macos")] {
+            None
+        } else {
+            headless::take_last_frame()
+        }
+    }
+}
+
+/// Runs a tutorial with time locked to a fixed step, writing out every frame.
+/// Renders without a window if `headless_frames` is set, for `recording.frames` if those are.
 #[allow(clippy::missing_safety_doc)]
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-pub async unsafe fn run_tutorial(tutorial_id: String) {
-    log::info!("Running tutorial {}", tutorial_id);
-    match tutorial_id.as_str() {
-        "1_1_1" => main_1_1_1().await,
-        "1_1_2" => main_1_1_2().await,
-        "1_2_1" => main_1_2_1().await,
-        "1_2_2" => main_1_2_2().await,
-        "1_2_3" => main_1_2_3().await,
-        "1_2_4" => main_1_2_4().await,
-        "1_2_5" => main_1_2_5().await,
-        "1_3_1" => main_1_3_1().await,
-        "1_3_2" => main_1_3_2().await,
-        "1_3_3" => main_1_3_3().await,
-        "1_3_4" => main_1_3_4().await,
-        "1_3_5" => main_1_3_5().await,
-        "1_3_6" => main_1_3_6().await,
-        "1_4_1" => main_1_4_1().await,
-        "1_4_2" => main_1_4_2().await,
-        "1_4_3" => main_1_4_3().await,
-        "1_4_4" => main_1_4_4().await,
-        "1_4_5" => main_1_4_5().await,
-        "1_4_6" => main_1_4_6().await,
-        "1_5_1" => main_1_5_1().await,
-        "1_5_2" => main_1_5_2().await,
-        "1_5_3" => main_1_5_3().await,
-        "1_6_1" => main_1_6_1().await,
-        "1_6_2" => main_1_6_2().await,
-        "1_6_3" => main_1_6_3().await,
-        "1_6_4" => main_1_6_4().await,
-        "1_7_1" => main_1_7_1().await,
-        "1_7_2" => main_1_7_2().await,
-        "1_7_3" => main_1_7_3().await,
-        "1_7_4" => main_1_7_4().await,
-        "1_7_5" => main_1_7_5().await,
-        "1_7_6" => main_1_7_6().await,
-        "2_1_1" => main_2_1_1().await,
-        "2_2_1" => main_2_2_1().await,
-        "2_2_2" => main_2_2_2().await,
-        "2_2_3" => main_2_2_3().await,
-        "2_2_4" => main_2_2_4().await,
-        "2_2_5" => main_2_2_5().await,
-        "2_3_1" => main_2_3_1().await,
-        "2_3_2" => main_2_3_2().await,
-        "2_4_1" => main_2_4_1().await,
-        "2_4_2" => main_2_4_2().await,
-        "2_4_3" => main_2_4_3().await,
-        "2_4_4" => main_2_4_4().await,
-        "2_4_5" => main_2_4_5().await,
-        "2_5_1" => main_2_5_1().await,
-        "2_5_2" => main_2_5_2().await,
-        "2_5_3" => main_2_5_3().await,
-        "2_5_4" => main_2_5_4().await,
-        "2_6_1" => main_2_6_1().await,
-        "2_6_2" => main_2_6_2().await,
-        "3_1_1" => main_3_1_1().await,
-        "4_1_1" => main_4_1_1().await,
-        "4_1_2" => main_4_1_2().await,
-        "4_2_1" => main_4_2_1().await,
-        "4_3_1" => main_4_3_1().await,
-        "4_3_2" => main_4_3_2().await,
-        "4_4_1" => main_4_4_1().await,
-        "4_5_1" => main_4_5_1().await,
-        "4_5_2" => main_4_5_2().await,
-        "4_5_3" => main_4_5_3().await,
-        "4_6_1" => main_4_6_1().await,
-        "4_6_2" => main_4_6_2().await,
-        "4_8_1" => main_4_8_1().await,
-        "4_9_1" => main_4_9_1().await,
-        "4_9_2" => main_4_9_2().await,
-        "4_9_3" => main_4_9_3().await,
-        "4_10_1" => main_4_10_1().await,
-        "4_10_2" => main_4_10_2().await,
-        "4_10_3" => main_4_10_3().await,
-        "4_11_1" => main_4_11_1().await,
-        "5_1_1" => main_5_1_1().await,
-        "5_2_1" => main_5_2_1().await,
-        "5_3_1" => main_5_3_1().await,
-        "5_3_2" => main_5_3_2().await,
-        "5_3_3" => main_5_3_3().await,
-        _ => log::error!("Unknown tutorial id: {}", tutorial_id),
+#[cfg(not(target_arch = "wasm32"))]
+pub async unsafe fn run_tutorial_recording(
+    tutorial_id: String,
+    recording: recording::Recording,
+    headless_frames: Option<u32>,
+) {
+    window::set_recording_override(Some(recording));
+    match headless_frames {
+        Some(frames) => {
+            run_tutorial_headless(tutorial_id, frames).await;
+        }
+        None => run_tutorial(tutorial_id).await,
     }
+    window::set_recording_override(None);
 }
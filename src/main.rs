@@ -1,19 +1,120 @@
-use lib_learn_opengl_rs::run_tutorial;
+use lib_learn_opengl_rs::recording::{Recording, RecordingOutput};
+use lib_learn_opengl_rs::{run_tutorial, run_tutorial_headless, run_tutorial_recording};
 use std::env;
 
 fn main() {
     env_logger::init();
 
     let args = env::args().collect::<Vec<String>>();
-    if args.len() != 2 {
-        println!(
-            "Call with the number of the tutorial, e.g. `1_1_2` for _1_2_hello_window_clear.rs"
-        );
-        std::process::exit(1);
+    if args.get(1).map(String::as_str) == Some("golden") {
+        golden(&args[2..]);
+    }
+
+    let Some(tutorial_id) = args.get(1) else {
+        usage();
+    };
+    let mut headless_frames = None;
+    let mut output = None;
+    let mut record_frames = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        let mut value = || options.next().cloned().unwrap_or_else(|| usage());
+        match option.as_str() {
+            "--headless" => {
+                headless_frames = Some(value().parse::<u32>().unwrap_or_else(|_| usage()))
+            }
+            "--record" => output = Some(RecordingOutput::ImageSequence(value().into())),
+            // the encoder and its arguments are the rest of the command line after `--`, passed
+            // on as they are so quoted arguments with spaces survive
+            "--encode" => {
+                if options.next().map(String::as_str) != Some("--") {
+                    usage();
+                }
+                let command = options.next().cloned().unwrap_or_else(|| usage());
+                output = Some(RecordingOutput::Encoder {
+                    command,
+                    args: options.by_ref().cloned().collect(),
+                });
+            }
+            "--frames" => record_frames = Some(value().parse::<u32>().unwrap_or_else(|_| usage())),
+            _ => usage(),
+        }
     }
 
-    let tutorial_id = &args[1];
     unsafe {
-        pollster::block_on(run_tutorial(tutorial_id.to_string()));
+        match (output, headless_frames) {
+            (Some(output), headless_frames) => {
+                // `--frames` decides how long to record, `--headless` alone also sets it
+                let recording = Recording {
+                    output,
+                    frames: record_frames.or(headless_frames),
+                };
+                pollster::block_on(run_tutorial_recording(
+                    tutorial_id.to_string(),
+                    recording,
+                    headless_frames,
+                ));
+            }
+            (None, Some(frames)) => {
+                pollster::block_on(run_tutorial_headless(tutorial_id.to_string(), frames));
+            }
+            (None, None) => pollster::block_on(run_tutorial(tutorial_id.to_string())),
+        }
+    }
+}
+
+/// `golden [--update] [--frames <n>] [--tolerance <n>] [tutorial ids...]`, all tutorials by default
+#[cfg(not(target_os = "macos"))]
+fn golden(args: &[String]) -> ! {
+    use lib_learn_opengl_rs::golden::{check_all, default_tutorial_ids, GoldenOptions};
+
+    let mut options = GoldenOptions::builder().build();
+    let mut ids = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().map(String::as_str).unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--update" => options.update = true,
+            "--frames" => options.frames = value().parse().unwrap_or_else(|_| usage()),
+            // per channel, so anything above 255 is rejected rather than wrapped
+            "--tolerance" => options.tolerance = value().parse().unwrap_or_else(|_| usage()),
+            id => ids.push(id),
+        }
+    }
+    if ids.is_empty() {
+        ids = default_tutorial_ids();
     }
+
+    let failures = unsafe { check_all(&ids, &options) };
+    if failures.is_empty() {
+        println!("{} tutorials match their golden images", ids.len());
+        std::process::exit(0);
+    }
+    println!(
+        "{} of {} tutorials differ from their golden images: {}. See {}",
+        failures.len(),
+        ids.len(),
+        failures.join(", "),
+        options.output_dir.display()
+    );
+    std::process::exit(1);
+}
+
+#[cfg(target_os = "macos")]
+fn golden(_args: &[String]) -> ! {
+    println!("Golden images are rendered headless through EGL, which is not available on macOS");
+    std::process::exit(1);
+}
+
+fn usage() -> ! {
+    println!("Call with the number of the tutorial, e.g. `1_1_2` for _1_2_hello_window_clear.rs");
+    println!("Add `--headless <frames>` to render that many frames offscreen without a window");
+    println!(
+        "Add `--record <dir>` or, last, `--encode -- <command> [args...]` to record every frame"
+    );
+    println!("at a fixed time step, and `--frames <n>` to stop after n frames, also when headless");
+    println!(
+        "Or `golden [--update] [--frames <n>] [--tolerance <n>] [ids...]` to check golden images"
+    );
+    std::process::exit(1);
 }
@@ -1,8 +1,12 @@
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray, SharedGl};
 use crate::shader::MyShader;
 use crate::texture::{map_texture_type_to_string, Texture, TextureType};
+use crate::uniform::MissingUniformPolicy;
 use bytemuck::{offset_of, Pod, Zeroable};
-use glow::{Buffer, Context, HasContext, VertexArray};
+use glow::{Context, HasContext};
 use nalgebra_glm as glm;
+use std::cell::RefCell;
+use std::collections::HashSet;
 
 #[repr(C)]
 #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
@@ -10,6 +14,20 @@ pub struct Vertex {
     pub position: glm::Vec3,
     pub normal: glm::Vec3,
     pub tex_coords: glm::Vec2,
+    /// texture space u and v directions, see `tangent::generate_mikktspace_tangents`
+    pub tangent: glm::Vec3,
+    pub bitangent: glm::Vec3,
+}
+
+impl Vertex {
+    /// (location, type) of the attributes `Mesh` sets up
+    pub const LAYOUT: [(u32, u32); 5] = [
+        (0, glow::FLOAT_VEC3),
+        (1, glow::FLOAT_VEC3),
+        (2, glow::FLOAT_VEC2),
+        (3, glow::FLOAT_VEC3),
+        (4, glow::FLOAT_VEC3),
+    ];
 }
 
 #[derive(Debug)]
@@ -24,25 +42,25 @@ pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
     pub material_id: usize,
-    pub vao: VertexArray,
-    pub vbo: Buffer,
-    pub ebo: Buffer,
+    pub vao: OwnedVertexArray,
+    pub vbo: OwnedBuffer,
+    pub ebo: OwnedBuffer,
+    /// `MyShader::source_hash` of the programs whose attributes were already checked against
+    /// `Vertex::LAYOUT`. Program ids are reused once a hot reloaded program is deleted
+    checked_programs: RefCell<HashSet<String>>,
 }
 
 impl Mesh {
     pub fn new(
-        gl: &Context,
+        gl: &SharedGl,
         name: &str,
         vertices: Vec<Vertex>,
         indices: Vec<u32>,
         material_id: usize,
     ) -> Self {
-        let vao = unsafe {
-            gl.create_vertex_array()
-                .expect("Cannot create vertex array")
-        };
-        let vbo = unsafe { gl.create_buffer().expect("Cannot create buffer") };
-        let ebo = unsafe { gl.create_buffer().expect("Cannot create buffer") };
+        let vao = OwnedVertexArray::create_vertex_array(gl, name);
+        let vbo = OwnedBuffer::create_buffer(gl, name);
+        let ebo = OwnedBuffer::create_buffer(gl, name);
         let mut mesh = Mesh {
             name: name.to_string(),
             vertices,
@@ -51,6 +69,7 @@ impl Mesh {
             vao,
             vbo,
             ebo,
+            checked_programs: Default::default(),
         };
         mesh.setup_mesh(gl);
         mesh
@@ -58,16 +77,16 @@ impl Mesh {
 
     fn setup_mesh(&mut self, gl: &Context) {
         unsafe {
-            gl.bind_vertex_array(Some(self.vao));
+            gl.bind_vertex_array(Some(self.vao.raw()));
 
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo.raw()));
             gl.buffer_data_u8_slice(
                 glow::ARRAY_BUFFER,
                 bytemuck::cast_slice(&self.vertices),
                 glow::STATIC_DRAW,
             );
 
-            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.ebo));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.ebo.raw()));
             gl.buffer_data_u8_slice(
                 glow::ELEMENT_ARRAY_BUFFER,
                 bytemuck::cast_slice(&self.indices),
@@ -106,6 +125,26 @@ impl Mesh {
                 offset_of!(Vertex, tex_coords) as i32,
             );
 
+            gl.enable_vertex_attrib_array(3);
+            gl.vertex_attrib_pointer_f32(
+                3,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                offset_of!(Vertex, tangent) as i32,
+            );
+
+            gl.enable_vertex_attrib_array(4);
+            gl.vertex_attrib_pointer_f32(
+                4,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                offset_of!(Vertex, bitangent) as i32,
+            );
+
             gl.bind_vertex_array(None);
         }
     }
@@ -144,11 +183,12 @@ impl Mesh {
                     } // _ => panic!("Unknown texture type"),
                 };
                 // shader.set_int(gl, &format!("material.{}", name), i as i32);
-                shader.try_set_int(gl, &name, i as i32);
+                shader.set_with_policy(gl, &name, &(i as i32), MissingUniformPolicy::Ignore);
                 gl.bind_texture(glow::TEXTURE_2D, Some(texture.raw()));
             }
 
-            gl.bind_vertex_array(Some(self.vao));
+            gl.bind_vertex_array(Some(self.vao.raw()));
+            self.check_layout(shader);
             gl.draw_elements(
                 glow::TRIANGLES,
                 self.indices.len() as i32,
@@ -161,11 +201,14 @@ impl Mesh {
         }
     }
 
-    pub fn delete(&self, gl: &Context) {
-        unsafe {
-            gl.delete_vertex_array(self.vao);
-            gl.delete_buffer(self.vbo);
-            gl.delete_buffer(self.ebo);
+    /// warns once per program about attributes the vertex array does not match
+    unsafe fn check_layout(&self, shader: &MyShader) {
+        let mut checked = self.checked_programs.borrow_mut();
+        if !checked.insert(shader.source_hash().to_string()) {
+            return;
+        }
+        for problem in shader.reflection().check_vertex_layout(&Vertex::LAYOUT) {
+            log::warn!("Mesh {}: {}", self.name, problem);
         }
     }
 }
@@ -14,10 +14,4 @@ impl Model {
             mesh.draw(gl, &self.materials, shader);
         }
     }
-
-    pub fn delete(&self, gl: &Context) {
-        for mesh in &self.meshes {
-            mesh.delete(gl);
-        }
-    }
 }
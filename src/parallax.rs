@@ -0,0 +1,148 @@
+use crate::shader::MyShader;
+use glow::Context;
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// `MAX_PARALLAX_LAYERS` in `common/parallax.glsl`
+pub const MAX_PARALLAX_LAYERS: u32 = 64;
+
+/// How `common/parallax.glsl` offsets the texture coordinates, in the order of its
+/// `parallaxMethod`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ParallaxMethod {
+    /// the texture coordinates as they are, only normal mapped
+    Off,
+    /// one offset along the view direction by the height at the fragment
+    Basic,
+    /// steps through `max_layers` depth layers until one is below the height map
+    Steep,
+    /// steep parallax with a layer count adapted to the view angle, interpolating between the
+    /// layers above and below the height map
+    #[default]
+    Occlusion,
+}
+
+impl ParallaxMethod {
+    pub const ALL: [ParallaxMethod; 4] = [
+        ParallaxMethod::Off,
+        ParallaxMethod::Basic,
+        ParallaxMethod::Steep,
+        ParallaxMethod::Occlusion,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&m| m == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for ParallaxMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParallaxMethod::Off => write!(f, "Off"),
+            ParallaxMethod::Basic => write!(f, "Parallax mapping"),
+            ParallaxMethod::Steep => write!(f, "Steep parallax mapping"),
+            ParallaxMethod::Occlusion => write!(f, "Parallax occlusion mapping"),
+        }
+    }
+}
+
+/// The uniforms of `common/parallax.glsl`
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ParallaxSettings {
+    #[builder(default)]
+    pub method: ParallaxMethod,
+    /// depth of the lowest point of the height map, in texture coordinates
+    #[builder(default = 0.1)]
+    pub height_scale: f32,
+    /// layers when looking straight at the surface, for parallax occlusion mapping
+    #[builder(default = 8)]
+    pub min_layers: u32,
+    /// layers at grazing angles, and always for steep parallax mapping
+    #[builder(default = 32)]
+    pub max_layers: u32,
+    /// whether the height map shadows itself, traced towards the light
+    #[builder(default = false)]
+    pub self_shadow: bool,
+}
+
+impl ParallaxSettings {
+    /// sets the uniforms of `common/parallax.glsl` on `shader`, which has to be in use
+    pub fn set_uniforms(&self, gl: &Context, shader: &MyShader) {
+        let max_layers = self.max_layers.clamp(1, MAX_PARALLAX_LAYERS);
+        shader.set_int(gl, "parallaxMethod", self.method as i32);
+        shader.set_float(gl, "heightScale", self.height_scale);
+        shader.set_float(
+            gl,
+            "parallaxMinLayers",
+            self.min_layers.clamp(1, max_layers) as f32,
+        );
+        shader.set_float(gl, "parallaxMaxLayers", max_layers as f32);
+        shader.set_bool(gl, "parallaxSelfShadow", self.self_shadow);
+    }
+
+    /// Method, height scale, layer counts and self-shadowing
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Method")
+            .selected_text(self.method.to_string())
+            .show_ui(ui, |ui| {
+                for method in ParallaxMethod::ALL {
+                    ui.selectable_value(&mut self.method, method, method.to_string());
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.height_scale, 0.0..=0.3).text("Height scale"));
+        if self.method == ParallaxMethod::Occlusion {
+            ui.add(
+                egui::Slider::new(&mut self.min_layers, 1..=MAX_PARALLAX_LAYERS).text("Min layers"),
+            );
+        }
+        if matches!(
+            self.method,
+            ParallaxMethod::Steep | ParallaxMethod::Occlusion
+        ) {
+            ui.add(
+                egui::Slider::new(&mut self.max_layers, 1..=MAX_PARALLAX_LAYERS).text("Max layers"),
+            );
+        }
+        ui.checkbox(&mut self.self_shadow, "Self-shadowing");
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        for method in ParallaxMethod::ALL {
+            if ui
+                .radio_button_config(method.to_string(), self.method == method)
+                .build()
+            {
+                self.method = method;
+            }
+        }
+        ui.slider_float_config("Height scale", &mut self.height_scale)
+            .range(0.0, 0.3)
+            .build();
+        let mut min_layers = self.min_layers as i32;
+        if self.method == ParallaxMethod::Occlusion
+            && ui
+                .slider_int_config("Min layers", &mut min_layers)
+                .range(1, MAX_PARALLAX_LAYERS as i32)
+                .build()
+        {
+            self.min_layers = min_layers as u32;
+        }
+        let mut max_layers = self.max_layers as i32;
+        if matches!(
+            self.method,
+            ParallaxMethod::Steep | ParallaxMethod::Occlusion
+        ) && ui
+            .slider_int_config("Max layers", &mut max_layers)
+            .range(1, MAX_PARALLAX_LAYERS as i32)
+            .build()
+        {
+            self.max_layers = max_layers as u32;
+        }
+        ui.checkbox_config("Self-shadowing", &mut self.self_shadow)
+            .build();
+    }
+}
@@ -0,0 +1,388 @@
+use crate::framebuffer::{FramebufferError, FramebufferStatus};
+use crate::gl_object::{OwnedFramebuffer, OwnedTexture, SharedGl};
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// How the six faces of the depth cubemap are rendered
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PointShadowMethod {
+    /// one pass, a geometry shader emits every triangle to all faces with `gl_Layer`. Desktop
+    /// only
+    GeometryShader,
+    /// one pass per face, as WebGL2 has no geometry shaders
+    SixPass,
+}
+
+impl PointShadowMethod {
+    pub const ALL: [PointShadowMethod; 2] = [
+        PointShadowMethod::GeometryShader,
+        PointShadowMethod::SixPass,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&m| m == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// whether `gl` can render with this method
+    pub fn supported(self, gl: &Context) -> bool {
+        match self {
+            PointShadowMethod::GeometryShader => !gl.version().is_embedded,
+            PointShadowMethod::SixPass => true,
+        }
+    }
+}
+
+impl Default for PointShadowMethod {
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            PointShadowMethod::SixPass
+        } else {
+            PointShadowMethod::GeometryShader
+        }
+    }
+}
+
+impl fmt::Display for PointShadowMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointShadowMethod::GeometryShader => write!(f, "Geometry shader"),
+            PointShadowMethod::SixPass => write!(f, "Six passes"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PointShadowSettings {
+    #[builder(default)]
+    pub method: PointShadowMethod,
+    /// width and height of each face
+    #[builder(default = 1024)]
+    pub resolution: u32,
+    #[builder(default = 1.0)]
+    pub near_plane: f32,
+    /// distance from the light beyond which nothing casts shadows, the depths are divided by it
+    #[builder(default = 25.0)]
+    pub far_plane: f32,
+    /// in world units, subtracted from the distance to the light against acne
+    #[builder(default = 0.15)]
+    pub bias: f32,
+    /// 20 samples around the fragment instead of one
+    #[builder(default = true)]
+    pub pcf: bool,
+}
+
+/// Omnidirectional shadows of a point light
+///
+/// The scene is rendered into the six faces of a depth cubemap looking out from the light,
+/// each storing the linear distance to the light divided by `far_plane` rather than the
+/// projected depth. Shaders sample it with the light to fragment vector through
+/// `common/point_shadows.glsl`.
+pub struct PointShadowMap {
+    pub settings: PointShadowSettings,
+    /// the cubemap faces are this large
+    allocated: u32,
+    depth_cubemap: OwnedTexture,
+    fbo: OwnedFramebuffer,
+    /// `None` where geometry shaders are not supported
+    layered_shader: Option<MyShader>,
+    six_pass_shader: MyShader,
+    debug_shader: MyShader,
+    quad: FullscreenQuad,
+}
+
+impl PointShadowMap {
+    pub fn new(
+        gl: &SharedGl,
+        settings: PointShadowSettings,
+        shader_version: Option<&str>,
+    ) -> Result<Self, PostProcessError> {
+        let layered_shader = if PointShadowMethod::GeometryShader.supported(gl) {
+            let mut shader = MyShader::new_with_geometry_from_source(
+                gl,
+                include_str!("shaders/shadow/point_shadow_depth_layered.vs"),
+                include_str!("shaders/shadow/point_shadow_depth.fs"),
+                include_str!("shaders/shadow/point_shadow_depth.gs"),
+                shader_version,
+            )?;
+            shader.set_name("point shadow depth (geometry shader)");
+            Some(shader)
+        } else {
+            None
+        };
+        let mut six_pass_shader = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/shadow/point_shadow_depth.vs"),
+            include_str!("shaders/shadow/point_shadow_depth.fs"),
+            shader_version,
+        )?;
+        six_pass_shader.set_name("point shadow depth");
+        let debug_shader = fullscreen_shader(
+            gl,
+            "point shadow debug",
+            include_str!("shaders/shadow/point_shadow_debug.fs"),
+            shader_version,
+        )?;
+
+        let label = "point shadow map";
+        let depth_cubemap = OwnedTexture::create_texture(gl, label);
+        let fbo = OwnedFramebuffer::create_framebuffer(gl, label);
+        let mut shadow_map = Self {
+            allocated: settings.resolution,
+            settings,
+            depth_cubemap,
+            fbo,
+            layered_shader,
+            six_pass_shader,
+            debug_shader,
+            quad: FullscreenQuad::new(gl),
+        };
+        shadow_map.allocate(gl);
+
+        // only a depth attachment
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(shadow_map.fbo.raw()));
+            shadow_map.attach_face(gl, 0);
+            gl.draw_buffers(&[glow::NONE]);
+            gl.read_buffer(glow::NONE);
+            let status = FramebufferStatus::from_gl(gl.check_framebuffer_status(glow::FRAMEBUFFER));
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if let Some(status) = status {
+                return Err(FramebufferError::Incomplete {
+                    label: label.to_string(),
+                    status,
+                }
+                .into());
+            }
+        }
+        Ok(shadow_map)
+    }
+
+    /// (Re)specifies the six faces at `settings.resolution`
+    fn allocate(&mut self, gl: &Context) {
+        let size = self.settings.resolution.max(1) as i32;
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, Some(self.depth_cubemap.raw()));
+            for face in 0..6 {
+                gl.tex_image_2d(
+                    glow::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    glow::DEPTH_COMPONENT24 as i32,
+                    size,
+                    size,
+                    0,
+                    glow::DEPTH_COMPONENT,
+                    glow::UNSIGNED_INT,
+                    None,
+                );
+            }
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::NEAREST),
+                (glow::TEXTURE_MAG_FILTER, glow::NEAREST),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_R, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_CUBE_MAP, parameter, value as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, None);
+        }
+        self.allocated = self.settings.resolution;
+    }
+
+    unsafe fn attach_face(&self, gl: &Context, face: u32) {
+        gl.framebuffer_texture_2d(
+            glow::FRAMEBUFFER,
+            glow::DEPTH_ATTACHMENT,
+            glow::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+            Some(self.depth_cubemap.raw()),
+            0,
+        );
+    }
+
+    /// binds the depth cubemap to texture unit `unit`, leaving it active
+    pub fn bind_texture(&self, gl: &Context, unit: u32) {
+        unsafe {
+            gl.active_texture(glow::TEXTURE0 + unit);
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, Some(self.depth_cubemap.raw()));
+        }
+    }
+
+    /// The method that is actually used, `SixPass` when `settings.method` is not supported
+    pub fn method(&self) -> PointShadowMethod {
+        match self.layered_shader {
+            Some(_) => self.settings.method,
+            None => PointShadowMethod::SixPass,
+        }
+    }
+
+    /// projection and view of each cubemap face, in the order of
+    /// `GL_TEXTURE_CUBE_MAP_POSITIVE_X + i`
+    pub fn face_matrices(&self, light_pos: &glm::Vec3) -> [glm::Mat4; 6] {
+        let shadow_proj = glm::perspective(
+            1.0,
+            90.0_f32.to_radians(),
+            self.settings.near_plane,
+            self.settings.far_plane,
+        );
+        let face = |direction: glm::Vec3, up: glm::Vec3| {
+            shadow_proj * glm::look_at(light_pos, &(light_pos + direction), &up)
+        };
+        [
+            face(glm::vec3(1.0, 0.0, 0.0), glm::vec3(0.0, -1.0, 0.0)),
+            face(glm::vec3(-1.0, 0.0, 0.0), glm::vec3(0.0, -1.0, 0.0)),
+            face(glm::vec3(0.0, 1.0, 0.0), glm::vec3(0.0, 0.0, 1.0)),
+            face(glm::vec3(0.0, -1.0, 0.0), glm::vec3(0.0, 0.0, -1.0)),
+            face(glm::vec3(0.0, 0.0, 1.0), glm::vec3(0.0, -1.0, 0.0)),
+            face(glm::vec3(0.0, 0.0, -1.0), glm::vec3(0.0, -1.0, 0.0)),
+        ]
+    }
+
+    /// Renders the distances to the light at `light_pos` into the cubemap. `draw_scene` draws
+    /// the shadow casters with the given shader in use, setting its `model` matrix; it is
+    /// called once, or once per face for `PointShadowMethod::SixPass`. The default framebuffer
+    /// is bound afterwards, with the viewport still at the face size.
+    pub fn render(
+        &mut self,
+        gl: &Context,
+        light_pos: &glm::Vec3,
+        mut draw_scene: impl FnMut(&MyShader),
+    ) {
+        if self.allocated != self.settings.resolution {
+            self.allocate(gl);
+        }
+        let matrices = self.face_matrices(light_pos);
+        let size = self.settings.resolution.max(1) as i32;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.fbo.raw()));
+            gl.viewport(0, 0, size, size);
+            gl.enable(glow::DEPTH_TEST);
+            match (&self.layered_shader, self.method()) {
+                (Some(shader), PointShadowMethod::GeometryShader) => {
+                    // all faces at once
+                    gl.framebuffer_texture(
+                        glow::FRAMEBUFFER,
+                        glow::DEPTH_ATTACHMENT,
+                        Some(self.depth_cubemap.raw()),
+                        0,
+                    );
+                    gl.clear(glow::DEPTH_BUFFER_BIT);
+                    shader.use_shader(gl);
+                    shader.set(gl, "shadowMatrices", &matrices);
+                    shader.set_float(gl, "far_plane", self.settings.far_plane);
+                    shader.set_vec3(gl, "lightPos", light_pos);
+                    draw_scene(shader);
+                }
+                _ => {
+                    let shader = &self.six_pass_shader;
+                    shader.use_shader(gl);
+                    shader.set_float(gl, "far_plane", self.settings.far_plane);
+                    shader.set_vec3(gl, "lightPos", light_pos);
+                    for (face, matrix) in matrices.iter().enumerate() {
+                        self.attach_face(gl, face as u32);
+                        gl.clear(glow::DEPTH_BUFFER_BIT);
+                        shader.set_mat4(gl, "shadowMatrix", matrix);
+                        draw_scene(shader);
+                    }
+                }
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Shows the six faces unfolded into a cross in the default framebuffer of `width` x
+    /// `height`, +Y on top and -Y below +Z, darker is closer to the light. Depth testing is
+    /// left disabled.
+    pub fn draw_debug(&self, gl: &Context, width: u32, height: u32) {
+        // (face, column, row from the bottom) of a 4 x 3 grid
+        const LAYOUT: [(i32, i32, i32); 6] = [
+            (0, 2, 1),
+            (1, 0, 1),
+            (2, 1, 2),
+            (3, 1, 0),
+            (4, 1, 1),
+            (5, 3, 1),
+        ];
+        let cell = (width as i32 / 4).min(height as i32 / 3);
+        let (x0, y0) = (
+            (width as i32 - 4 * cell) / 2,
+            (height as i32 - 3 * cell) / 2,
+        );
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.disable(glow::DEPTH_TEST);
+            gl.viewport(0, 0, width as i32, height as i32);
+            gl.clear_color(0.0, 0.0, 0.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            self.debug_shader.use_shader(gl);
+            self.debug_shader.set_int(gl, "depthMap", 0);
+            self.bind_texture(gl, 0);
+            for (face, column, row) in LAYOUT {
+                gl.viewport(x0 + column * cell, y0 + row * cell, cell, cell);
+                self.debug_shader.set_int(gl, "face", face);
+                self.quad.draw(gl);
+            }
+            gl.viewport(0, 0, width as i32, height as i32);
+        }
+    }
+
+    /// Method, resolution, far plane, bias and PCF
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let geometry_shader = self.layered_shader.is_some();
+        let settings = &mut self.settings;
+        egui::ComboBox::from_label("Method")
+            .selected_text(settings.method.to_string())
+            .show_ui(ui, |ui| {
+                for method in PointShadowMethod::ALL {
+                    let enabled = geometry_shader || method == PointShadowMethod::SixPass;
+                    ui.add_enabled_ui(enabled, |ui| {
+                        ui.selectable_value(&mut settings.method, method, method.to_string());
+                    });
+                }
+            });
+        ui.add(
+            egui::Slider::new(&mut settings.resolution, 64..=4096)
+                .logarithmic(true)
+                .text("Resolution"),
+        );
+        ui.add(egui::Slider::new(&mut settings.far_plane, 5.0..=100.0).text("Far plane"));
+        ui.add(egui::Slider::new(&mut settings.bias, 0.0..=1.0).text("Bias"));
+        ui.checkbox(&mut settings.pcf, "PCF");
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let geometry_shader = self.layered_shader.is_some();
+        let settings = &mut self.settings;
+        for method in PointShadowMethod::ALL {
+            if (geometry_shader || method == PointShadowMethod::SixPass)
+                && ui
+                    .radio_button_config(method.to_string(), settings.method == method)
+                    .build()
+            {
+                settings.method = method;
+            }
+        }
+        let mut resolution = settings.resolution as i32;
+        if ui
+            .slider_int_config("Resolution", &mut resolution)
+            .range(64, 4096)
+            .build()
+        {
+            settings.resolution = resolution as u32;
+        }
+        ui.slider_float_config("Far plane", &mut settings.far_plane)
+            .range(5.0, 100.0)
+            .build();
+        ui.slider_float_config("Bias", &mut settings.bias)
+            .range(0.0, 1.0)
+            .build();
+        ui.checkbox_config("PCF", &mut settings.pcf).build();
+    }
+}
@@ -0,0 +1,368 @@
+use crate::framebuffer::{ColorFormat, Framebuffer, FramebufferDesc, FramebufferError};
+use crate::gl_object::{OwnedBuffer, OwnedVertexArray, SharedGl};
+use crate::shader::MyShader;
+use crate::shader_error::ShaderError;
+use glow::{Context, HasContext};
+use std::mem::size_of;
+use std::ops::RangeInclusive;
+
+/// vertex shader of every pass, passes `TexCoords` to the fragment shader
+pub const FULLSCREEN_VS: &str = include_str!("shaders/post/fullscreen.vs");
+
+#[rustfmt::skip]
+const QUAD_VERTICES: [f32; 16] = [
+    // positions   // texture Coords
+    -1.0,  1.0,  0.0, 1.0,
+    -1.0, -1.0,  0.0, 0.0,
+    1.0,  1.0,  1.0, 1.0,
+    1.0, -1.0,  1.0, 0.0,
+];
+
+/// A program of `FULLSCREEN_VS` and `fragment_shader`, named `name` in the tweak panel
+pub fn fullscreen_shader(
+    gl: &SharedGl,
+    name: &str,
+    fragment_shader: &str,
+    shader_version: Option<&str>,
+) -> Result<MyShader, ShaderError> {
+    let mut shader = MyShader::new_from_source(gl, FULLSCREEN_VS, fragment_shader, shader_version)?;
+    shader.set_name(name);
+    Ok(shader)
+}
+
+/// A quad covering the screen, with the attributes `FULLSCREEN_VS` expects
+pub struct FullscreenQuad {
+    _vbo: OwnedBuffer,
+    vao: OwnedVertexArray,
+}
+
+impl FullscreenQuad {
+    pub fn new(gl: &SharedGl) -> Self {
+        let vbo = OwnedBuffer::create_buffer(gl, "fullscreen quad vbo");
+        let vao = OwnedVertexArray::create_vertex_array(gl, "fullscreen quad vao");
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo.raw()));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(&QUAD_VERTICES),
+                glow::STATIC_DRAW,
+            );
+            gl.bind_vertex_array(Some(vao.raw()));
+            let stride = 4 * size_of::<f32>() as i32;
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(0);
+            let offset = 2 * size_of::<f32>() as i32;
+            gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, stride, offset);
+            gl.enable_vertex_attrib_array(1);
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+        Self { _vbo: vbo, vao }
+    }
+
+    /// draws with the program in use
+    pub fn draw(&self, gl: &Context) {
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao.raw()));
+            gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+            gl.bind_vertex_array(None);
+        }
+    }
+}
+
+/// A float uniform of a pass, set before every run and edited with a slider
+#[derive(Debug, Clone)]
+pub struct PassParam {
+    pub name: String,
+    pub value: f32,
+    /// of the slider, only read by the UI
+    #[cfg_attr(
+        not(any(
+            all(not(target_arch = "wasm32"), feature = "egui-support"),
+            feature = "imgui-support"
+        )),
+        allow(dead_code)
+    )]
+    pub range: RangeInclusive<f32>,
+}
+
+/// A full-screen pass, its fragment shader samples the previous result from `screenTexture`
+pub struct PostProcessPass {
+    /// shown in the UI
+    #[cfg_attr(
+        not(any(
+            all(not(target_arch = "wasm32"), feature = "egui-support"),
+            feature = "imgui-support"
+        )),
+        allow(dead_code)
+    )]
+    name: String,
+    shader: MyShader,
+    pub enabled: bool,
+    pub params: Vec<PassParam>,
+}
+
+impl PostProcessPass {
+    /// Disabled until `enabled` is set
+    pub fn new(
+        gl: &SharedGl,
+        name: &str,
+        fragment_shader: &str,
+        shader_version: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        let shader = fullscreen_shader(gl, name, fragment_shader, shader_version)?;
+        shader.use_shader(gl);
+        shader.set_int(gl, "screenTexture", 0);
+        Ok(Self {
+            name: name.to_string(),
+            shader,
+            enabled: false,
+            params: Vec::new(),
+        })
+    }
+
+    pub fn with_param(mut self, name: &str, value: f32, range: RangeInclusive<f32>) -> Self {
+        self.params.push(PassParam {
+            name: name.to_string(),
+            value,
+            range,
+        });
+        self
+    }
+}
+
+/// Runs the enabled passes in order, each reading the result of the one before. Intermediate
+/// results go to two window sized framebuffers used in turn (ping-pong), the last pass writes
+/// to the output.
+pub struct PostProcessStack {
+    passes: Vec<PostProcessPass>,
+    /// used when no pass is enabled
+    copy: MyShader,
+    targets: [Framebuffer; 2],
+    quad: FullscreenQuad,
+}
+
+impl PostProcessStack {
+    /// `format` is the format of the intermediate results
+    pub fn new(
+        gl: &SharedGl,
+        format: ColorFormat,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        let copy = PostProcessPass::new(
+            gl,
+            "post process copy",
+            include_str!("shaders/post/copy.fs"),
+            shader_version,
+        )?
+        .shader;
+
+        let target = |label: &str| {
+            let desc = FramebufferDesc::builder()
+                .colors(vec![format])
+                .label(label)
+                .build();
+            Framebuffer::new(gl, desc, window_width, window_height)
+        };
+        let targets = [target("post process ping")?, target("post process pong")?];
+
+        let quad = FullscreenQuad::new(gl);
+
+        Ok(Self {
+            passes: Vec::new(),
+            copy,
+            targets,
+            quad,
+        })
+    }
+
+    pub fn push(&mut self, pass: PostProcessPass) {
+        self.passes.push(pass);
+    }
+
+    pub fn passes_mut(&mut self) -> &mut [PostProcessPass] {
+        &mut self.passes
+    }
+
+    /// Moves the pass at `index` one place earlier (`up`) or later in the chain
+    #[cfg(any(
+        all(not(target_arch = "wasm32"), feature = "egui-support"),
+        feature = "imgui-support"
+    ))]
+    pub fn move_pass(&mut self, index: usize, up: bool) {
+        let other = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < self.passes.len())
+        };
+        if let Some(other) = other {
+            self.passes.swap(index, other);
+        }
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        for target in &mut self.targets {
+            target.resize(gl, width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the chain on `input`, writing the result to `output` or the default framebuffer
+    /// of `width` x `height`. Depth testing is left disabled.
+    pub fn run(
+        &self,
+        gl: &Context,
+        input: glow::Texture,
+        output: Option<&Framebuffer>,
+        width: u32,
+        height: u32,
+    ) {
+        let enabled: Vec<&PostProcessPass> = self.passes.iter().filter(|p| p.enabled).collect();
+        unsafe {
+            gl.disable(glow::DEPTH_TEST);
+            gl.active_texture(glow::TEXTURE0);
+
+            if enabled.is_empty() {
+                self.bind_output(gl, output, width, height);
+                self.copy.use_shader(gl);
+                gl.bind_texture(glow::TEXTURE_2D, Some(input));
+                self.quad.draw(gl);
+            }
+
+            let mut source = input;
+            for (i, pass) in enabled.iter().enumerate() {
+                let target = &self.targets[i % 2];
+                if i + 1 == enabled.len() {
+                    self.bind_output(gl, output, width, height);
+                } else {
+                    target.bind(gl);
+                }
+                pass.shader.use_shader(gl);
+                for param in &pass.params {
+                    pass.shader.set_float(gl, &param.name, param.value);
+                }
+                gl.bind_texture(glow::TEXTURE_2D, Some(source));
+                self.quad.draw(gl);
+                source = target.color_texture(0).unwrap();
+            }
+        }
+    }
+
+    unsafe fn bind_output(
+        &self,
+        gl: &Context,
+        output: Option<&Framebuffer>,
+        width: u32,
+        height: u32,
+    ) {
+        match output {
+            Some(output) => output.bind(gl),
+            None => {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                gl.viewport(0, 0, width as i32, height as i32);
+            }
+        }
+    }
+
+    /// Toggles, order and parameters of the passes
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let mut moved = None;
+        let count = self.passes.len();
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut pass.enabled, &pass.name);
+                    if ui
+                        .add_enabled(i > 0, egui::Button::new("up").small())
+                        .clicked()
+                    {
+                        moved = Some((i, true));
+                    }
+                    if ui
+                        .add_enabled(i + 1 < count, egui::Button::new("down").small())
+                        .clicked()
+                    {
+                        moved = Some((i, false));
+                    }
+                });
+                for param in &mut pass.params {
+                    ui.add_enabled(
+                        pass.enabled,
+                        egui::Slider::new(&mut param.value, param.range.clone()).text(&param.name),
+                    );
+                }
+            });
+        }
+        if let Some((index, up)) = moved {
+            self.move_pass(index, up);
+        }
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let mut moved = None;
+        let count = self.passes.len();
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            ui.checkbox_config(format!("{}##{}", pass.name, i), &mut pass.enabled)
+                .build();
+            if i > 0 {
+                ui.same_line();
+                if ui.button_config(format!("up##{}", i)).build() {
+                    moved = Some((i, true));
+                }
+            }
+            if i + 1 < count {
+                ui.same_line();
+                if ui.button_config(format!("down##{}", i)).build() {
+                    moved = Some((i, false));
+                }
+            }
+            for param in &mut pass.params {
+                ui.slider_float_config(format!("{}##{}", param.name, i), &mut param.value)
+                    .range(*param.range.start(), *param.range.end())
+                    .build();
+            }
+        }
+        if let Some((index, up)) = moved {
+            self.move_pass(index, up);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PostProcessError {
+    Shader(ShaderError),
+    Framebuffer(FramebufferError),
+}
+
+impl std::fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostProcessError::Shader(e) => write!(f, "Post processing shader: {}", e),
+            PostProcessError::Framebuffer(e) => write!(f, "Post processing target: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PostProcessError {}
+
+impl From<ShaderError> for PostProcessError {
+    fn from(e: ShaderError) -> Self {
+        PostProcessError::Shader(e)
+    }
+}
+
+impl From<FramebufferError> for PostProcessError {
+    fn from(e: FramebufferError) -> Self {
+        PostProcessError::Framebuffer(e)
+    }
+}
@@ -0,0 +1,224 @@
+/// Files available to `#include`, embedded so includes resolve the same way on the web,
+/// where resources can only be loaded asynchronously.
+const EMBEDDED_INCLUDES: &[(&str, &str)] = &[
+    (
+        "common/cascaded_shadows.glsl",
+        include_str!("./shaders/common/cascaded_shadows.glsl"),
+    ),
+    (
+        "common/deferred.glsl",
+        include_str!("./shaders/common/deferred.glsl"),
+    ),
+    (
+        "common/lights.glsl",
+        include_str!("./shaders/common/lights.glsl"),
+    ),
+    (
+        "common/parallax.glsl",
+        include_str!("./shaders/common/parallax.glsl"),
+    ),
+    (
+        "common/point_shadows.glsl",
+        include_str!("./shaders/common/point_shadows.glsl"),
+    ),
+    (
+        "common/shadows.glsl",
+        include_str!("./shaders/common/shadows.glsl"),
+    ),
+    (
+        "common/soft_shadows.glsl",
+        include_str!("./shaders/common/soft_shadows.glsl"),
+    ),
+];
+
+/// Shader source with includes resolved and defines injected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preprocessed {
+    pub source: String,
+    /// file names, the top-level file first
+    pub files: Vec<String>,
+    /// (index into `files`, line number) each line of `source` comes from, `None` for the lines
+    /// added by the preprocessor. Not every driver reports the source string of `#line`, so
+    /// locations are mapped back with this table instead.
+    pub lines: Vec<Option<(usize, usize)>>,
+}
+
+/// Resolves `#include "path"` lines, against the embedded includes first and the resources on
+/// desktop, and injects `defines` as `#define NAME VALUE` right after `#version`.
+/// `name` is used for the top-level file in error messages and `Preprocessed::location`.
+pub fn preprocess(
+    source: &str,
+    name: &str,
+    defines: &[(&str, &str)],
+) -> Result<Preprocessed, String> {
+    preprocess_with(source, name, defines, &load_include)
+}
+
+/// `preprocess` with `load` resolving the includes
+fn preprocess_with(
+    source: &str,
+    name: &str,
+    defines: &[(&str, &str)],
+    load: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<Preprocessed, String> {
+    let mut preprocessed = Preprocessed {
+        source: String::new(),
+        files: vec![name.to_string()],
+        lines: Vec::new(),
+    };
+    let mut lines = source.lines().enumerate().peekable();
+    if let Some((_, version)) = lines.next_if(|(_, line)| line.trim_start().starts_with("#version"))
+    {
+        preprocessed.push_line(version, Some((0, 1)));
+    }
+    for (define, value) in defines {
+        preprocessed.push_line(&format!("#define {} {}", define, value), None);
+    }
+
+    let mut stack = vec![name.to_string()];
+    expand(lines, 0, &mut stack, &mut preprocessed, load)?;
+    Ok(preprocessed)
+}
+
+fn expand<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+    file_index: usize,
+    stack: &mut Vec<String>,
+    out: &mut Preprocessed,
+    load: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<(), String> {
+    for (i, line) in lines {
+        let Some(rest) = line.trim_start().strip_prefix("#include") else {
+            out.push_line(line, Some((file_index, i + 1)));
+            continue;
+        };
+        let location = format!("{}:{}", out.files[file_index], i + 1);
+        let path = rest
+            .trim()
+            .strip_prefix('"')
+            .and_then(|p| p.strip_suffix('"'))
+            .ok_or_else(|| format!("{}: expected #include \"file\"", location))?;
+        if stack.iter().any(|p| p == path) {
+            return Err(format!(
+                "{}: #include cycle {} -> {}",
+                location,
+                stack.join(" -> "),
+                path
+            ));
+        }
+        let included = load(path).map_err(|e| format!("{}: {}", location, e))?;
+
+        let included_index = out.files.len();
+        out.files.push(path.to_string());
+        stack.push(path.to_string());
+        expand(
+            included.lines().enumerate(),
+            included_index,
+            stack,
+            out,
+            load,
+        )?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+fn load_include(path: &str) -> Result<String, String> {
+    if let Some((_, source)) = EMBEDDED_INCLUDES.iter().find(|(p, _)| *p == path) {
+        return Ok(source.to_string());
+    }
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            Err(format!("cannot include {}, only embedded includes are available on the web", path))
+        } else {
+            let file = std::path::Path::new(env!("OUT_DIR")).join("resources").join(path);
+            std::fs::read_to_string(&file).map_err(|e| format!("cannot include {}: {}", path, e))
+        }
+    }
+}
+
+impl Preprocessed {
+    fn push_line(&mut self, line: &str, origin: Option<(usize, usize)>) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.lines.push(origin);
+    }
+
+    /// Keeps the line table in sync when a line is inserted into `source` after preprocessing
+    pub fn insert_generated_line(&mut self, index: usize) {
+        self.lines.insert(index, None);
+    }
+
+    /// file and line the 1-based `line` of `source` comes from
+    pub fn location(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = (*self.lines.get(line.checked_sub(1)?)?)?;
+        Some((&self.files[file], line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preprocess_files(source: &str, files: &[(&str, &str)]) -> Result<Preprocessed, String> {
+        let load = |path: &str| {
+            files
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map(|(_, source)| source.to_string())
+                .ok_or_else(|| format!("no file {}", path))
+        };
+        preprocess_with(source, "main", &[("SHADOWS", "1")], &load)
+    }
+
+    #[test]
+    fn nested_includes_are_expanded_in_place() {
+        let files = [
+            ("a.glsl", "float a;\n#include \"b.glsl\"\nfloat a2;"),
+            ("b.glsl", "float b;"),
+        ];
+        let preprocessed = preprocess_files(
+            "#version 330 core\n#include \"a.glsl\"\nvoid main() {}",
+            &files,
+        )
+        .unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "#version 330 core\n#define SHADOWS 1\nfloat a;\nfloat b;\nfloat a2;\nvoid main() {}\n"
+        );
+        assert_eq!(preprocessed.files, ["main", "a.glsl", "b.glsl"]);
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let files = [
+            ("a.glsl", "#include \"b.glsl\""),
+            ("b.glsl", "float b;\n#include \"a.glsl\""),
+        ];
+        let error = preprocess_files("#include \"a.glsl\"", &files).unwrap_err();
+        assert_eq!(
+            error,
+            "b.glsl:2: #include cycle main -> a.glsl -> b.glsl -> a.glsl"
+        );
+    }
+
+    #[test]
+    fn lines_map_back_to_their_files() {
+        let files = [(
+            "lights.glsl",
+            "struct Light {};\nvec3 Shade(Light light) {}",
+        )];
+        let preprocessed = preprocess_files(
+            "#version 330 core\n#include \"lights.glsl\"\nout vec4 FragColor;",
+            &files,
+        )
+        .unwrap();
+        // the define after #version is generated
+        assert_eq!(preprocessed.location(1), Some(("main", 1)));
+        assert_eq!(preprocessed.location(2), None);
+        // a driver error on line 4 of the compiled source is in the include
+        assert_eq!(preprocessed.location(4), Some(("lights.glsl", 2)));
+        assert_eq!(preprocessed.location(5), Some(("main", 3)));
+        assert_eq!(preprocessed.location(6), None);
+    }
+}
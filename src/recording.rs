@@ -0,0 +1,125 @@
+use crate::window::FPS;
+use glow::{Context, HasContext};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use typed_builder::TypedBuilder;
+
+/// Deterministic recording of a run: time is locked to a fixed step of `TIME_STEP` per frame
+/// and every rendered frame is written out, however long it takes to render and encode.
+#[derive(TypedBuilder, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recording {
+    pub output: RecordingOutput,
+    /// exit after this many frames, otherwise record until the window is closed
+    #[builder(default, setter(strip_option))]
+    pub frames: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RecordingOutput {
+    /// `frame_00000.png`, `frame_00001.png`, ... in this directory
+    ImageSequence(PathBuf),
+    /// raw RGB24 frames, top row first, written to the stdin of `command`. `{width}`, `{height}`
+    /// and `{fps}` in `args` are replaced, e.g. for ffmpeg:
+    /// `-f rawvideo -pix_fmt rgb24 -s {width}x{height} -r {fps} -i - out.mp4`
+    Encoder { command: String, args: Vec<String> },
+}
+
+pub struct Recorder {
+    output: RecordingOutput,
+    frames: Option<u32>,
+    frame: u32,
+    size: (u32, u32),
+    encoder: Option<Child>,
+}
+
+impl Recorder {
+    /// `width` and `height` are the size of the default framebuffer, which an encoder expects
+    /// to stay the same for the whole recording
+    pub fn new(recording: &Recording, width: u32, height: u32) -> anyhow::Result<Self> {
+        let encoder = match &recording.output {
+            RecordingOutput::ImageSequence(dir) => {
+                std::fs::create_dir_all(dir)?;
+                None
+            }
+            RecordingOutput::Encoder { command, args } => {
+                let args = args.iter().map(|arg| {
+                    arg.replace("{width}", &width.to_string())
+                        .replace("{height}", &height.to_string())
+                        .replace("{fps}", &FPS.to_string())
+                });
+                log::info!("Starting encoder {}", command);
+                Some(
+                    Command::new(command)
+                        .args(args)
+                        .stdin(Stdio::piped())
+                        .spawn()?,
+                )
+            }
+        };
+        Ok(Self {
+            output: recording.output.clone(),
+            frames: recording.frames,
+            frame: 0,
+            size: (width, height),
+            encoder,
+        })
+    }
+
+    /// frames written so far
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frames.is_some_and(|frames| self.frame >= frames)
+    }
+
+    /// Reads back the default framebuffer (or `framebuffer` when rendering into our own) and
+    /// writes it out.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn record_frame(
+        &mut self,
+        gl: &Context,
+        framebuffer: Option<glow::Framebuffer>,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<()> {
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, framebuffer);
+        let image = crate::capture::read_rgb8(gl, 0, 0, width, height);
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+
+        match (&self.output, &mut self.encoder) {
+            (RecordingOutput::ImageSequence(dir), _) => {
+                image.save(dir.join(format!("frame_{:05}.png", self.frame)))?;
+            }
+            (RecordingOutput::Encoder { .. }, Some(encoder)) => {
+                if (width, height) != self.size {
+                    return Err(anyhow::anyhow!(
+                        "Frame size changed from {:?} to {:?}, the encoder cannot follow",
+                        self.size,
+                        (width, height)
+                    ));
+                }
+                let stdin = encoder.stdin.as_mut().unwrap();
+                stdin.write_all(image.as_raw())?;
+            }
+            (RecordingOutput::Encoder { .. }, None) => unreachable!(),
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// closes the encoder input and waits for it to write the file
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        log::info!("Recorded {} frames", self.frame);
+        if let Some(mut encoder) = self.encoder.take() {
+            drop(encoder.stdin.take());
+            let status = encoder.wait()?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("Encoder exited with {}", status));
+            }
+        }
+        Ok(())
+    }
+}
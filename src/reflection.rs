@@ -0,0 +1,463 @@
+use crate::uniform_buffer::Std140Field;
+use glow::{Context, HasContext, Program, UniformLocation};
+
+/// an active vertex shader input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub name: String,
+    /// e.g. `glow::FLOAT_VEC3`, see `type_name`
+    pub gl_type: u32,
+    /// array size, 1 for plain attributes
+    pub size: i32,
+    /// `None` for built-ins like `gl_VertexID`
+    pub location: Option<u32>,
+}
+
+/// an active uniform. Arrays are reported once, as `name[0]`
+#[derive(Debug, Clone)]
+pub struct UniformInfo {
+    pub name: String,
+    pub gl_type: u32,
+    /// array size, 1 for plain uniforms
+    pub size: i32,
+    /// `None` for members of uniform blocks, which are set through their buffer
+    pub location: Option<UniformLocation>,
+    /// index into `Reflection::uniform_blocks`
+    pub block: Option<usize>,
+    /// byte offset inside the block
+    pub offset: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: u32,
+    pub binding: u32,
+    pub data_size: i32,
+    /// indices into `Reflection::uniforms`
+    pub members: Vec<usize>,
+}
+
+/// What a linked program contains, queried once after linking.
+///
+/// glow 0.13 lacks `glGetProgramiv`, `glGetActiveUniformsiv` and `glGetVertexAttribiv`, they are
+/// loaded next to the glow context by `load_functions`. Without them (on the web) uniform
+/// blocks are not listed, block members only show up as uniforms without location.
+#[derive(Debug, Clone, Default)]
+pub struct Reflection {
+    pub attributes: Vec<AttributeInfo>,
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+    /// `local_size_x/y/z` of compute programs, see `query_work_group_size`
+    pub work_group_size: Option<[u32; 3]>,
+}
+
+impl Reflection {
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new(gl: &Context, program: Program) -> Self {
+        let attributes = (0..gl.get_active_attributes(program))
+            .filter_map(|index| gl.get_active_attribute(program, index))
+            .map(|attribute| AttributeInfo {
+                location: gl.get_attrib_location(program, &attribute.name),
+                name: attribute.name,
+                gl_type: attribute.atype,
+                size: attribute.size,
+            })
+            .collect::<Vec<_>>();
+
+        let uniform_indices = 0..gl.get_active_uniforms(program);
+        let mut uniforms = uniform_indices
+            .clone()
+            .filter_map(|index| gl.get_active_uniform(program, index))
+            .map(|uniform| UniformInfo {
+                location: gl.get_uniform_location(program, &uniform.name),
+                name: uniform.name,
+                gl_type: uniform.utype,
+                size: uniform.size,
+                block: None,
+                offset: None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut uniform_blocks = Vec::new();
+        if let Some(block_count) = native::program_i32(program, glow::ACTIVE_UNIFORM_BLOCKS) {
+            let indices = uniform_indices.collect::<Vec<_>>();
+            let block_indices =
+                native::active_uniforms_i32(program, &indices, glow::UNIFORM_BLOCK_INDEX);
+            let offsets = native::active_uniforms_i32(program, &indices, glow::UNIFORM_OFFSET);
+            if let (Some(block_indices), Some(offsets)) = (block_indices, offsets) {
+                for (uniform, (block, offset)) in uniforms
+                    .iter_mut()
+                    .zip(block_indices.into_iter().zip(offsets))
+                {
+                    if block >= 0 {
+                        uniform.block = Some(block as usize);
+                        uniform.offset = Some(offset);
+                    }
+                }
+            }
+
+            for index in 0..block_count as u32 {
+                uniform_blocks.push(UniformBlockInfo {
+                    name: gl.get_active_uniform_block_name(program, index),
+                    index,
+                    binding: gl.get_active_uniform_block_parameter_i32(
+                        program,
+                        index,
+                        glow::UNIFORM_BLOCK_BINDING,
+                    ) as u32,
+                    data_size: gl.get_active_uniform_block_parameter_i32(
+                        program,
+                        index,
+                        glow::UNIFORM_BLOCK_DATA_SIZE,
+                    ),
+                    members: uniforms
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, uniform)| uniform.block == Some(index as usize))
+                        .map(|(i, _)| i)
+                        .collect(),
+                });
+            }
+        }
+
+        Self {
+            attributes,
+            uniforms,
+            uniform_blocks,
+            work_group_size: None,
+        }
+    }
+
+    /// Reads the work group size of `program`, which has to be a compute program linked from
+    /// `source`. Parsed from the source where the program cannot be queried
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn query_work_group_size(&mut self, program: Program, source: &str) {
+        self.work_group_size = native::program_i32_array(program, glow::COMPUTE_WORK_GROUP_SIZE)
+            .map(|size| size.map(|s| s as u32))
+            .or_else(|| parse_local_size(source));
+    }
+
+    pub fn uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|b| b.name == name)
+    }
+
+    /// Checks the attributes the vertex shader reads against what the bound vertex array
+    /// provides: `layout` lists (location, type) of its vertex buffer, attributes at other
+    /// locations have to be enabled on the vertex array, e.g. for instancing.
+    /// Returns one message per mismatch.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn check_vertex_layout(&self, layout: &[(u32, u32)]) -> Vec<String> {
+        let mut problems = Vec::new();
+        for attribute in &self.attributes {
+            let Some(location) = attribute.location else {
+                continue;
+            };
+            match layout.iter().find(|(l, _)| *l == location) {
+                Some((_, gl_type)) if *gl_type != attribute.gl_type => problems.push(format!(
+                    "attribute {} at location {} is {}, the vertex buffer has {}",
+                    attribute.name,
+                    location,
+                    type_name(attribute.gl_type),
+                    type_name(*gl_type)
+                )),
+                Some(_) => {}
+                None => {
+                    if native::vertex_attrib_i32(location, glow::VERTEX_ATTRIB_ARRAY_ENABLED)
+                        == Some(0)
+                    {
+                        problems.push(format!(
+                            "attribute {} at location {} is not provided by the vertex array",
+                            attribute.name, location
+                        ));
+                    }
+                }
+            }
+        }
+        problems
+    }
+
+    /// Checks the members of the uniform block `name` against the std140 layout of the Rust
+    /// struct filling it, see `UniformBuffer`. Without member offsets (on the web) nothing is
+    /// checked. Returns one message per mismatch.
+    pub fn check_block_layout(&self, name: &str, fields: &[Std140Field]) -> Vec<String> {
+        let mut problems = Vec::new();
+        let Some(block) = self.uniform_block(name) else {
+            return problems;
+        };
+        let members = block
+            .members
+            .iter()
+            .map(|i| &self.uniforms[*i])
+            .map(|member| {
+                // members of blocks with an instance name are reported as `Block.member`
+                let member_name = member.name.strip_prefix(&format!("{}.", name));
+                let member_name = member_name.unwrap_or(&member.name);
+                (
+                    member_name.strip_suffix("[0]").unwrap_or(member_name),
+                    member,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for field in fields {
+            match members.iter().find(|(name, _)| *name == field.name) {
+                None => problems.push(format!("block {} has no member {}", name, field.name)),
+                Some((_, member)) => {
+                    if member.gl_type != field.gl_type || member.size as usize != field.len {
+                        problems.push(format!(
+                            "member {} of block {} is {}, the struct has {}",
+                            field.name,
+                            name,
+                            array_type_name(member.gl_type, member.size as usize),
+                            array_type_name(field.gl_type, field.len)
+                        ));
+                    }
+                    if let Some(offset) = member.offset {
+                        if offset as usize != field.offset {
+                            problems.push(format!(
+                                "member {} of block {} is at offset {}, the struct has it at {}",
+                                field.name, name, offset, field.offset
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        for (member_name, _) in &members {
+            if !fields.iter().any(|field| field.name == *member_name) {
+                problems.push(format!(
+                    "member {} of block {} is not in the struct",
+                    member_name, name
+                ));
+            }
+        }
+        if block.data_size as usize > Std140Field::block_size(fields) {
+            problems.push(format!(
+                "block {} takes {} bytes, the struct only {}",
+                name,
+                block.data_size,
+                Std140Field::block_size(fields)
+            ));
+        }
+        problems
+    }
+}
+
+/// `local_size_x/y/z` of the `layout(..) in;` of a compute shader, the ones not given are 1.
+/// `None` without any or if one is not a literal, e.g. a macro
+fn parse_local_size(source: &str) -> Option<[u32; 3]> {
+    let mut size = [1; 3];
+    let mut found = false;
+    for line in source.lines() {
+        let code = line.split("//").next().unwrap_or("");
+        for (axis, name) in ["local_size_x", "local_size_y", "local_size_z"]
+            .iter()
+            .enumerate()
+        {
+            let Some(rest) = code.split(name).nth(1) else {
+                continue;
+            };
+            let value = rest.trim_start().strip_prefix('=')?.trim_start();
+            let end = value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(value.len());
+            size[axis] = value[..end].parse().ok()?;
+            found = true;
+        }
+    }
+    found.then_some(size)
+}
+
+/// `type_name` with the array length, if any
+fn array_type_name(gl_type: u32, len: usize) -> String {
+    match len {
+        1 => type_name(gl_type).to_string(),
+        len => format!("{}[{}]", type_name(gl_type), len),
+    }
+}
+
+/// GLSL name of a uniform or attribute type
+pub fn type_name(gl_type: u32) -> &'static str {
+    match gl_type {
+        glow::FLOAT => "float",
+        glow::FLOAT_VEC2 => "vec2",
+        glow::FLOAT_VEC3 => "vec3",
+        glow::FLOAT_VEC4 => "vec4",
+        glow::INT => "int",
+        glow::INT_VEC2 => "ivec2",
+        glow::INT_VEC3 => "ivec3",
+        glow::INT_VEC4 => "ivec4",
+        glow::UNSIGNED_INT => "uint",
+        glow::UNSIGNED_INT_VEC2 => "uvec2",
+        glow::UNSIGNED_INT_VEC3 => "uvec3",
+        glow::UNSIGNED_INT_VEC4 => "uvec4",
+        glow::BOOL => "bool",
+        glow::BOOL_VEC2 => "bvec2",
+        glow::BOOL_VEC3 => "bvec3",
+        glow::BOOL_VEC4 => "bvec4",
+        glow::FLOAT_MAT2 => "mat2",
+        glow::FLOAT_MAT3 => "mat3",
+        glow::FLOAT_MAT4 => "mat4",
+        glow::SAMPLER_2D => "sampler2D",
+        glow::SAMPLER_3D => "sampler3D",
+        glow::SAMPLER_CUBE => "samplerCube",
+        glow::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        glow::SAMPLER_2D_ARRAY => "sampler2DArray",
+        glow::SAMPLER_2D_ARRAY_SHADOW => "sampler2DArrayShadow",
+        glow::SAMPLER_CUBE_SHADOW => "samplerCubeShadow",
+        glow::SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        glow::INT_SAMPLER_2D => "isampler2D",
+        glow::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        _ => "unknown",
+    }
+}
+
+/// Loads the introspection functions glow does not wrap. Call it with the loader of the glow
+/// context, on the thread that uses it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_functions(loader: impl Fn(&std::ffi::CStr) -> *const std::ffi::c_void) {
+    native::load(loader);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use glow::Program;
+    use std::cell::Cell;
+    use std::ffi::{c_void, CStr};
+
+    type GetProgramiv = unsafe extern "system" fn(u32, u32, *mut i32);
+    type GetActiveUniformsiv = unsafe extern "system" fn(u32, i32, *const u32, u32, *mut i32);
+    type GetVertexAttribiv = unsafe extern "system" fn(u32, u32, *mut i32);
+
+    #[derive(Copy, Clone)]
+    struct Functions {
+        get_programiv: GetProgramiv,
+        get_active_uniformsiv: GetActiveUniformsiv,
+        get_vertex_attribiv: GetVertexAttribiv,
+    }
+
+    thread_local! {
+        static FUNCTIONS: Cell<Option<Functions>> = const { Cell::new(None) };
+    }
+
+    pub fn load(loader: impl Fn(&CStr) -> *const c_void) {
+        let get_programiv = loader(c"glGetProgramiv");
+        let get_active_uniformsiv = loader(c"glGetActiveUniformsiv");
+        let get_vertex_attribiv = loader(c"glGetVertexAttribiv");
+        let functions = if [get_programiv, get_active_uniformsiv, get_vertex_attribiv]
+            .iter()
+            .any(|f| f.is_null())
+        {
+            None
+        } else {
+            unsafe {
+                Some(Functions {
+                    get_programiv: std::mem::transmute::<*const c_void, GetProgramiv>(
+                        get_programiv,
+                    ),
+                    get_active_uniformsiv: std::mem::transmute::<*const c_void, GetActiveUniformsiv>(
+                        get_active_uniformsiv,
+                    ),
+                    get_vertex_attribiv: std::mem::transmute::<*const c_void, GetVertexAttribiv>(
+                        get_vertex_attribiv,
+                    ),
+                })
+            }
+        };
+        FUNCTIONS.with(|f| f.set(functions));
+    }
+
+    fn functions() -> Option<Functions> {
+        FUNCTIONS.with(|f| f.get())
+    }
+
+    pub unsafe fn program_i32(program: Program, parameter: u32) -> Option<i32> {
+        program_i32_array::<1>(program, parameter).map(|[value]| value)
+    }
+
+    /// for parameters with several values, like `COMPUTE_WORK_GROUP_SIZE`
+    pub unsafe fn program_i32_array<const N: usize>(
+        program: Program,
+        parameter: u32,
+    ) -> Option<[i32; N]> {
+        let functions = functions()?;
+        let mut values = [0; N];
+        (functions.get_programiv)(program.0.get(), parameter, values.as_mut_ptr());
+        Some(values)
+    }
+
+    pub unsafe fn active_uniforms_i32(
+        program: Program,
+        indices: &[u32],
+        parameter: u32,
+    ) -> Option<Vec<i32>> {
+        let functions = functions()?;
+        let mut values = vec![0; indices.len()];
+        (functions.get_active_uniformsiv)(
+            program.0.get(),
+            indices.len() as i32,
+            indices.as_ptr(),
+            parameter,
+            values.as_mut_ptr(),
+        );
+        Some(values)
+    }
+
+    /// of the bound vertex array
+    pub unsafe fn vertex_attrib_i32(index: u32, parameter: u32) -> Option<i32> {
+        let functions = functions()?;
+        let mut value = 0;
+        (functions.get_vertex_attribiv)(index, parameter, &mut value);
+        Some(value)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod native {
+    use glow::Program;
+
+    pub unsafe fn program_i32(_program: Program, _parameter: u32) -> Option<i32> {
+        None
+    }
+
+    pub unsafe fn program_i32_array<const N: usize>(
+        _program: Program,
+        _parameter: u32,
+    ) -> Option<[i32; N]> {
+        None
+    }
+
+    pub unsafe fn active_uniforms_i32(
+        _program: Program,
+        _indices: &[u32],
+        _parameter: u32,
+    ) -> Option<Vec<i32>> {
+        None
+    }
+
+    pub unsafe fn vertex_attrib_i32(_index: u32, _parameter: u32) -> Option<i32> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_size_is_parsed_with_defaults() {
+        let source = "#version 430 core\nlayout(local_size_x = 16, local_size_y = 8) in;\n";
+        assert_eq!(parse_local_size(source), Some([16, 8, 1]));
+        assert_eq!(
+            parse_local_size("layout (local_size_x=64) in;"),
+            Some([64, 1, 1])
+        );
+    }
+
+    #[test]
+    fn local_size_needs_literals() {
+        assert_eq!(parse_local_size("layout(local_size_x = SIZE) in;"), None);
+        assert_eq!(parse_local_size("void main() {}"), None);
+    }
+}
@@ -1,8 +1,9 @@
+use crate::gl_object::SharedGl;
 use crate::mesh::{Material, Mesh, Vertex};
 use crate::model::Model;
+use crate::tangent;
 use crate::texture::{Texture, TextureType};
 use cfg_if::cfg_if;
-use glow::Context;
 use nalgebra_glm as glm;
 use std::io::{BufReader, Cursor};
 use std::path::Path;
@@ -44,6 +45,21 @@ pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
     Ok(txt)
 }
 
+/// Where `file_name`, as passed to `load_string`, lives in the source tree. `load_string` reads
+/// the copy made by build.rs, edits only show up here until the next build.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn source_path(file_name: &str) -> std::path::PathBuf {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // build.rs copies `src/<chapter>/shaders/<file>` to `shaders/<chapter>/<file>`
+    let mut parts = file_name.splitn(3, '/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("shaders"), Some(chapter), Some(file)) => {
+            root.join("src").join(chapter).join("shaders").join(file)
+        }
+        _ => root.join("resources").join(file_name),
+    }
+}
+
 #[allow(dead_code)]
 pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
     cfg_if! {
@@ -70,7 +86,7 @@ pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
 }
 
 pub async fn load_texture_with_type(
-    gl: &Context,
+    gl: &SharedGl,
     file_name: &str,
     ty: TextureType,
 ) -> anyhow::Result<Texture> {
@@ -79,13 +95,13 @@ pub async fn load_texture_with_type(
     Texture::from_bytes(gl, &data, file_name, ty)
 }
 
-pub async fn load_texture(gl: &Context, file_name: &str) -> anyhow::Result<Texture> {
+pub async fn load_texture(gl: &SharedGl, file_name: &str) -> anyhow::Result<Texture> {
     log::info!("Loading texture file_name: {}", file_name);
     let data = load_binary(file_name).await?;
     Texture::from_bytes(gl, &data, file_name, TextureType::Diffuse)
 }
 
-pub async fn load_obj(gl: &Context, file_name: &str) -> anyhow::Result<Model> {
+pub async fn load_obj(gl: &SharedGl, file_name: &str) -> anyhow::Result<Model> {
     let obj_text = load_string(file_name).await?;
     let obj_cursor = Cursor::new(obj_text);
     let mut obj_reader = BufReader::new(obj_cursor);
@@ -119,16 +135,32 @@ pub async fn load_obj(gl: &Context, file_name: &str) -> anyhow::Result<Model> {
             let diffuse = load_texture_with_type(gl, &path, TextureType::Diffuse).await?;
             textures.push(diffuse);
         }
-        // if let Some(p) = m.specular_texture {
-        //     let path = format!("{}/{}", model_directory_path, p);
-        //     let specular = load_texture(gl, &path, TextureType::Specular).await?;
-        //     textures.push(specular);
-        // }
-        // if let Some(p) = m.normal_texture {
-        //     let path = format!("{}/{}", model_directory_path, p);
-        //     let normal = load_texture(gl, &path, TextureType::Normal).await?;
-        //     textures.push(normal);
-        // }
+        if let Some(p) = m.specular_texture {
+            let path = format!("{}/{}", model_directory_path, p);
+            log::info!("Loading texture: {} for mat: {}", path, m.name);
+            let specular = load_texture_with_type(gl, &path, TextureType::Specular).await?;
+            textures.push(specular);
+        }
+        // tobj reads `map_Bump`/`bump` as the normal texture, `norm` is left unknown
+        if let Some(p) = m
+            .normal_texture
+            .or_else(|| m.unknown_param.get("norm").cloned())
+        {
+            let path = format!("{}/{}", model_directory_path, p);
+            log::info!("Loading texture: {} for mat: {}", path, m.name);
+            let normal = load_texture_with_type(gl, &path, TextureType::Normal).await?;
+            textures.push(normal);
+        }
+        // displacement maps, the heights of parallax mapping
+        let height = ["disp", "map_disp"]
+            .iter()
+            .find_map(|key| m.unknown_param.get(*key));
+        if let Some(p) = height {
+            let path = format!("{}/{}", model_directory_path, p);
+            log::info!("Loading texture: {} for mat: {}", path, m.name);
+            let height = load_texture_with_type(gl, &path, TextureType::Height).await?;
+            textures.push(height);
+        }
 
         materials.push(Material {
             name: m.name,
@@ -139,7 +171,7 @@ pub async fn load_obj(gl: &Context, file_name: &str) -> anyhow::Result<Model> {
     let meshes = models
         .into_iter()
         .map(|m| {
-            let vertices = (0..m.mesh.positions.len() / 3)
+            let mut vertices = (0..m.mesh.positions.len() / 3)
                 .map(|i| Vertex {
                     position: glm::vec3(
                         m.mesh.positions[i * 3],
@@ -152,14 +184,17 @@ pub async fn load_obj(gl: &Context, file_name: &str) -> anyhow::Result<Model> {
                         m.mesh.normals[i * 3 + 1],
                         m.mesh.normals[i * 3 + 2],
                     ),
+                    ..Default::default()
                 })
                 .collect::<Vec<_>>();
+            let mut indices = m.mesh.indices;
+            tangent::generate_mikktspace_tangents(&mut vertices, &mut indices);
 
             Mesh::new(
                 gl,
                 &m.name,
                 vertices,
-                m.mesh.indices,
+                indices,
                 m.mesh.material_id.unwrap_or(0),
             )
         })
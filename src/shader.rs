@@ -1,220 +1,492 @@
-use crate::resources;
-use glow::{Context, HasContext, Program, FRAGMENT_SHADER, GEOMETRY_SHADER, VERTEX_SHADER};
-
+use crate::gl_object::{OwnedProgram, SharedGl};
+use crate::reflection::Reflection;
+use crate::shader_error::ShaderError;
+use crate::tweaks::TweakValue;
+use crate::uniform::{MissingUniformPolicy, Uniform};
+use crate::uniform_buffer::{Std140, UniformBuffer};
+use crate::{preprocessor, resources, tweaks};
+use glow::{
+    Context, HasContext, Program, UniformLocation, COMPUTE_SHADER, FRAGMENT_SHADER,
+    GEOMETRY_SHADER, VERTEX_SHADER,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// A linked program, deleted when dropped
 pub struct MyShader {
     name: Option<String>,
-    program: Program,
+    /// identifies the program in the tweak panel, the name if set or else a hash of the sources
+    tweak_key: String,
+    /// hash of the sources and defines, unlike the program id never reused by another program
+    source_hash: String,
+    program: OwnedProgram,
+    /// locations of the active uniforms by name, queried once after linking. Array elements are
+    /// there both as `name[i]` and, for the first one, as `name`
+    uniforms: HashMap<String, UniformLocation>,
+    reflection: Reflection,
+    missing_uniform_policy: MissingUniformPolicy,
+    /// missing uniforms already reported with `MissingUniformPolicy::WarnOnce`
+    warned_uniforms: RefCell<HashSet<String>>,
+    tweaks: RefCell<ShaderTweaks>,
+}
+
+/// What this program has of the tweak panel, refreshed when `tweaks::generation` changes
+#[derive(Default)]
+struct ShaderTweaks {
+    /// 0 if never fetched
+    generation: u64,
+    /// by `tweaks::index`
+    index: Option<usize>,
+    /// whether the uniforms are listed in the panel
+    registered: bool,
+    overrides: HashMap<String, TweakValue>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ShaderType {
     Vertex,
     Fragment,
-    #[allow(dead_code)]
     Compute,
     Geometry,
 }
 
+impl ShaderType {
+    pub fn gl_type(&self) -> u32 {
+        match self {
+            ShaderType::Vertex => VERTEX_SHADER,
+            ShaderType::Fragment => FRAGMENT_SHADER,
+            ShaderType::Compute => COMPUTE_SHADER,
+            ShaderType::Geometry => GEOMETRY_SHADER,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShaderType::Vertex => "vertex",
+            ShaderType::Fragment => "fragment",
+            ShaderType::Compute => "compute",
+            ShaderType::Geometry => "geometry",
+        }
+    }
+}
+
 impl MyShader {
     #[allow(dead_code)]
     pub async fn new(
-        gl: &Context,
+        gl: &SharedGl,
         vertex_path: &str,
         fragment_path: &str,
         shader_version: Option<&str>,
-    ) -> Result<Self, String> {
-        let vertex_shader = resources::load_string(vertex_path)
-            .await
-            .expect("Failed to load vertex shader");
-        let fragment_shader = resources::load_string(fragment_path)
-            .await
-            .expect("Failed to load fragment shader");
+    ) -> Result<Self, ShaderError> {
+        let vertex_shader = Self::load_source(vertex_path).await?;
+        let fragment_shader = Self::load_source(fragment_path).await?;
 
         Self::new_from_source(gl, &vertex_shader, &fragment_shader, shader_version)
     }
 
+    async fn load_source(path: &str) -> Result<String, ShaderError> {
+        resources::load_string(path)
+            .await
+            .map_err(|e| ShaderError::Load {
+                path: path.to_string(),
+                message: e.to_string(),
+            })
+    }
+
     pub fn new_from_source(
-        gl: &Context,
+        gl: &SharedGl,
         vertex_shader: &str,
         fragment_shader: &str,
         shader_version: Option<&str>,
-    ) -> Result<Self, String> {
-        let vertex_shader =
-            Self::modify_shader_to_support_webgl(vertex_shader, shader_version, ShaderType::Vertex);
-        let fragment_shader = Self::modify_shader_to_support_webgl(
-            fragment_shader,
+    ) -> Result<Self, ShaderError> {
+        Self::new_from_source_with_defines(gl, vertex_shader, fragment_shader, shader_version, &[])
+    }
+
+    /// Like `new_from_source`, with `#define NAME VALUE` for each of `defines` added to both
+    /// stages, e.g. to build variants of one shader.
+    pub fn new_from_source_with_defines(
+        gl: &SharedGl,
+        vertex_shader: &str,
+        fragment_shader: &str,
+        shader_version: Option<&str>,
+        defines: &[(&str, &str)],
+    ) -> Result<Self, ShaderError> {
+        Self::link(
+            gl,
+            &[
+                (ShaderType::Vertex, vertex_shader),
+                (ShaderType::Fragment, fragment_shader),
+            ],
             shader_version,
-            ShaderType::Fragment,
-        );
+            defines,
+        )
+    }
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            log::info!("vs: \n{}\n\nfs: \n{}", vertex_shader, fragment_shader);
+    pub fn new_with_geometry_from_source(
+        gl: &SharedGl,
+        vertex_shader: &str,
+        fragment_shader: &str,
+        geometry_shader: &str,
+        shader_version: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        Self::link(
+            gl,
+            &[
+                (ShaderType::Vertex, vertex_shader),
+                (ShaderType::Fragment, fragment_shader),
+                (ShaderType::Geometry, geometry_shader),
+            ],
+            shader_version,
+            &[],
+        )
+    }
+
+    /// A program with only a compute shader, run with `dispatch`. Needs a 4.3 context.
+    pub fn new_compute(
+        gl: &SharedGl,
+        compute_shader: &str,
+        shader_version: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        Self::link(
+            gl,
+            &[(ShaderType::Compute, compute_shader)],
+            shader_version,
+            &[],
+        )
+    }
+
+    fn link(
+        gl: &SharedGl,
+        stages: &[(ShaderType, &str)],
+        shader_version: Option<&str>,
+        defines: &[(&str, &str)],
+    ) -> Result<Self, ShaderError> {
+        let mut shaders = Vec::new();
+        for (shader_type, source) in stages {
+            let shader = Self::compile_stage(gl, *shader_type, source, shader_version, defines)
+                .inspect_err(|_| unsafe {
+                    for shader in &shaders {
+                        gl.delete_shader(*shader);
+                    }
+                })?;
+            shaders.push(shader);
         }
-        let program = unsafe { gl.create_program().expect("Failed to create program") };
 
-        let (vertex, fragment) = (
-            Self::compile_shader(gl, VERTEX_SHADER, &vertex_shader)?,
-            Self::compile_shader(gl, FRAGMENT_SHADER, &fragment_shader)?,
-        );
+        let program = unsafe { gl.create_program().expect("Failed to create program") };
 
         unsafe {
-            gl.attach_shader(program, vertex);
-            gl.attach_shader(program, fragment);
+            for shader in &shaders {
+                gl.attach_shader(program, *shader);
+            }
             gl.link_program(program);
         }
 
-        if !unsafe { gl.get_program_link_status(program) } {
-            return Err(unsafe { gl.get_program_info_log(program) });
+        unsafe {
+            for shader in &shaders {
+                gl.detach_shader(program, *shader);
+                gl.delete_shader(*shader);
+            }
         }
 
-        unsafe {
-            gl.detach_shader(program, vertex);
-            gl.detach_shader(program, fragment);
-            gl.delete_shader(vertex);
-            gl.delete_shader(fragment);
+        if !unsafe { gl.get_program_link_status(program) } {
+            let log = unsafe { gl.get_program_info_log(program) };
+            unsafe { gl.delete_program(program) };
+            return Err(ShaderError::Link { log });
         }
 
+        let mut reflection = unsafe { Reflection::new(gl, program) };
+        if let Some((_, source)) = stages.iter().find(|(ty, _)| *ty == ShaderType::Compute) {
+            unsafe { reflection.query_work_group_size(program, source) };
+        }
+        let source_hash = Self::hash_sources(stages, defines);
         Ok(Self {
             name: None,
-            program,
+            uniforms: unsafe { Self::uniform_locations(gl, program, &reflection) },
+            program: OwnedProgram::new(gl, program, &source_hash),
+            tweak_key: source_hash.clone(),
+            source_hash,
+            reflection,
+            missing_uniform_policy: MissingUniformPolicy::default(),
+            warned_uniforms: Default::default(),
+            tweaks: Default::default(),
         })
     }
 
-    pub fn new_with_geometry_from_source(
-        gl: &Context,
-        vertex_shader: &str,
-        fragment_shader: &str,
-        geometry_shader: &str,
-        shader_version: Option<&str>,
-    ) -> Result<Self, String> {
-        let vertex_shader =
-            Self::modify_shader_to_support_webgl(vertex_shader, shader_version, ShaderType::Vertex);
-        let fragment_shader = Self::modify_shader_to_support_webgl(
-            fragment_shader,
-            shader_version,
-            ShaderType::Fragment,
-        );
-        let geometry_shader = Self::modify_shader_to_support_webgl(
-            geometry_shader,
-            shader_version,
-            ShaderType::Geometry,
-        );
-
-        #[cfg(target_arch = "wasm32")]
-        {
-            log::info!("vs: \n{}\n\nfs: \n{}", vertex_shader, fragment_shader);
+    /// FNV-1a of the sources and defines, stable between runs unlike the program id
+    fn hash_sources(stages: &[(ShaderType, &str)], defines: &[(&str, &str)]) -> String {
+        let parts = stages
+            .iter()
+            .map(|(_, source)| *source)
+            .chain(defines.iter().flat_map(|(name, value)| [*name, *value]));
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for part in parts {
+            for byte in part.bytes().chain([0]) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
         }
-        let program = unsafe { gl.create_program().expect("Failed to create program") };
-
-        let (vertex, fragment, geometry) = (
-            Self::compile_shader(gl, VERTEX_SHADER, &vertex_shader)?,
-            Self::compile_shader(gl, FRAGMENT_SHADER, &fragment_shader)?,
-            Self::compile_shader(gl, GEOMETRY_SHADER, &geometry_shader)?,
-        );
+        format!("{:016x}", hash)
+    }
 
-        unsafe {
-            gl.attach_shader(program, vertex);
-            gl.attach_shader(program, fragment);
-            gl.attach_shader(program, geometry);
-            gl.link_program(program);
+    // uniform locations are only `Copy` on native
+    #[allow(clippy::clone_on_copy)]
+    unsafe fn uniform_locations(
+        gl: &Context,
+        program: Program,
+        reflection: &Reflection,
+    ) -> HashMap<String, UniformLocation> {
+        let mut uniforms = HashMap::new();
+        for uniform in &reflection.uniforms {
+            // members of uniform blocks have no location
+            let Some(location) = &uniform.location else {
+                continue;
+            };
+            uniforms.insert(uniform.name.clone(), location.clone());
+            // arrays are reported once, as `name[0]` with their size
+            if let Some(base) = uniform.name.strip_suffix("[0]") {
+                uniforms.insert(base.to_string(), location.clone());
+                for i in 1..uniform.size {
+                    let name = format!("{}[{}]", base, i);
+                    if let Some(location) = gl.get_uniform_location(program, &name) {
+                        uniforms.insert(name, location);
+                    }
+                }
+            }
         }
+        uniforms
+    }
 
-        if !unsafe { gl.get_program_link_status(program) } {
-            return Err(unsafe { gl.get_program_info_log(program) });
+    /// resolves includes and defines, adapts the source to `shader_version` and compiles it.
+    /// Locations in the error refer to the original files
+    fn compile_stage(
+        gl: &Context,
+        shader_type: ShaderType,
+        source: &str,
+        shader_version: Option<&str>,
+        defines: &[(&str, &str)],
+    ) -> Result<glow::Shader, ShaderError> {
+        let mut preprocessed = preprocessor::preprocess(source, shader_type.name(), defines)
+            .map_err(|message| ShaderError::Preprocess {
+                stage: shader_type,
+                message,
+            })?;
+        let source =
+            Self::modify_shader_to_support_webgl(&preprocessed.source, shader_version, shader_type);
+        if source.lines().count() > preprocessed.lines.len() {
+            // the precision statement after #version
+            preprocessed.insert_generated_line(1);
         }
+        preprocessed.source = source;
 
-        unsafe {
-            gl.detach_shader(program, vertex);
-            gl.detach_shader(program, fragment);
-            gl.detach_shader(program, geometry);
-            gl.delete_shader(vertex);
-            gl.delete_shader(fragment);
-            gl.delete_shader(geometry);
+        #[cfg(target_arch = "wasm32")]
+        {
+            log::info!("{}: \n{}", shader_type.name(), preprocessed.source);
         }
-
-        Ok(Self {
-            name: None,
-            program,
+        Self::compile_shader(gl, shader_type.gl_type(), &preprocessed.source).map_err(|log| {
+            ShaderError::Compile {
+                stage: shader_type,
+                log,
+                source: preprocessed,
+            }
         })
     }
 
     pub fn set_name(&mut self, name: &str) {
         self.name = Some(name.to_string());
+        self.tweak_key = name.to_string();
+        *self.tweaks.get_mut() = ShaderTweaks::default();
+    }
+
+    /// What `set` does with names that are not active uniforms, `MissingUniformPolicy::Panic`
+    /// by default
+    pub fn set_missing_uniform_policy(&mut self, policy: MissingUniformPolicy) {
+        self.missing_uniform_policy = policy;
+    }
+
+    /// attributes, uniforms and uniform blocks of the linked program
+    pub fn reflection(&self) -> &Reflection {
+        &self.reflection
     }
 
     pub fn use_shader(&self, gl: &Context) {
         unsafe {
-            gl.use_program(Some(self.program));
+            gl.use_program(Some(self.program.raw()));
+            let generation = tweaks::generation();
+            if generation == 0 {
+                return;
+            }
+            let mut cache = self.tweaks.borrow_mut();
+            let index = *cache
+                .index
+                .get_or_insert_with(|| tweaks::index(&self.tweak_key));
+            if cache.generation != generation {
+                cache.generation = generation;
+                cache.overrides = tweaks::overrides(index);
+            }
+            if !cache.registered && tweaks::panel_open() {
+                tweaks::register(index, self.tweakable_uniforms(gl));
+                cache.registered = true;
+            }
+            for (name, value) in &cache.overrides {
+                if let Some(location) = self.uniforms.get(name) {
+                    value.set_uniform(gl, location);
+                }
+            }
         }
     }
 
-    #[allow(dead_code)]
-    pub fn set_bool(&self, gl: &Context, name: &str, value: bool) {
-        unsafe {
-            let location = gl
-                .get_uniform_location(self.program, name)
-                .unwrap_or_else(|| panic!("Cannot find uniform location {} {:?}", name, self.name));
-            gl.uniform_1_i32(Some(&location), value as i32);
-        }
+    /// the uniforms the tweak panel can edit, with their current values
+    unsafe fn tweakable_uniforms(&self, gl: &Context) -> Vec<(String, TweakValue)> {
+        self.reflection
+            .uniforms
+            .iter()
+            .filter(|uniform| uniform.block.is_none() && uniform.size == 1)
+            .filter_map(|uniform| {
+                let location = uniform.location.as_ref()?;
+                let mut f = [0.0; 4];
+                let mut i = [0];
+                let value = match uniform.gl_type {
+                    glow::FLOAT => {
+                        gl.get_uniform_f32(self.program.raw(), location, &mut f[..1]);
+                        TweakValue::Float(f[0])
+                    }
+                    glow::INT | glow::BOOL => {
+                        gl.get_uniform_i32(self.program.raw(), location, &mut i);
+                        if uniform.gl_type == glow::BOOL {
+                            TweakValue::Bool(i[0] != 0)
+                        } else {
+                            TweakValue::Int(i[0])
+                        }
+                    }
+                    glow::FLOAT_VEC3 => {
+                        gl.get_uniform_f32(self.program.raw(), location, &mut f[..3]);
+                        TweakValue::Vec3([f[0], f[1], f[2]])
+                    }
+                    glow::FLOAT_VEC4 if TweakValue::is_color(&uniform.name) => {
+                        gl.get_uniform_f32(self.program.raw(), location, &mut f);
+                        TweakValue::Vec4(f)
+                    }
+                    _ => return None,
+                };
+                Some((uniform.name.clone(), value))
+            })
+            .collect()
     }
 
-    pub fn set_int(&self, gl: &Context, name: &str, value: i32) {
-        unsafe {
-            let location = gl
-                .get_uniform_location(self.program, name)
-                .unwrap_or_else(|| panic!("Cannot find uniform location {} {:?}", name, self.name));
-            gl.uniform_1_i32(Some(&location), value);
-        }
+    /// Runs the compute program, which has to be in use, on `x * y * z` work groups. Results
+    /// are only visible to later commands after a `compute::memory_barrier`.
+    pub fn dispatch(&self, gl: &Context, x: u32, y: u32, z: u32) {
+        unsafe { gl.dispatch_compute(x, y, z) };
     }
 
-    pub fn try_set_int(&self, gl: &Context, name: &str, value: i32) -> bool {
-        unsafe {
-            let location = gl.get_uniform_location(self.program, name);
-            if let Some(location) = location {
-                gl.uniform_1_i32(Some(&location), value);
-                return true;
+    /// `dispatch` with enough work groups for `width * height * depth` invocations, e.g. one
+    /// per texel of an image. Fails if this is not a compute program, or its work group size
+    /// could neither be queried nor parsed
+    pub fn dispatch_size(
+        &self,
+        gl: &Context,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<(), ShaderError> {
+        let [x, y, z] =
+            self.reflection
+                .work_group_size
+                .ok_or_else(|| ShaderError::UnknownWorkGroupSize {
+                    shader: self.name.clone(),
+                })?;
+        self.dispatch(gl, width.div_ceil(x), height.div_ceil(y), depth.div_ceil(z));
+        Ok(())
+    }
+
+    /// Sets the uniform `name` of this program, which has to be in use. Returns false if it is
+    /// not active, after applying the missing uniform policy.
+    pub fn set<T: Uniform + ?Sized>(&self, gl: &Context, name: &str, value: &T) -> bool {
+        self.set_with_policy(gl, name, value, self.missing_uniform_policy)
+    }
+
+    /// `set` with a policy for this call only, e.g. `MissingUniformPolicy::Ignore` for uniforms
+    /// that only some of the shaders drawing a mesh have
+    pub fn set_with_policy<T: Uniform + ?Sized>(
+        &self,
+        gl: &Context,
+        name: &str,
+        value: &T,
+        policy: MissingUniformPolicy,
+    ) -> bool {
+        if let Some(location) = self.uniforms.get(name) {
+            match self.tweak_override(name, value) {
+                Some(tweak) => unsafe { tweak.set_uniform(gl, location) },
+                None => unsafe { value.set_uniform(gl, location) },
             }
+            return true;
         }
+
+        self.missing(name, policy);
         false
     }
 
-    pub fn set_float(&self, gl: &Context, name: &str, value: f32) {
-        unsafe {
-            let location = gl
-                .get_uniform_location(self.program, name)
-                .unwrap_or_else(|| panic!("Cannot find uniform location {} {:?}", name, self.name));
-            gl.uniform_1_f32(Some(&location), value);
+    /// the override of the uniform `name` if any, after showing `value` in the tweak panel
+    fn tweak_override<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> Option<TweakValue> {
+        let cache = self.tweaks.borrow();
+        let index = cache.index?;
+        if cache.registered && tweaks::panel_open() {
+            if let Some(tweak) = value.tweak_value() {
+                tweaks::record(index, name, tweak);
+            }
         }
+        cache.overrides.get(name).copied()
     }
 
-    pub fn try_set_float(&self, gl: &Context, name: &str, value: f32) -> bool {
-        unsafe {
-            let location = gl.get_uniform_location(self.program, name);
-            if let Some(location) = location {
-                gl.uniform_1_f32(Some(&location), value);
-                return true;
+    fn missing(&self, name: &str, policy: MissingUniformPolicy) {
+        let error = || ShaderError::MissingUniform {
+            shader: self.name.clone(),
+            name: name.to_string(),
+        };
+        match policy {
+            MissingUniformPolicy::Panic => panic!("{}", error()),
+            MissingUniformPolicy::WarnOnce => {
+                if self.warned_uniforms.borrow_mut().insert(name.to_string()) {
+                    log::warn!("{}", error());
+                }
             }
+            MissingUniformPolicy::Ignore => {}
         }
-        false
     }
 
-    pub fn set_mat4(&self, gl: &Context, name: &str, value: &nalgebra_glm::Mat4) {
-        unsafe {
-            let location = gl
-                .get_uniform_location(self.program, name)
-                .unwrap_or_else(|| panic!("Cannot find uniform location {} {:?}", name, self.name));
-            gl.uniform_matrix_4_f32_slice(Some(&location), false, value.as_slice());
+    /// Makes the uniform block `name` read from `ubo`, warning about members whose layout
+    /// differs from `T`. Missing blocks are handled like missing uniforms.
+    pub fn bind_block<T: Std140>(&self, gl: &Context, name: &str, ubo: &UniformBuffer<T>) -> bool {
+        let Some(index) = (unsafe { gl.get_uniform_block_index(self.program.raw(), name) }) else {
+            self.missing(name, self.missing_uniform_policy);
+            return false;
+        };
+        unsafe { gl.uniform_block_binding(self.program.raw(), index, ubo.binding()) };
+        for problem in self.reflection.check_block_layout(name, T::FIELDS) {
+            log::warn!("{}", problem);
         }
+        true
+    }
+
+    #[allow(dead_code)]
+    pub fn set_bool(&self, gl: &Context, name: &str, value: bool) {
+        self.set(gl, name, &value);
+    }
+
+    pub fn set_int(&self, gl: &Context, name: &str, value: i32) {
+        self.set(gl, name, &value);
+    }
+
+    pub fn set_float(&self, gl: &Context, name: &str, value: f32) {
+        self.set(gl, name, &value);
+    }
+
+    pub fn set_mat4(&self, gl: &Context, name: &str, value: &nalgebra_glm::Mat4) {
+        self.set(gl, name, value);
     }
 
     pub fn set_vec3(&self, gl: &Context, name: &str, value: &nalgebra_glm::Vec3) {
-        unsafe {
-            let location = gl
-                .get_uniform_location(self.program, name)
-                .unwrap_or_else(|| panic!("Cannot find uniform location {} {:?}", name, self.name));
-            gl.uniform_3_f32(Some(&location), value.x, value.y, value.z);
-        }
+        self.set(gl, name, value);
     }
 
     pub fn compile_shader(
@@ -229,18 +501,14 @@ impl MyShader {
         }
 
         if !unsafe { gl.get_shader_compile_status(shader) } {
-            return Err(unsafe { gl.get_shader_info_log(shader) });
+            let log = unsafe { gl.get_shader_info_log(shader) };
+            unsafe { gl.delete_shader(shader) };
+            return Err(log);
         }
 
         Ok(shader)
     }
 
-    pub fn delete(&self, gl: &Context) {
-        unsafe {
-            gl.delete_program(self.program);
-        }
-    }
-
     /// for better compatibility with WebGL
     /// if first line is #version and provided shader_version is not empty, replace it
     pub fn modify_shader_to_support_webgl(
@@ -261,7 +529,7 @@ impl MyShader {
 
         // if fragment shader don't have precision defined, insert it to the second line
         let shader_source =
-            if shader_type == ShaderType::Fragment && !shader_source.contains("precision") {
+            if shader_type == ShaderType::Fragment && !declares_float_precision(&shader_source) {
                 let mut lines = shader_source.lines();
                 let first_line = lines.next().unwrap();
                 let rest = lines.collect::<Vec<&str>>().join("\n");
@@ -274,6 +542,51 @@ impl MyShader {
     }
 
     pub fn program(&self) -> Program {
-        self.program
+        self.program.raw()
+    }
+
+    /// identifies what the program was built from, stays the same when reloaded unchanged
+    pub fn source_hash(&self) -> &str {
+        &self.source_hash
+    }
+}
+
+/// whether `source` has a `precision <qualifier> float;` statement. Only the statement counts,
+/// comments or the precision of other types like samplers do not
+fn declares_float_precision(source: &str) -> bool {
+    source.lines().any(|line| {
+        let statement = line.split("//").next().unwrap_or("");
+        let tokens = statement
+            .split(|c: char| c.is_whitespace() || c == ';')
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        matches!(
+            tokens.as_slice(),
+            ["precision", "lowp" | "mediump" | "highp", "float", ..]
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precision_is_inserted_unless_float_precision_is_declared() {
+        let webgl = |source| {
+            MyShader::modify_shader_to_support_webgl(
+                source,
+                Some("#version 300 es"),
+                ShaderType::Fragment,
+            )
+        };
+        // a comment or a sampler's precision leaves float without a default
+        let source = "#version 330 core\n// no default precision\nuniform highp sampler2DShadow s;\nvoid main() {}";
+        assert_eq!(
+            webgl(source).lines().nth(1),
+            Some("precision mediump float;")
+        );
+        let source = "#version 330 core\nprecision highp float;\nvoid main() {}";
+        assert_eq!(webgl(source).matches("precision").count(), 1);
     }
 }
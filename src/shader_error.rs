@@ -0,0 +1,237 @@
+use crate::preprocessor::Preprocessed;
+use crate::shader::ShaderType;
+use std::fmt;
+
+/// lines of source shown before and after every line the driver complains about
+const CONTEXT_LINES: usize = 2;
+
+/// Why a `MyShader` could not be created or used. `Debug` prints the same annotated listing
+/// as `Display`, so the `.expect(..)` of the tutorials shows the offending lines.
+pub enum ShaderError {
+    /// a shader file could not be loaded
+    Load {
+        path: String,
+        message: String,
+    },
+    /// an `#include` could not be resolved
+    Preprocess {
+        stage: ShaderType,
+        message: String,
+    },
+    /// `source` is what was handed to the driver, with the table to map its lines back to the
+    /// original files
+    Compile {
+        stage: ShaderType,
+        log: String,
+        source: Preprocessed,
+    },
+    Link {
+        log: String,
+    },
+    MissingUniform {
+        shader: Option<String>,
+        name: String,
+    },
+    /// `dispatch_size` on a program without a known work group size
+    UnknownWorkGroupSize {
+        shader: Option<String>,
+    },
+}
+
+/// one message of a driver compile log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// line in the source handed to the driver
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/// Splits a compile log into messages. Knows the Mesa `0:12(5): error: ..`,
+/// NVIDIA `0(12) : error C0000: ..` and ANGLE/AMD `ERROR: 0:12: ..` formats, other lines are
+/// kept as messages without a location.
+pub fn parse_log(log: &str) -> Vec<LogEntry> {
+    log.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_log_line(line).unwrap_or_else(|| LogEntry {
+                line: None,
+                column: None,
+                message: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_log_line(line: &str) -> Option<LogEntry> {
+    let (severity, rest) = ["ERROR: ", "WARNING: "]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix).map(|rest| (prefix.trim(), rest)))
+        .unwrap_or(("", line));
+
+    let (_source_string, rest) = split_number(rest)?;
+    let (line_number, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let (line_number, rest) = split_number(rest)?;
+        // Mesa appends the column in parentheses
+        match rest.strip_prefix('(').and_then(split_number) {
+            Some((column, after)) if after.starts_with(')') => {
+                (line_number, Some(column), &after[1..])
+            }
+            _ => (line_number, None, rest),
+        }
+    } else {
+        let (line_number, rest) = split_number(rest.strip_prefix('(')?)?;
+        (line_number, None, rest.strip_prefix(')')?)
+    };
+
+    let message = rest.trim_start_matches([':', ' ']);
+    Some(LogEntry {
+        line: Some(line_number),
+        column,
+        message: if severity.is_empty() {
+            message.to_string()
+        } else {
+            format!("{} {}", severity.to_lowercase(), message)
+        },
+    })
+}
+
+fn split_number(s: &str) -> Option<(usize, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    Some((s[..end].parse().ok()?, &s[end..]))
+}
+
+impl ShaderError {
+    fn fmt_compile(
+        f: &mut fmt::Formatter<'_>,
+        stage: ShaderType,
+        log: &str,
+        source: &Preprocessed,
+    ) -> fmt::Result {
+        writeln!(f, "{} shader failed to compile", stage.name())?;
+        let source_lines = source.source.lines().collect::<Vec<_>>();
+        for entry in parse_log(log) {
+            let Some((file, line)) = entry.line.and_then(|line| source.location(line)) else {
+                writeln!(f, "{}", entry.message)?;
+                continue;
+            };
+            match entry.column {
+                Some(column) => writeln!(f, "{}:{}:{}: {}", file, line, column, entry.message)?,
+                None => writeln!(f, "{}:{}: {}", file, line, entry.message)?,
+            }
+
+            // context from the same file, lines of other files or added by the preprocessor
+            // are skipped
+            let index = entry.line.unwrap() - 1;
+            let first = index.saturating_sub(CONTEXT_LINES);
+            let context = source_lines
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(index - first + CONTEXT_LINES + 1);
+            for (i, text) in context {
+                match source.location(i + 1) {
+                    Some((other_file, other_line)) if other_file == file => {
+                        let marker = if i == index { '>' } else { ' ' };
+                        writeln!(f, "{} {:>5} | {}", marker, other_line, text)?;
+                    }
+                    _ => {}
+                }
+                if let (true, Some(column)) = (i == index, entry.column) {
+                    writeln!(f, "  {:>5} | {:>width$}", "", "^", width = column)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Load { path, message } => {
+                write!(f, "cannot load shader {}: {}", path, message)
+            }
+            ShaderError::Preprocess { stage, message } => {
+                write!(f, "{} shader: {}", stage.name(), message)
+            }
+            ShaderError::Compile { stage, log, source } => {
+                Self::fmt_compile(f, *stage, log, source)
+            }
+            ShaderError::Link { log } => write!(f, "program failed to link\n{}", log),
+            ShaderError::MissingUniform { shader, name } => match shader {
+                Some(shader) => write!(f, "cannot find uniform {} in shader {}", name, shader),
+                None => write!(f, "cannot find uniform {}", name),
+            },
+            ShaderError::UnknownWorkGroupSize { shader } => match shader {
+                Some(shader) => write!(f, "unknown work group size of shader {}", shader),
+                None => write!(f, "unknown work group size"),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `expect` prints the Debug form, show the listing instead of the escaped log
+        writeln!(f)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: usize, column: Option<usize>, message: &str) -> LogEntry {
+        LogEntry {
+            line: Some(line),
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn mesa_log() {
+        let log = "0:12(5): error: `lightDir' undeclared\n0:14(10): warning: unused variable\n";
+        assert_eq!(
+            parse_log(log),
+            [
+                entry(12, Some(5), "error: `lightDir' undeclared"),
+                entry(14, Some(10), "warning: unused variable"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nvidia_log() {
+        let log = "0(12) : error C1008: undefined variable \"lightDir\"\n";
+        assert_eq!(
+            parse_log(log),
+            [entry(
+                12,
+                None,
+                "error C1008: undefined variable \"lightDir\""
+            )]
+        );
+    }
+
+    #[test]
+    fn angle_log() {
+        let log = "ERROR: 0:12: 'lightDir' : undeclared identifier\nERROR: 1 compilation errors.  No code generated.\n";
+        assert_eq!(
+            parse_log(log),
+            [
+                entry(12, None, "error: 'lightDir' : undeclared identifier"),
+                LogEntry {
+                    line: None,
+                    column: None,
+                    message: "ERROR: 1 compilation errors.  No code generated.".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// cascaded shadow maps of a directional light, see cascaded_shadow.rs which sets these uniforms.
+// Cascade i covers view depths up to cascadeSplits[i] and is layer i of cascadeShadowMap. Near
+// the end of a cascade its shadow is blended into the next one over cascadeBlend of its range
+
+const int MAX_CASCADES = 4;
+
+// highp as GLSL ES gives sampler2DArray no default qualifier
+uniform highp sampler2DArray cascadeShadowMap;
+uniform mat4 cascadeMatrices[MAX_CASCADES];
+// far end of each cascade, in view space depth
+uniform float cascadeSplits[MAX_CASCADES];
+// world space size of a shadow map texel in each cascade
+uniform float cascadeTexelSizes[MAX_CASCADES];
+uniform int cascadeCount;
+uniform float cascadeBlend;
+// how far the position is moved along the normal before the lookup, in texels
+uniform float cascadeNormalOffset;
+// depth bias of a surface facing away from the light, the bias of one facing it is a tenth
+uniform float cascadeBias;
+
+int CascadeIndex(float viewDepth)
+{
+    for (int i = 0; i < MAX_CASCADES; ++i)
+    {
+        if (i >= cascadeCount - 1 || viewDepth < cascadeSplits[i])
+            return i;
+    }
+    return cascadeCount - 1;
+}
+
+float CascadeShadow(int cascade, vec3 worldPos, vec3 normal, vec3 lightDir)
+{
+    // normal offset against acne, larger for the coarser cascades
+    vec3 offsetPos = worldPos + normal * cascadeTexelSizes[cascade] * cascadeNormalOffset;
+    vec4 fragPosLightSpace = cascadeMatrices[cascade] * vec4(offsetPos, 1.0);
+    vec3 projCoords = fragPosLightSpace.xyz / fragPosLightSpace.w * 0.5 + 0.5;
+    // beyond the far plane of the light
+    if (projCoords.z > 1.0)
+        return 0.0;
+    float currentDepth = projCoords.z;
+    float bias = max(cascadeBias * (1.0 - dot(normal, lightDir)), cascadeBias * 0.1);
+
+    // 3x3 PCF
+    float shadow = 0.0;
+    vec2 texelSize = 1.0 / vec2(textureSize(cascadeShadowMap, 0).xy);
+    for (int x = -1; x <= 1; ++x)
+    {
+        for (int y = -1; y <= 1; ++y)
+        {
+            vec2 uv = projCoords.xy + vec2(x, y) * texelSize;
+            float pcfDepth = texture(cascadeShadowMap, vec3(uv, float(cascade))).r;
+            shadow += currentDepth - bias > pcfDepth ? 1.0 : 0.0;
+        }
+    }
+    return shadow / 9.0;
+}
+
+// weight of the next cascade at viewDepth in cascade `cascade`
+float CascadeBlendWeight(int cascade, float viewDepth)
+{
+    if (cascade >= cascadeCount - 1 || cascadeBlend <= 0.0)
+        return 0.0;
+    float start = cascade == 0 ? 0.0 : cascadeSplits[cascade - 1];
+    float end = cascadeSplits[cascade];
+    float blendStart = end - (end - start) * cascadeBlend;
+    return clamp((viewDepth - blendStart) / (end - blendStart), 0.0, 1.0);
+}
+
+// 1.0 in shadow, 0.0 lit. viewDepth is the distance to the camera along its view direction,
+// lightDir points towards the light
+float CascadedShadowCalculation(vec3 worldPos, float viewDepth, vec3 normal, vec3 lightDir)
+{
+    int cascade = CascadeIndex(viewDepth);
+    float shadow = CascadeShadow(cascade, worldPos, normal, lightDir);
+    float blend = CascadeBlendWeight(cascade, viewDepth);
+    if (blend > 0.0)
+        shadow = mix(shadow, CascadeShadow(cascade + 1, worldPos, normal, lightDir), blend);
+    return shadow;
+}
+
+// red, green, blue and yellow for cascades 0 to 3, blended like the shadows
+vec3 CascadeDebugColor(float viewDepth)
+{
+    const vec3 colors[MAX_CASCADES] = vec3[](
+        vec3(1.0, 0.3, 0.3), vec3(0.3, 1.0, 0.3), vec3(0.3, 0.3, 1.0), vec3(1.0, 1.0, 0.3)
+    );
+    int cascade = CascadeIndex(viewDepth);
+    float blend = CascadeBlendWeight(cascade, viewDepth);
+    if (blend > 0.0)
+        return mix(colors[cascade], colors[cascade + 1], blend);
+    return colors[cascade];
+}
@@ -0,0 +1,30 @@
+// shading of a G-buffer sample (world space position, normal, albedo and specular intensity)
+// by a point light whose contribution is cut off at `radius`, where attenuation has made it
+// too dark to see
+
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D gAlbedoSpec;
+
+uniform vec3 viewPos;
+uniform float lightLinear;
+uniform float lightQuadratic;
+
+vec3 CalcDeferredPointLight(vec3 lightPos, vec3 lightColor, float radius, vec3 fragPos, vec3 normal, vec3 albedo, float specular)
+{
+    // calculate distance between light source and current fragment
+    float distance = length(lightPos - fragPos);
+    if (distance > radius)
+        return vec3(0.0);
+    vec3 viewDir = normalize(viewPos - fragPos);
+    // diffuse
+    vec3 lightDir = normalize(lightPos - fragPos);
+    vec3 diffuse = max(dot(normal, lightDir), 0.0) * albedo * lightColor;
+    // specular
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), 16.0);
+    vec3 specularColor = lightColor * spec * specular;
+    // attenuation
+    float attenuation = 1.0 / (1.0 + lightLinear * distance + lightQuadratic * distance * distance);
+    return (diffuse + specularColor) * attenuation;
+}
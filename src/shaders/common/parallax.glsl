@@ -0,0 +1,106 @@
+// parallax mapping of a height map, see parallax.rs which sets these uniforms.
+// parallaxMethod picks the method: 0 off, 1 basic offset, 2 steep, 3 parallax occlusion.
+// Heights are 1.0 at the surface and 0.0 at its deepest, heightScale below it in uv units;
+// the view and light directions are in tangent space
+
+const int PARALLAX_OFF = 0;
+const int PARALLAX_BASIC = 1;
+const int PARALLAX_STEEP = 2;
+const int PARALLAX_OCCLUSION = 3;
+const int MAX_PARALLAX_LAYERS = 64;
+
+uniform int parallaxMethod;
+uniform float heightScale;
+// parallax occlusion mapping blends from min layers head on to max layers at grazing angles
+uniform float parallaxMinLayers;
+uniform float parallaxMaxLayers;
+uniform bool parallaxSelfShadow;
+
+// layers along the ray, more at grazing angles where the ray crosses more texels
+float ParallaxLayers(vec3 dir)
+{
+    if (parallaxMethod == PARALLAX_STEEP)
+        return parallaxMaxLayers;
+    return mix(parallaxMaxLayers, parallaxMinLayers, abs(dir.z));
+}
+
+// depth below the surface, sampled with the gradients of the original texture coordinates as
+// those of the offset ones are undefined in the loops below
+float ParallaxDepth(sampler2D heightMap, vec2 texCoords, vec2 dx, vec2 dy)
+{
+    return 1.0 - textureGrad(heightMap, texCoords, dx, dy).r;
+}
+
+// texture coordinates where the ray to the eye (viewDir, towards the viewer) hits the height map
+vec2 ParallaxMapping(sampler2D heightMap, vec2 texCoords, vec3 viewDir)
+{
+    if (parallaxMethod == PARALLAX_OFF)
+        return texCoords;
+    vec2 dx = dFdx(texCoords);
+    vec2 dy = dFdy(texCoords);
+    if (parallaxMethod == PARALLAX_BASIC)
+    {
+        float depth = ParallaxDepth(heightMap, texCoords, dx, dy);
+        return texCoords - viewDir.xy / viewDir.z * (depth * heightScale);
+    }
+
+    // steep parallax: step into the surface until a layer is below the height map
+    float numLayers = ParallaxLayers(viewDir);
+    float layerDepth = 1.0 / numLayers;
+    // the amount to shift the texture coordinates per layer (from vector P)
+    vec2 deltaTexCoords = viewDir.xy / viewDir.z * heightScale / numLayers;
+    float currentLayerDepth = 0.0;
+    vec2 currentTexCoords = texCoords;
+    float currentDepth = ParallaxDepth(heightMap, currentTexCoords, dx, dy);
+    for (int i = 0; i < MAX_PARALLAX_LAYERS; ++i)
+    {
+        if (currentLayerDepth >= currentDepth || float(i) >= numLayers)
+            break;
+        currentTexCoords -= deltaTexCoords;
+        currentDepth = ParallaxDepth(heightMap, currentTexCoords, dx, dy);
+        currentLayerDepth += layerDepth;
+    }
+    if (parallaxMethod == PARALLAX_STEEP)
+        return currentTexCoords;
+
+    // parallax occlusion: intersect the line between the depths before and after the collision
+    vec2 prevTexCoords = currentTexCoords + deltaTexCoords;
+    float afterDepth = currentDepth - currentLayerDepth;
+    float beforeDepth = ParallaxDepth(heightMap, prevTexCoords, dx, dy) - currentLayerDepth + layerDepth;
+    float weight = afterDepth / (afterDepth - beforeDepth);
+    return mix(currentTexCoords, prevTexCoords, clamp(weight, 0.0, 1.0));
+}
+
+// 1.0 in the shadow of the height map, 0.0 lit, at texCoords found by ParallaxMapping.
+// Steps from there towards the light (lightDir, towards the light) up to the surface; the
+// highest point above the ray, nearer ones counting more, darkens for soft edges
+float ParallaxSelfShadow(sampler2D heightMap, vec2 texCoords, vec3 lightDir)
+{
+    if (!parallaxSelfShadow || parallaxMethod == PARALLAX_OFF)
+        return 0.0;
+    // facing away from the light, the lighting is dark already
+    if (lightDir.z <= 0.0)
+        return 0.0;
+    vec2 dx = dFdx(texCoords);
+    vec2 dy = dFdy(texCoords);
+    float startDepth = ParallaxDepth(heightMap, texCoords, dx, dy);
+    if (startDepth <= 0.0)
+        return 0.0;
+    float numLayers = ParallaxLayers(lightDir);
+    float layerDepth = startDepth / numLayers;
+    vec2 deltaTexCoords = lightDir.xy / lightDir.z * heightScale * layerDepth;
+    float currentLayerDepth = startDepth - layerDepth;
+    vec2 currentTexCoords = texCoords + deltaTexCoords;
+    float shadow = 0.0;
+    for (int i = 1; i < MAX_PARALLAX_LAYERS; ++i)
+    {
+        if (currentLayerDepth <= 0.0 || float(i) >= numLayers)
+            break;
+        float occluder = currentLayerDepth - ParallaxDepth(heightMap, currentTexCoords, dx, dy);
+        shadow = max(shadow, occluder * (1.0 - float(i) / numLayers));
+        currentLayerDepth -= layerDepth;
+        currentTexCoords += deltaTexCoords;
+    }
+    // occluders a tenth of the depth range above the ray shadow fully
+    return clamp(shadow * 10.0, 0.0, 1.0);
+}
@@ -0,0 +1,45 @@
+// shadow of a point light from a depth cubemap holding the distance to the light divided by
+// farPlane, see point_shadow.rs. With pcf the cubemap is sampled in 20 directions around the
+// fragment on a disk growing with the view distance, otherwise only towards the fragment
+
+// array of offset direction for sampling
+const vec3 gridSamplingDisk[20] = vec3[]
+(
+   vec3(1, 1,  1), vec3( 1, -1,  1), vec3(-1, -1,  1), vec3(-1, 1,  1),
+   vec3(1, 1, -1), vec3( 1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+   vec3(1, 1,  0), vec3( 1, -1,  0), vec3(-1, -1,  0), vec3(-1, 1,  0),
+   vec3(1, 0,  1), vec3(-1,  0,  1), vec3( 1,  0, -1), vec3(-1, 0, -1),
+   vec3(0, 1,  1), vec3( 0, -1,  1), vec3( 0, -1, -1), vec3( 0, 1, -1)
+);
+
+float PointShadowCalculation(samplerCube depthMap, vec3 fragPos, vec3 lightPos, vec3 viewPos,
+                             float farPlane, float bias, bool pcf)
+{
+    // get vector between fragment position and light position
+    vec3 fragToLight = fragPos - lightPos;
+    // now get current linear depth as the length between the fragment and light position
+    float currentDepth = length(fragToLight);
+    if (!pcf)
+    {
+        // use the light to fragment vector to sample from the depth map
+        float closestDepth = texture(depthMap, fragToLight).r;
+        // it is currently in linear range between [0,1], re-transform back to original value
+        closestDepth *= farPlane;
+        // now test for shadows
+        return currentDepth - bias > closestDepth ? 1.0 : 0.0;
+    }
+
+    float shadow = 0.0;
+    int samples = 20;
+    float viewDistance = length(viewPos - fragPos);
+    float diskRadius = (1.0 + (viewDistance / farPlane)) / 25.0;
+    for (int i = 0; i < samples; ++i)
+    {
+        float closestDepth = texture(depthMap, fragToLight + gridSamplingDisk[i] * diskRadius).r;
+        closestDepth *= farPlane; // undo mapping [0;1]
+        if (currentDepth - bias > closestDepth)
+            shadow += 1.0;
+    }
+    shadow /= float(samples);
+    return shadow;
+}
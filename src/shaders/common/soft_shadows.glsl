@@ -0,0 +1,164 @@
+// filtered shadows of a single 2D shadow map, see soft_shadow.rs which sets these uniforms.
+// shadowFilter picks the filter: 0 hardware comparison, 1 Poisson PCF, 2 PCSS, 3 VSM, 4 ESM
+
+const int SHADOW_HARDWARE = 0;
+const int SHADOW_POISSON_PCF = 1;
+const int SHADOW_PCSS = 2;
+const int SHADOW_VSM = 3;
+const int SHADOW_ESM = 4;
+const int MAX_SHADOW_TAPS = 32;
+
+// the same depth texture, through a sampler comparing with the reference depth and one that
+// returns the stored depth. highp as GLSL ES gives shadow samplers no default qualifier
+uniform highp sampler2DShadow shadowMapCompare;
+uniform highp sampler2D shadowMapDepth;
+// blurred (depth, depth^2) for VSM, exp(esmExponent * depth) for ESM
+uniform highp sampler2D shadowMoments;
+uniform mat4 lightSpaceMatrix;
+
+uniform int shadowFilter;
+// Poisson disk samples of PCF and of both PCSS steps
+uniform int shadowTaps;
+// radius of Poisson PCF, in texels
+uniform float shadowFilterRadius;
+// PCSS penumbra width per unit of depth between blocker and receiver, in shadow map uv
+uniform float shadowLightSize;
+// in shadow map depth, constant and times the tangent of the angle to the light
+uniform float shadowBias;
+uniform float shadowSlopeBias;
+// in world units, times (1 - N.L)
+uniform float shadowNormalOffset;
+uniform float shadowMinVariance;
+// VSM probabilities below this are cut to 0, against light bleeding
+uniform float shadowLightBleed;
+uniform float esmExponent;
+
+const vec2 poissonDisk[MAX_SHADOW_TAPS] = vec2[](
+    vec2(-0.975402, -0.0711386), vec2(-0.920347, -0.41142), vec2(-0.883908, 0.217872),
+    vec2(-0.884518, 0.568041), vec2(-0.811945, 0.90521), vec2(-0.792474, -0.779962),
+    vec2(-0.614856, 0.386578), vec2(-0.580859, -0.208777), vec2(-0.53795, 0.716666),
+    vec2(-0.515427, 0.0899991), vec2(-0.454634, -0.707938), vec2(-0.420942, 0.991272),
+    vec2(-0.261147, 0.588488), vec2(-0.211219, 0.114841), vec2(-0.146336, -0.259194),
+    vec2(-0.139439, -0.888668), vec2(0.0116886, 0.326395), vec2(0.0380566, 0.625477),
+    vec2(0.0625935, -0.50853), vec2(0.125584, 0.0469069), vec2(0.169469, -0.997253),
+    vec2(0.320597, 0.291055), vec2(0.359172, -0.633717), vec2(0.435713, -0.250832),
+    vec2(0.507797, -0.916562), vec2(0.545763, 0.730216), vec2(0.56859, 0.11655),
+    vec2(0.743156, -0.505173), vec2(0.736442, -0.189734), vec2(0.843562, 0.357036),
+    vec2(0.865413, 0.763726), vec2(0.872093, -0.927)
+);
+
+// rotates the disk per pixel, which turns the banding of few taps into noise
+mat2 PoissonRotation()
+{
+    float angle = 6.2831853 * fract(sin(dot(gl_FragCoord.xy, vec2(12.9898, 78.233))) * 43758.5453);
+    float s = sin(angle);
+    float c = cos(angle);
+    return mat2(c, s, -s, c);
+}
+
+// change of the receiver's shadow map depth per unit of uv, from the screen space derivatives.
+// Taps away from the center compare against the receiver's plane there, so wide kernels do not
+// shadow a sloped surface with itself
+vec2 ReceiverPlaneDepthSlope(vec3 projCoords)
+{
+    vec3 dx = dFdx(projCoords);
+    vec3 dy = dFdy(projCoords);
+    float det = dx.x * dy.y - dx.y * dy.x;
+    if (abs(det) < 1e-12)
+        return vec2(0.0);
+    return vec2(dy.y * dx.z - dx.y * dy.z, dx.x * dy.z - dy.x * dx.z) / det;
+}
+
+// fraction of the taps within `radius` (in uv) that are occluded at `depth`
+float PoissonPcf(vec2 uv, float depth, float radius, vec2 depthSlope)
+{
+    mat2 rotation = PoissonRotation();
+    float lit = 0.0;
+    int taps = clamp(shadowTaps, 1, MAX_SHADOW_TAPS);
+    for (int i = 0; i < MAX_SHADOW_TAPS; ++i)
+    {
+        if (i >= taps)
+            break;
+        vec2 offset = rotation * poissonDisk[i] * radius;
+        lit += texture(shadowMapCompare, vec3(uv + offset, depth + dot(depthSlope, offset)));
+    }
+    return 1.0 - lit / float(taps);
+}
+
+float Pcss(vec2 uv, float depth, vec2 texelSize, vec2 depthSlope)
+{
+    // 1. average depth of the blockers within the largest penumbra the receiver can have
+    mat2 rotation = PoissonRotation();
+    float searchRadius = shadowLightSize * depth;
+    float blockerSum = 0.0;
+    float blockers = 0.0;
+    int taps = clamp(shadowTaps, 1, MAX_SHADOW_TAPS);
+    for (int i = 0; i < MAX_SHADOW_TAPS; ++i)
+    {
+        if (i >= taps)
+            break;
+        vec2 offset = rotation * poissonDisk[i] * searchRadius;
+        float sampleDepth = texture(shadowMapDepth, uv + offset).r;
+        if (sampleDepth < depth + dot(depthSlope, offset))
+        {
+            blockerSum += sampleDepth;
+            blockers += 1.0;
+        }
+    }
+    if (blockers == 0.0)
+        return 0.0;
+    // 2. the penumbra grows with the distance between blocker and receiver, as for a
+    // directional light of an orthographic shadow map
+    float penumbra = (depth - blockerSum / blockers) * shadowLightSize;
+    // 3. PCF over the penumbra
+    return PoissonPcf(uv, depth, max(penumbra, texelSize.x), depthSlope);
+}
+
+float Vsm(vec2 uv, float depth)
+{
+    vec2 moments = texture(shadowMoments, uv).rg;
+    if (depth <= moments.x)
+        return 0.0;
+    // Chebyshev's upper bound of the lit fraction
+    float variance = max(moments.y - moments.x * moments.x, shadowMinVariance);
+    float d = depth - moments.x;
+    float pMax = variance / (variance + d * d);
+    // light bleeding reduction
+    pMax = clamp((pMax - shadowLightBleed) / (1.0 - shadowLightBleed), 0.0, 1.0);
+    return 1.0 - pMax;
+}
+
+float Esm(vec2 uv, float depth)
+{
+    float occluder = texture(shadowMoments, uv).r;
+    return 1.0 - clamp(occluder * exp(-esmExponent * depth), 0.0, 1.0);
+}
+
+// 1.0 in shadow, 0.0 lit. lightDir points towards the light
+float SoftShadowCalculation(vec3 worldPos, vec3 normal, vec3 lightDir)
+{
+    float nDotL = clamp(dot(normal, lightDir), 0.0, 1.0);
+    // normal offset: grazing surfaces are moved furthest out of their own shadow
+    vec3 offsetPos = worldPos + normal * shadowNormalOffset * (1.0 - nDotL);
+    vec4 fragPosLightSpace = lightSpaceMatrix * vec4(offsetPos, 1.0);
+    vec3 projCoords = fragPosLightSpace.xyz / fragPosLightSpace.w * 0.5 + 0.5;
+    // before any branch, derivatives need all pixels of a quad
+    vec2 depthSlope = ReceiverPlaneDepthSlope(projCoords);
+    // keep the shadow at 0.0 when outside the far_plane region of the light's frustum.
+    if (projCoords.z > 1.0)
+        return 0.0;
+    // slope scaled bias, tan(acos(N.L)) clamped for surfaces edge on to the light
+    float slope = min(sqrt(1.0 - nDotL * nDotL) / max(nDotL, 0.001), 10.0);
+    float depth = projCoords.z - shadowBias - shadowSlopeBias * slope;
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMapDepth, 0));
+
+    if (shadowFilter == SHADOW_HARDWARE)
+        return 1.0 - texture(shadowMapCompare, vec3(projCoords.xy, depth));
+    if (shadowFilter == SHADOW_POISSON_PCF)
+        return PoissonPcf(projCoords.xy, depth, shadowFilterRadius * texelSize.x, depthSlope);
+    if (shadowFilter == SHADOW_PCSS)
+        return Pcss(projCoords.xy, depth, texelSize, depthSlope);
+    if (shadowFilter == SHADOW_VSM)
+        return Vsm(projCoords.xy, depth);
+    return Esm(projCoords.xy, depth);
+}
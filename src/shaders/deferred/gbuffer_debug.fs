@@ -0,0 +1,26 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D gAlbedoSpec;
+
+// 0: position, 1: normal, 2: albedo, 3: specular intensity
+uniform int attachment;
+
+void main()
+{
+    vec3 color;
+    if (attachment == 0)
+        color = texture(gPosition, TexCoords).rgb;
+    else if (attachment == 1)
+        // from [-1, 1] to [0, 1]
+        color = texture(gNormal, TexCoords).rgb * 0.5 + 0.5;
+    else if (attachment == 2)
+        color = texture(gAlbedoSpec, TexCoords).rgb;
+    else
+        color = vec3(texture(gAlbedoSpec, TexCoords).a);
+    FragColor = vec4(color, 1.0);
+}
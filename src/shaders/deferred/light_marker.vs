@@ -0,0 +1,17 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+// per light, advanced once per instance
+layout (location = 1) in vec3 aLightPos;
+layout (location = 2) in vec3 aLightColor;
+
+out vec3 LightColor;
+
+uniform mat4 projection;
+uniform mat4 view;
+uniform float size;
+
+void main()
+{
+    LightColor = aLightColor;
+    gl_Position = projection * view * vec4(aLightPos + aPos * size, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 330 core
+out vec4 FragColor;
+
+flat in vec3 LightPos;
+flat in vec3 LightColor;
+flat in float LightRadius;
+
+#include "common/deferred.glsl"
+
+uniform vec2 screenSize;
+
+void main()
+{
+    // the volume only selects the pixels, the G-buffer is read where it covers the screen
+    vec2 TexCoords = gl_FragCoord.xy / screenSize;
+    vec3 FragPos = texture(gPosition, TexCoords).rgb;
+    vec3 Normal = texture(gNormal, TexCoords).rgb;
+    vec4 AlbedoSpec = texture(gAlbedoSpec, TexCoords);
+
+    vec3 lighting = CalcDeferredPointLight(LightPos, LightColor, LightRadius, FragPos, Normal, AlbedoSpec.rgb, AlbedoSpec.a);
+    FragColor = vec4(lighting, 1.0);
+}
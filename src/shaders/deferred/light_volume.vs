@@ -0,0 +1,22 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+// per light, advanced once per instance
+layout (location = 1) in vec3 aLightPos;
+layout (location = 2) in vec3 aLightColor;
+layout (location = 3) in float aLightRadius;
+
+flat out vec3 LightPos;
+flat out vec3 LightColor;
+flat out float LightRadius;
+
+uniform mat4 projection;
+uniform mat4 view;
+
+void main()
+{
+    LightPos = aLightPos;
+    LightColor = aLightColor;
+    LightRadius = aLightRadius;
+    // the unit sphere scaled to the radius of the light
+    gl_Position = projection * view * vec4(aLightPos + aPos * aLightRadius, 1.0);
+}
@@ -0,0 +1,35 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+#include "common/deferred.glsl"
+
+// lights of one batch, the pass is drawn once per batch with additive blending
+const int MAX_LIGHTS = 32;
+uniform vec3 lightPositions[MAX_LIGHTS];
+uniform vec3 lightColors[MAX_LIGHTS];
+uniform float lightRadii[MAX_LIGHTS];
+uniform int lightCount;
+// hard-coded ambient component, only added by the first batch
+uniform float ambient;
+
+void main()
+{
+    // retrieve data from gbuffer
+    vec3 FragPos = texture(gPosition, TexCoords).rgb;
+    vec3 Normal = texture(gNormal, TexCoords).rgb;
+    vec4 AlbedoSpec = texture(gAlbedoSpec, TexCoords);
+    vec3 Diffuse = AlbedoSpec.rgb;
+    float Specular = AlbedoSpec.a;
+
+    // then calculate lighting as usual
+    vec3 lighting = Diffuse * ambient;
+    for (int i = 0; i < MAX_LIGHTS; ++i)
+    {
+        if (i >= lightCount)
+            break;
+        lighting += CalcDeferredPointLight(lightPositions[i], lightColors[i], lightRadii[i], FragPos, Normal, Diffuse, Specular);
+    }
+    FragColor = vec4(lighting, 1.0);
+}
@@ -0,0 +1,77 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// the previous, twice as large, level of the chain
+uniform sampler2D srcTexture;
+// whether this is the first downsample, which averages with Karis weights against fireflies
+uniform bool firstLevel;
+
+vec3 powVec3(vec3 v, float p)
+{
+    return vec3(pow(v.x, p), pow(v.y, p), pow(v.z, p));
+}
+
+float karisWeight(vec3 color)
+{
+    float luma = dot(powVec3(color, 1.0 / 2.2), vec3(0.2126, 0.7152, 0.0722)) * 0.25;
+    return 1.0 / (1.0 + luma);
+}
+
+// 13 bilinear taps as in Call of Duty: Advanced Warfare, see
+// https://learnopengl.com/Guest-Articles/2022/Phys.-Based-Bloom
+void main()
+{
+    vec2 srcTexelSize = 1.0 / vec2(textureSize(srcTexture, 0));
+    float x = srcTexelSize.x;
+    float y = srcTexelSize.y;
+
+    // a - b - c
+    // - j - k -
+    // d - e - f
+    // - l - m -
+    // g - h - i
+    vec3 a = texture(srcTexture, vec2(TexCoords.x - 2.0 * x, TexCoords.y + 2.0 * y)).rgb;
+    vec3 b = texture(srcTexture, vec2(TexCoords.x, TexCoords.y + 2.0 * y)).rgb;
+    vec3 c = texture(srcTexture, vec2(TexCoords.x + 2.0 * x, TexCoords.y + 2.0 * y)).rgb;
+
+    vec3 d = texture(srcTexture, vec2(TexCoords.x - 2.0 * x, TexCoords.y)).rgb;
+    vec3 e = texture(srcTexture, vec2(TexCoords.x, TexCoords.y)).rgb;
+    vec3 f = texture(srcTexture, vec2(TexCoords.x + 2.0 * x, TexCoords.y)).rgb;
+
+    vec3 g = texture(srcTexture, vec2(TexCoords.x - 2.0 * x, TexCoords.y - 2.0 * y)).rgb;
+    vec3 h = texture(srcTexture, vec2(TexCoords.x, TexCoords.y - 2.0 * y)).rgb;
+    vec3 i = texture(srcTexture, vec2(TexCoords.x + 2.0 * x, TexCoords.y - 2.0 * y)).rgb;
+
+    vec3 j = texture(srcTexture, vec2(TexCoords.x - x, TexCoords.y + y)).rgb;
+    vec3 k = texture(srcTexture, vec2(TexCoords.x + x, TexCoords.y + y)).rgb;
+    vec3 l = texture(srcTexture, vec2(TexCoords.x - x, TexCoords.y - y)).rgb;
+    vec3 m = texture(srcTexture, vec2(TexCoords.x + x, TexCoords.y - y)).rgb;
+
+    vec3 downsample;
+    if (firstLevel)
+    {
+        // average groups of 4 weighted by their luma
+        vec3 group0 = (a + b + d + e) * (0.125 / 4.0);
+        vec3 group1 = (b + c + e + f) * (0.125 / 4.0);
+        vec3 group2 = (d + e + g + h) * (0.125 / 4.0);
+        vec3 group3 = (e + f + h + i) * (0.125 / 4.0);
+        vec3 group4 = (j + k + l + m) * (0.5 / 4.0);
+        group0 *= karisWeight(group0);
+        group1 *= karisWeight(group1);
+        group2 *= karisWeight(group2);
+        group3 *= karisWeight(group3);
+        group4 *= karisWeight(group4);
+        downsample = group0 + group1 + group2 + group3 + group4;
+    }
+    else
+    {
+        downsample = e * 0.125;
+        downsample += (a + c + g + i) * 0.03125;
+        downsample += (b + d + f + h) * 0.0625;
+        downsample += (j + k + l + m) * 0.125;
+    }
+    // keep it positive, blending would spread a negative value
+    FragColor = vec4(max(downsample, 0.0001), 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D hdrBuffer;
+// luminance from which colors start to bloom
+uniform float threshold;
+
+void main()
+{
+    vec3 color = texture(hdrBuffer, TexCoords).rgb;
+    float brightness = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    // only the part above the threshold blooms, so it fades in instead of popping
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 1e-4);
+    FragColor = vec4(color * contribution, 1.0);
+}
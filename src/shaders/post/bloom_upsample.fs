@@ -0,0 +1,37 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// the next, half as large, level of the chain
+uniform sampler2D srcTexture;
+// in texture coordinates
+uniform float filterRadius;
+
+// 3x3 tent filter, added to the larger level by blending
+void main()
+{
+    float x = filterRadius;
+    float y = filterRadius;
+
+    // a - b - c
+    // d - e - f
+    // g - h - i
+    vec3 a = texture(srcTexture, vec2(TexCoords.x - x, TexCoords.y + y)).rgb;
+    vec3 b = texture(srcTexture, vec2(TexCoords.x, TexCoords.y + y)).rgb;
+    vec3 c = texture(srcTexture, vec2(TexCoords.x + x, TexCoords.y + y)).rgb;
+
+    vec3 d = texture(srcTexture, vec2(TexCoords.x - x, TexCoords.y)).rgb;
+    vec3 e = texture(srcTexture, vec2(TexCoords.x, TexCoords.y)).rgb;
+    vec3 f = texture(srcTexture, vec2(TexCoords.x + x, TexCoords.y)).rgb;
+
+    vec3 g = texture(srcTexture, vec2(TexCoords.x - x, TexCoords.y - y)).rgb;
+    vec3 h = texture(srcTexture, vec2(TexCoords.x, TexCoords.y - y)).rgb;
+    vec3 i = texture(srcTexture, vec2(TexCoords.x + x, TexCoords.y - y)).rgb;
+
+    vec3 upsample = e * 4.0;
+    upsample += (b + d + f + h) * 2.0;
+    upsample += (a + c + g + i);
+    upsample *= 1.0 / 16.0;
+    FragColor = vec4(upsample, 1.0);
+}
@@ -0,0 +1,61 @@
+#version 330 core
+out vec4 FragColor;
+
+// log2 luminance of the scene with its mip chain
+uniform sampler2D luminance;
+// result of the last frame, 1x1
+uniform sampler2D previousExposure;
+// mip level whose texels make up the histogram
+uniform int level;
+uniform float minLogLuminance;
+uniform float maxLogLuminance;
+// the darkest and brightest parts of the histogram are ignored
+uniform float lowPercent;
+uniform float highPercent;
+// luminance the average is mapped to, 0.18 is middle gray
+uniform float key;
+// how much of the way to the new exposure is made this frame
+uniform float adaptation;
+
+const int BINS = 32;
+
+void main()
+{
+    float histogram[BINS];
+    for (int i = 0; i < BINS; i++)
+    {
+        histogram[i] = 0.0;
+    }
+
+    ivec2 size = textureSize(luminance, level);
+    float range = maxLogLuminance - minLogLuminance;
+    for (int y = 0; y < size.y; y++)
+    {
+        for (int x = 0; x < size.x; x++)
+        {
+            float logLuminance = texelFetch(luminance, ivec2(x, y), level).r;
+            float t = clamp((logLuminance - minLogLuminance) / range, 0.0, 0.999);
+            histogram[int(t * float(BINS))] += 1.0;
+        }
+    }
+
+    // average of the bins between the percentiles
+    float total = float(size.x * size.y);
+    float low = total * lowPercent;
+    float high = total * highPercent;
+    float seen = 0.0;
+    float sum = 0.0;
+    float count = 0.0;
+    for (int i = 0; i < BINS; i++)
+    {
+        float inRange = max(0.0, min(seen + histogram[i], high) - max(seen, low));
+        seen += histogram[i];
+        sum += inRange * (minLogLuminance + (float(i) + 0.5) / float(BINS) * range);
+        count += inRange;
+    }
+    float averageLuminance = exp2(count > 0.0 ? sum / count : 0.0);
+
+    float target = key / averageLuminance;
+    float previous = texelFetch(previousExposure, ivec2(0, 0), 0).r;
+    FragColor = vec4(mix(previous, target, adaptation), 0.0, 0.0, 1.0);
+}
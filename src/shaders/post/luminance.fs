@@ -0,0 +1,14 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D hdrBuffer;
+
+void main()
+{
+    vec3 color = texture(hdrBuffer, TexCoords).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    // the mip chain then averages log luminance, i.e. the geometric mean of each region
+    FragColor = vec4(log2(max(luminance, 1e-4)), 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,97 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D hdrBuffer;
+// written by exposure.fs, 1x1
+uniform sampler2D exposureTexture;
+uniform bool autoExposure;
+// the exposure, or a factor of the automatic one
+uniform float exposure;
+// order of `ToneMapper`
+uniform int toneMapper;
+uniform bool srgb;
+
+vec3 reinhard(vec3 color)
+{
+    return color / (color + vec3(1.0));
+}
+
+vec3 exposureCurve(vec3 color)
+{
+    return vec3(1.0) - exp(-color);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 acesFilmic(vec3 color)
+{
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+// John Hable's curve from Uncharted 2
+vec3 uncharted2Partial(vec3 x)
+{
+    const float A = 0.15;
+    const float B = 0.50;
+    const float C = 0.10;
+    const float D = 0.20;
+    const float E = 0.02;
+    const float F = 0.30;
+    return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+}
+
+vec3 uncharted2(vec3 color)
+{
+    const float exposureBias = 2.0;
+    const float whitePoint = 11.2;
+    vec3 whiteScale = vec3(1.0) / uncharted2Partial(vec3(whitePoint));
+    return uncharted2Partial(color * exposureBias) * whiteScale;
+}
+
+vec3 linearToSrgb(vec3 color)
+{
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(color, vec3(0.0031308))));
+}
+
+void main()
+{
+    vec3 color = texture(hdrBuffer, TexCoords).rgb;
+    float scale = exposure;
+    if (autoExposure)
+    {
+        scale *= texelFetch(exposureTexture, ivec2(0, 0), 0).r;
+    }
+    color *= scale;
+
+    if (toneMapper == 1)
+    {
+        color = reinhard(color);
+    }
+    else if (toneMapper == 2)
+    {
+        color = exposureCurve(color);
+    }
+    else if (toneMapper == 3)
+    {
+        color = acesFilmic(color);
+    }
+    else if (toneMapper == 4)
+    {
+        color = uncharted2(color);
+    }
+    color = clamp(color, 0.0, 1.0);
+
+    if (srgb)
+    {
+        color = linearToSrgb(color);
+    }
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+#ifdef INSTANCED
+layout (location = 3) in mat4 aInstanceMatrix;
+#endif
+
+uniform mat4 lightSpaceMatrix;
+uniform mat4 model;
+
+void main()
+{
+#ifdef INSTANCED
+    gl_Position = lightSpaceMatrix * model * aInstanceMatrix * vec4(aPos, 1.0);
+#else
+    gl_Position = lightSpaceMatrix * model * vec4(aPos, 1.0);
+#endif
+}
@@ -0,0 +1,23 @@
+#version 330 core
+out vec2 FragColor;
+
+in vec2 TexCoords;
+
+uniform highp sampler2D image;
+
+uniform bool horizontal;
+const float weight[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main()
+{
+    // the gaussian of post/bloom_blur.fs, on the two moments
+    vec2 tex_offset = 1.0 / vec2(textureSize(image, 0));
+    vec2 step = horizontal ? vec2(tex_offset.x, 0.0) : vec2(0.0, tex_offset.y);
+    vec2 result = texture(image, TexCoords).rg * weight[0];
+    for (int i = 1; i < 5; ++i)
+    {
+        result += texture(image, TexCoords + step * float(i)).rg * weight[i];
+        result += texture(image, TexCoords - step * float(i)).rg * weight[i];
+    }
+    FragColor = result;
+}
@@ -0,0 +1,31 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform samplerCube depthMap;
+// GL_TEXTURE_CUBE_MAP_POSITIVE_X + face
+uniform int face;
+
+void main()
+{
+    // direction of the texel at TexCoords of the face, t points down in cubemap faces
+    vec2 st = TexCoords * 2.0 - 1.0;
+    st.y = -st.y;
+    vec3 direction;
+    if (face == 0)
+        direction = vec3(1.0, -st.y, -st.x);
+    else if (face == 1)
+        direction = vec3(-1.0, -st.y, st.x);
+    else if (face == 2)
+        direction = vec3(st.x, 1.0, st.y);
+    else if (face == 3)
+        direction = vec3(st.x, -1.0, -st.y);
+    else if (face == 4)
+        direction = vec3(st.x, -st.y, 1.0);
+    else
+        direction = vec3(-st.x, -st.y, -1.0);
+    // linear distance to the light divided by the far plane
+    float depth = texture(depthMap, direction).r;
+    FragColor = vec4(vec3(depth), 1.0);
+}
@@ -0,0 +1,23 @@
+#version 330 core
+// the depth goes to the depth texture, the moments filtered by VSM and ESM to the color target
+layout (location = 0) out vec2 Moments;
+
+// ESM instead of VSM moments
+uniform bool exponential;
+uniform float esmExponent;
+
+void main()
+{
+    float depth = gl_FragCoord.z;
+    if (exponential)
+    {
+        Moments = vec2(exp(esmExponent * depth), 0.0);
+    }
+    else
+    {
+        // the second moment includes the depth variance over the pixel, against acne on slopes
+        float dx = dFdx(depth);
+        float dy = dFdy(depth);
+        Moments = vec2(depth, depth * depth + 0.25 * (dx * dx + dy * dy));
+    }
+}
@@ -0,0 +1,59 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// view space position and normal
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+// random rotations around the normal, tiled over the screen
+uniform sampler2D texNoise;
+
+const int MAX_KERNEL_SIZE = 64;
+uniform vec3 samples[MAX_KERNEL_SIZE];
+uniform int kernelSize;
+uniform float radius;
+uniform float bias;
+uniform float power;
+
+uniform mat4 projection;
+// tile noise texture over screen based on screen dimensions divided by noise size
+uniform vec2 noiseScale;
+
+void main()
+{
+    // get input for SSAO algorithm
+    vec3 fragPos = texture(gPosition, TexCoords).xyz;
+    vec3 normal = normalize(texture(gNormal, TexCoords).rgb);
+    vec3 randomVec = normalize(texture(texNoise, TexCoords * noiseScale).xyz);
+    // create TBN change-of-basis matrix: from tangent-space to view-space
+    vec3 tangent = normalize(randomVec - normal * dot(randomVec, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 TBN = mat3(tangent, bitangent, normal);
+    // iterate over the sample kernel and calculate occlusion factor
+    float occlusion = 0.0;
+    for (int i = 0; i < MAX_KERNEL_SIZE; ++i)
+    {
+        if (i >= kernelSize)
+            break;
+        // get sample position
+        vec3 samplePos = TBN * samples[i]; // from tangent to view-space
+        samplePos = fragPos + samplePos * radius;
+
+        // project sample position (to sample texture) (to get position on screen/texture)
+        vec4 offset = vec4(samplePos, 1.0);
+        offset = projection * offset; // from view to clip-space
+        offset.xyz /= offset.w; // perspective divide
+        offset.xyz = offset.xyz * 0.5 + 0.5; // transform to range 0.0 - 1.0
+
+        // get sample depth
+        float sampleDepth = texture(gPosition, offset.xy).z; // get depth value of kernel sample
+
+        // range check & accumulate
+        float rangeCheck = smoothstep(0.0, 1.0, radius / abs(fragPos.z - sampleDepth));
+        occlusion += (sampleDepth >= samplePos.z + bias ? 1.0 : 0.0) * rangeCheck;
+    }
+    occlusion = 1.0 - (occlusion / float(kernelSize));
+
+    FragColor = vec4(vec3(pow(occlusion, power)), 1.0);
+}
@@ -0,0 +1,508 @@
+use crate::framebuffer::{
+    float_linear_supported, float_targets_supported, ColorFormat, Depth, Framebuffer,
+    FramebufferDesc, FramebufferSize,
+};
+use crate::gl_object::{OwnedSampler, SharedGl};
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// `MAX_SHADOW_TAPS` in `common/soft_shadows.glsl`
+pub const MAX_SHADOW_TAPS: u32 = 32;
+
+/// How `common/soft_shadows.glsl` filters the shadow map, in the order of its `shadowFilter`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ShadowFilter {
+    /// one `sampler2DShadow` lookup, bilinearly filtered 2x2 comparisons
+    Hardware,
+    /// hardware comparisons at Poisson disk samples rotated per pixel
+    #[default]
+    PoissonPcf,
+    /// percentage-closer soft shadows: Poisson PCF over a penumbra estimated from the average
+    /// depth of the blockers, sharp at contact and softer further away
+    Pcss,
+    /// variance shadow maps: Chebyshev's bound from the blurred depth and depth squared
+    Vsm,
+    /// exponential shadow maps: the blurred exponential of the depth
+    Esm,
+}
+
+impl ShadowFilter {
+    pub const ALL: [ShadowFilter; 5] = [
+        ShadowFilter::Hardware,
+        ShadowFilter::PoissonPcf,
+        ShadowFilter::Pcss,
+        ShadowFilter::Vsm,
+        ShadowFilter::Esm,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&f| f == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// whether the filter reads the moments rather than the depth texture
+    pub fn uses_moments(self) -> bool {
+        matches!(self, ShadowFilter::Vsm | ShadowFilter::Esm)
+    }
+}
+
+impl fmt::Display for ShadowFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShadowFilter::Hardware => write!(f, "Hardware comparison"),
+            ShadowFilter::PoissonPcf => write!(f, "Poisson PCF"),
+            ShadowFilter::Pcss => write!(f, "PCSS"),
+            ShadowFilter::Vsm => write!(f, "VSM"),
+            ShadowFilter::Esm => write!(f, "ESM"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SoftShadowSettings {
+    #[builder(default)]
+    pub filter: ShadowFilter,
+    /// width and height of the shadow map, fixed once created
+    #[builder(default = 1024)]
+    pub resolution: u32,
+    /// Poisson disk samples of PCF and of both PCSS steps, up to `MAX_SHADOW_TAPS`
+    #[builder(default = 16)]
+    pub taps: u32,
+    /// radius of Poisson PCF, in texels
+    #[builder(default = 2.0)]
+    pub filter_radius: f32,
+    /// PCSS penumbra width per unit of depth between blocker and receiver, in shadow map uv
+    #[builder(default = 0.02)]
+    pub light_size: f32,
+    /// in shadow map depth
+    #[builder(default = 0.001)]
+    pub bias: f32,
+    /// times the tangent of the angle between the normal and the light
+    #[builder(default = 0.002)]
+    pub slope_bias: f32,
+    /// in world units, scaled by how far the surface faces away from the light
+    #[builder(default = 0.03)]
+    pub normal_offset: f32,
+    /// VSM variance floor, against acne where the moments are nearly equal
+    #[builder(default = 0.00002)]
+    pub min_variance: f32,
+    /// VSM lit fractions below this become shadow, against light bleeding through overlapping
+    /// casters
+    #[builder(default = 0.2)]
+    pub light_bleed_reduction: f32,
+    /// ESM sharpness. exp(exponent) has to fit a float
+    #[builder(default = 40.0)]
+    pub esm_exponent: f32,
+    /// gaussian blur of the VSM and ESM moments
+    #[builder(default = true)]
+    pub blur: bool,
+}
+
+/// A directional light's shadow map with a choice of filters
+///
+/// The casters are rendered once into a depth texture, which also gets the moments VSM and ESM
+/// filter as a color attachment, blurred in place. Where float targets are not supported the
+/// moments are left out, and VSM and ESM fall back to Poisson PCF. Shaders sample through
+/// `common/soft_shadows.glsl`, whose uniforms `set_uniforms` sets; the depth texture is bound
+/// twice, through a sampler object comparing depths for the hardware and PCF lookups and one
+/// returning them for the PCSS blocker search.
+pub struct SoftShadowMap {
+    pub settings: SoftShadowSettings,
+    /// moments in color attachment 0 if supported, depth as a texture
+    target: Framebuffer,
+    /// the horizontally blurred moments, `None` without float targets
+    blur_target: Option<Framebuffer>,
+    compare_sampler: OwnedSampler,
+    depth_sampler: OwnedSampler,
+    depth_shader: MyShader,
+    blur_shader: MyShader,
+    quad: FullscreenQuad,
+    light_space_matrix: glm::Mat4,
+}
+
+impl SoftShadowMap {
+    pub fn new(
+        gl: &SharedGl,
+        settings: SoftShadowSettings,
+        shader_version: Option<&str>,
+    ) -> Result<Self, PostProcessError> {
+        let mut depth_shader = MyShader::new_from_source(
+            gl,
+            include_str!("shaders/shadow/soft_shadow_depth.vs"),
+            include_str!("shaders/shadow/soft_shadow_depth.fs"),
+            shader_version,
+        )?;
+        depth_shader.set_name("soft shadow depth");
+        let blur_shader = fullscreen_shader(
+            gl,
+            "shadow moments blur",
+            include_str!("shaders/shadow/moments_blur.fs"),
+            shader_version,
+        )?;
+
+        let size = FramebufferSize::Fixed(settings.resolution, settings.resolution);
+        let moments = float_targets_supported(gl);
+        if !moments && settings.filter.uses_moments() {
+            log::warn!(
+                "EXT_color_buffer_float is not available, {} falls back to {}",
+                settings.filter,
+                ShadowFilter::PoissonPcf
+            );
+        }
+        let target = Framebuffer::new(
+            gl,
+            FramebufferDesc::builder()
+                .colors(if moments {
+                    vec![ColorFormat::Rg32F]
+                } else {
+                    vec![]
+                })
+                .depth(Depth::Texture)
+                .size(size)
+                .label("soft shadow map")
+                .build(),
+            settings.resolution,
+            settings.resolution,
+        )?;
+        let blur_target = if moments {
+            Some(Framebuffer::new(
+                gl,
+                FramebufferDesc::builder()
+                    .colors(vec![ColorFormat::Rg32F])
+                    .size(size)
+                    .label("soft shadow blur")
+                    .build(),
+                settings.resolution,
+                settings.resolution,
+            )?)
+        } else {
+            None
+        };
+        if !float_linear_supported(gl) {
+            // RG32F is incomplete with linear filtering there, the blur smooths the moments
+            let textures = [Some(&target), blur_target.as_ref()]
+                .into_iter()
+                .flatten()
+                .filter_map(|target| target.color_texture(0));
+            for texture in textures {
+                unsafe {
+                    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+                    for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+                        gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::NEAREST as i32);
+                    }
+                }
+            }
+        }
+
+        let sampler = |label: &str, filter: u32, compare: bool| {
+            let sampler = OwnedSampler::create_sampler(gl, label);
+            unsafe {
+                for (parameter, value) in [
+                    (glow::TEXTURE_MIN_FILTER, filter),
+                    (glow::TEXTURE_MAG_FILTER, filter),
+                    (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                    (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+                ] {
+                    gl.sampler_parameter_i32(sampler.raw(), parameter, value as i32);
+                }
+                if compare {
+                    gl.sampler_parameter_i32(
+                        sampler.raw(),
+                        glow::TEXTURE_COMPARE_MODE,
+                        glow::COMPARE_REF_TO_TEXTURE as i32,
+                    );
+                    gl.sampler_parameter_i32(
+                        sampler.raw(),
+                        glow::TEXTURE_COMPARE_FUNC,
+                        glow::LEQUAL as i32,
+                    );
+                }
+            }
+            sampler
+        };
+
+        Ok(Self {
+            settings,
+            target,
+            blur_target,
+            // linear filtering of a comparison blends the results of the 4 nearest texels
+            compare_sampler: sampler("shadow compare sampler", glow::LINEAR, true),
+            depth_sampler: sampler("shadow depth sampler", glow::NEAREST, false),
+            depth_shader,
+            blur_shader,
+            quad: FullscreenQuad::new(gl),
+            light_space_matrix: glm::Mat4::identity(),
+        })
+    }
+
+    /// whether VSM and ESM are available, which needs float targets
+    pub fn moments_supported(&self) -> bool {
+        self.blur_target.is_some()
+    }
+
+    /// `settings.filter`, or Poisson PCF in place of unsupported moments filters
+    pub fn filter(&self) -> ShadowFilter {
+        match self.settings.filter {
+            filter if filter.uses_moments() && !self.moments_supported() => {
+                ShadowFilter::PoissonPcf
+            }
+            filter => filter,
+        }
+    }
+
+    /// switches to the next supported filter
+    pub fn next_filter(&mut self) {
+        let mut filter = self.settings.filter.next();
+        while filter.uses_moments() && !self.moments_supported() {
+            filter = filter.next();
+        }
+        self.settings.filter = filter;
+    }
+
+    pub fn depth_texture(&self) -> glow::Texture {
+        self.target.depth_texture().unwrap()
+    }
+
+    /// Renders the depth seen through `light_space_matrix`, and the moments when the filter
+    /// needs them. `draw_scene` draws the shadow casters with the given shader in use, setting
+    /// its `model` matrix. The default framebuffer is bound afterwards, with the viewport still
+    /// at the shadow map size.
+    pub fn render(
+        &mut self,
+        gl: &Context,
+        light_space_matrix: &glm::Mat4,
+        mut draw_scene: impl FnMut(&MyShader),
+    ) {
+        self.light_space_matrix = *light_space_matrix;
+        let filter = self.filter();
+        let settings = &self.settings;
+        let moments = filter.uses_moments();
+        let exponential = filter == ShadowFilter::Esm;
+        unsafe {
+            // the moments have no alpha to blend with
+            let blend = gl.is_enabled(glow::BLEND);
+            gl.disable(glow::BLEND);
+            self.target.bind(gl);
+            gl.enable(glow::DEPTH_TEST);
+            gl.clear(glow::DEPTH_BUFFER_BIT);
+            // the moments of the far plane, where nothing is drawn, leaving the clear color be
+            if moments {
+                let far = if exponential {
+                    [settings.esm_exponent.exp(), 0.0, 0.0, 0.0]
+                } else {
+                    [1.0, 1.0, 0.0, 0.0]
+                };
+                gl.clear_buffer_f32_slice(glow::COLOR, 0, &far);
+            }
+            gl.color_mask(moments, moments, moments, moments);
+            self.depth_shader.use_shader(gl);
+            self.depth_shader
+                .set_mat4(gl, "lightSpaceMatrix", light_space_matrix);
+            self.depth_shader.set_bool(gl, "exponential", exponential);
+            self.depth_shader
+                .set_float(gl, "esmExponent", settings.esm_exponent);
+            draw_scene(&self.depth_shader);
+            gl.color_mask(true, true, true, true);
+
+            if let (true, Some(blur_target)) = (moments && settings.blur, &self.blur_target) {
+                // a separable gaussian, back into the moments target. The depth attachment is
+                // left alone with depth testing off
+                gl.disable(glow::DEPTH_TEST);
+                self.blur_shader.use_shader(gl);
+                self.blur_shader.set_int(gl, "image", 0);
+                for (source, destination, horizontal) in [
+                    (&self.target, blur_target, true),
+                    (blur_target, &self.target, false),
+                ] {
+                    destination.bind(gl);
+                    source.bind_color(gl, 0, 0);
+                    self.blur_shader.set_bool(gl, "horizontal", horizontal);
+                    self.quad.draw(gl);
+                }
+                gl.enable(glow::DEPTH_TEST);
+            }
+            if blend {
+                gl.enable(glow::BLEND);
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Binds the shadow map to texture units `unit` to `unit + 2` and sets the uniforms of
+    /// `common/soft_shadows.glsl` on `shader`, which has to be in use. The sampler objects
+    /// stay bound to `unit` and `unit + 1` until `unbind_samplers`.
+    pub fn set_uniforms(&self, gl: &Context, shader: &MyShader, unit: u32) {
+        let settings = &self.settings;
+        unsafe {
+            for (offset, sampler) in [(0, &self.compare_sampler), (1, &self.depth_sampler)] {
+                gl.active_texture(glow::TEXTURE0 + unit + offset);
+                gl.bind_texture(glow::TEXTURE_2D, self.target.depth_texture());
+                gl.bind_sampler(unit + offset, Some(sampler.raw()));
+            }
+        }
+        if self.moments_supported() {
+            self.target.bind_color(gl, 0, unit + 2);
+        }
+        shader.set_int(gl, "shadowMapCompare", unit as i32);
+        shader.set_int(gl, "shadowMapDepth", unit as i32 + 1);
+        shader.set_int(gl, "shadowMoments", unit as i32 + 2);
+        shader.set_mat4(gl, "lightSpaceMatrix", &self.light_space_matrix);
+        shader.set_int(gl, "shadowFilter", self.filter() as i32);
+        shader.set_int(
+            gl,
+            "shadowTaps",
+            settings.taps.clamp(1, MAX_SHADOW_TAPS) as i32,
+        );
+        shader.set_float(gl, "shadowFilterRadius", settings.filter_radius);
+        shader.set_float(gl, "shadowLightSize", settings.light_size);
+        shader.set_float(gl, "shadowBias", settings.bias);
+        shader.set_float(gl, "shadowSlopeBias", settings.slope_bias);
+        shader.set_float(gl, "shadowNormalOffset", settings.normal_offset);
+        shader.set_float(gl, "shadowMinVariance", settings.min_variance);
+        shader.set_float(gl, "shadowLightBleed", settings.light_bleed_reduction);
+        shader.set_float(gl, "esmExponent", settings.esm_exponent);
+    }
+
+    /// unbinds the sampler objects `set_uniforms` bound at `unit`, so later textures there use
+    /// their own parameters again
+    pub fn unbind_samplers(&self, gl: &Context, unit: u32) {
+        unsafe {
+            gl.bind_sampler(unit, None);
+            gl.bind_sampler(unit + 1, None);
+        }
+    }
+
+    /// Filter, its parameters and the biases
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let moments_supported = self.moments_supported();
+        let filter = self.filter();
+        let settings = &mut self.settings;
+        egui::ComboBox::from_label("Filter")
+            .selected_text(filter.to_string())
+            .show_ui(ui, |ui| {
+                for filter in ShadowFilter::ALL {
+                    ui.add_enabled_ui(moments_supported || !filter.uses_moments(), |ui| {
+                        ui.selectable_value(&mut settings.filter, filter, filter.to_string());
+                    });
+                }
+            });
+        match filter {
+            ShadowFilter::Hardware => {}
+            ShadowFilter::PoissonPcf => {
+                ui.add(egui::Slider::new(&mut settings.taps, 1..=MAX_SHADOW_TAPS).text("Taps"));
+                ui.add(
+                    egui::Slider::new(&mut settings.filter_radius, 0.0..=10.0)
+                        .text("Radius (texels)"),
+                );
+            }
+            ShadowFilter::Pcss => {
+                ui.add(egui::Slider::new(&mut settings.taps, 1..=MAX_SHADOW_TAPS).text("Taps"));
+                ui.add(egui::Slider::new(&mut settings.light_size, 0.0..=0.1).text("Light size"));
+            }
+            ShadowFilter::Vsm => {
+                ui.add(
+                    egui::Slider::new(&mut settings.min_variance, 0.0..=0.001)
+                        .logarithmic(true)
+                        .text("Min variance"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut settings.light_bleed_reduction, 0.0..=0.9)
+                        .text("Light bleeding reduction"),
+                );
+                ui.checkbox(&mut settings.blur, "Blur");
+            }
+            ShadowFilter::Esm => {
+                ui.add(egui::Slider::new(&mut settings.esm_exponent, 1.0..=80.0).text("Exponent"));
+                ui.checkbox(&mut settings.blur, "Blur");
+            }
+        }
+        ui.add(
+            egui::Slider::new(&mut settings.bias, 0.0..=0.01)
+                .logarithmic(true)
+                .text("Constant bias"),
+        );
+        ui.add(
+            egui::Slider::new(&mut settings.slope_bias, 0.0..=0.01)
+                .logarithmic(true)
+                .text("Slope bias"),
+        );
+        ui.add(egui::Slider::new(&mut settings.normal_offset, 0.0..=0.2).text("Normal offset"));
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let moments_supported = self.moments_supported();
+        let current = self.filter();
+        let settings = &mut self.settings;
+        for filter in ShadowFilter::ALL {
+            if (moments_supported || !filter.uses_moments())
+                && ui
+                    .radio_button_config(filter.to_string(), current == filter)
+                    .build()
+            {
+                settings.filter = filter;
+            }
+        }
+        let mut taps = settings.taps as i32;
+        match current {
+            ShadowFilter::Hardware => {}
+            ShadowFilter::PoissonPcf => {
+                if ui
+                    .slider_int_config("Taps", &mut taps)
+                    .range(1, MAX_SHADOW_TAPS as i32)
+                    .build()
+                {
+                    settings.taps = taps as u32;
+                }
+                ui.slider_float_config("Radius (texels)", &mut settings.filter_radius)
+                    .range(0.0, 10.0)
+                    .build();
+            }
+            ShadowFilter::Pcss => {
+                if ui
+                    .slider_int_config("Taps", &mut taps)
+                    .range(1, MAX_SHADOW_TAPS as i32)
+                    .build()
+                {
+                    settings.taps = taps as u32;
+                }
+                ui.slider_float_config("Light size", &mut settings.light_size)
+                    .range(0.0, 0.1)
+                    .build();
+            }
+            ShadowFilter::Vsm => {
+                ui.slider_float_config("Min variance", &mut settings.min_variance)
+                    .range(0.0, 0.001)
+                    .build();
+                ui.slider_float_config(
+                    "Light bleeding reduction",
+                    &mut settings.light_bleed_reduction,
+                )
+                .range(0.0, 0.9)
+                .build();
+                ui.checkbox_config("Blur", &mut settings.blur).build();
+            }
+            ShadowFilter::Esm => {
+                ui.slider_float_config("Exponent", &mut settings.esm_exponent)
+                    .range(1.0, 80.0)
+                    .build();
+                ui.checkbox_config("Blur", &mut settings.blur).build();
+            }
+        }
+        ui.slider_float_config("Constant bias", &mut settings.bias)
+            .range(0.0, 0.01)
+            .build();
+        ui.slider_float_config("Slope bias", &mut settings.slope_bias)
+            .range(0.0, 0.01)
+            .build();
+        ui.slider_float_config("Normal offset", &mut settings.normal_offset)
+            .range(0.0, 0.2)
+            .build();
+    }
+}
@@ -0,0 +1,341 @@
+use crate::deferred::GBuffer;
+use crate::framebuffer::{ColorFormat, Framebuffer, FramebufferDesc, FramebufferError};
+use crate::gl_object::{OwnedTexture, SharedGl};
+use crate::post_process::{fullscreen_shader, FullscreenQuad, PostProcessError};
+use crate::shader::MyShader;
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use typed_builder::TypedBuilder;
+
+/// `MAX_KERNEL_SIZE` in `ssao/ssao.fs`
+pub const MAX_KERNEL_SIZE: usize = 64;
+
+/// the noise texture is `NOISE_SIZE` x `NOISE_SIZE`, tiled over the screen
+const NOISE_SIZE: usize = 4;
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SsaoSettings {
+    /// when off the AO texture is white, so the ambient term is unchanged
+    #[builder(default = true)]
+    pub enabled: bool,
+    /// samples per pixel, at most `MAX_KERNEL_SIZE`
+    #[builder(default = 64)]
+    pub kernel_size: usize,
+    /// of the sample hemisphere, in view space units
+    #[builder(default = 0.5)]
+    pub radius: f32,
+    /// depth difference below which a sample does not occlude, against acne
+    #[builder(default = 0.025)]
+    pub bias: f32,
+    /// exponent of the result, higher values darken the occlusion
+    #[builder(default = 1.0)]
+    pub power: f32,
+    /// averages the 4x4 noise pattern away
+    #[builder(default = true)]
+    pub blur: bool,
+    /// of the kernel and noise, the same seed gives the same pattern
+    #[builder(default = 0)]
+    pub seed: u64,
+}
+
+/// Which AO buffer `Ssao::draw_debug` shows
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SsaoView {
+    /// the lit scene, nothing is drawn
+    #[default]
+    Lit,
+    Raw,
+    Blurred,
+}
+
+impl SsaoView {
+    pub const ALL: [SsaoView; 3] = [SsaoView::Lit, SsaoView::Raw, SsaoView::Blurred];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&v| v == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for SsaoView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsaoView::Lit => write!(f, "Lit"),
+            SsaoView::Raw => write!(f, "Raw AO"),
+            SsaoView::Blurred => write!(f, "Blurred AO"),
+        }
+    }
+}
+
+/// Screen-space ambient occlusion of a `GBuffer` holding view space positions and normals
+///
+/// Every pixel tests a kernel of samples in the hemisphere around its normal against the
+/// depths of the G-buffer, rotated by a small tiled noise texture, and the result is blurred
+/// over the size of that tile. Lighting multiplies its ambient term with `texture()`.
+pub struct Ssao {
+    pub settings: SsaoSettings,
+    /// kernel and noise were generated with these
+    generated: (usize, u64),
+    kernel: Vec<glm::Vec3>,
+    noise: OwnedTexture,
+    raw: Framebuffer,
+    blurred: Framebuffer,
+    ssao_shader: MyShader,
+    blur_shader: MyShader,
+    debug_shader: MyShader,
+    quad: FullscreenQuad,
+}
+
+impl Ssao {
+    pub fn new(
+        gl: &SharedGl,
+        settings: SsaoSettings,
+        shader_version: Option<&str>,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, PostProcessError> {
+        let ssao_shader = fullscreen_shader(
+            gl,
+            "ssao",
+            include_str!("shaders/ssao/ssao.fs"),
+            shader_version,
+        )?;
+        ssao_shader.use_shader(gl);
+        ssao_shader.set_int(gl, "gPosition", 0);
+        ssao_shader.set_int(gl, "gNormal", 1);
+        ssao_shader.set_int(gl, "texNoise", 2);
+        let blur_shader = fullscreen_shader(
+            gl,
+            "ssao blur",
+            include_str!("shaders/ssao/ssao_blur.fs"),
+            shader_version,
+        )?;
+        let debug_shader = fullscreen_shader(
+            gl,
+            "ssao debug",
+            include_str!("shaders/post/copy.fs"),
+            shader_version,
+        )?;
+
+        // occlusion is in [0, 1], 8 bits are plenty and render everywhere
+        let target = |label: &str| {
+            let desc = FramebufferDesc::builder()
+                .colors(vec![ColorFormat::Rgba8])
+                .label(label)
+                .build();
+            Framebuffer::new(gl, desc, window_width, window_height)
+        };
+        let raw = target("ssao")?;
+        let blurred = target("ssao blur")?;
+
+        let noise = OwnedTexture::create_texture(gl, "ssao noise");
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(noise.raw()));
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::NEAREST),
+                (glow::TEXTURE_MAG_FILTER, glow::NEAREST),
+                (glow::TEXTURE_WRAP_S, glow::REPEAT),
+                (glow::TEXTURE_WRAP_T, glow::REPEAT),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+
+        let mut ssao = Self {
+            generated: (settings.kernel_size, settings.seed),
+            settings,
+            kernel: Vec::new(),
+            noise,
+            raw,
+            blurred,
+            ssao_shader,
+            blur_shader,
+            debug_shader,
+            quad: FullscreenQuad::new(gl),
+        };
+        ssao.generate(gl);
+        Ok(ssao)
+    }
+
+    /// Fills the kernel and noise texture from `settings.seed`
+    fn generate(&mut self, gl: &Context) {
+        let kernel_size = self.settings.kernel_size.clamp(1, MAX_KERNEL_SIZE);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(self.settings.seed);
+        // sample kernel, in the hemisphere around +z
+        self.kernel = (0..kernel_size)
+            .map(|i| {
+                let sample = glm::vec3(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                );
+                let sample = glm::normalize(&sample) * rng.gen_range(0.0..1.0);
+                // scale samples s.t. they're more aligned to center of kernel
+                let scale = i as f32 / kernel_size as f32;
+                sample * glm::lerp_scalar(0.1, 1.0, scale * scale)
+            })
+            .collect();
+
+        // noise texture, rotations around z (in tangent space)
+        let noise: Vec<f32> = (0..NOISE_SIZE * NOISE_SIZE)
+            .flat_map(|_| [rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0, 0.0])
+            .collect();
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.noise.raw()));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA16F as i32,
+                NOISE_SIZE as i32,
+                NOISE_SIZE as i32,
+                0,
+                glow::RGBA,
+                glow::FLOAT,
+                Some(bytemuck::cast_slice(&noise)),
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+        self.generated = (self.settings.kernel_size, self.settings.seed);
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        self.raw.resize(gl, width, height)?;
+        self.blurred.resize(gl, width, height)?;
+        Ok(())
+    }
+
+    /// the occlusion to multiply the ambient light with, blurred if `settings.blur`
+    pub fn texture(&self) -> glow::Texture {
+        let target = if self.settings.blur {
+            &self.blurred
+        } else {
+            &self.raw
+        };
+        target.color_texture(0).unwrap()
+    }
+
+    /// Computes the occlusion of `gbuffer`, whose positions and normals are in the view space
+    /// of `projection`. Regenerates the kernel and noise if their settings changed. Depth
+    /// testing is left disabled.
+    pub fn apply(&mut self, gl: &Context, gbuffer: &GBuffer, projection: &glm::Mat4) {
+        if self.generated != (self.settings.kernel_size, self.settings.seed) {
+            self.generate(gl);
+        }
+        unsafe {
+            gl.disable(glow::DEPTH_TEST);
+            if !self.settings.enabled {
+                for target in [&self.raw, &self.blurred] {
+                    target.bind(gl);
+                    gl.clear_color(1.0, 1.0, 1.0, 1.0);
+                    gl.clear(glow::COLOR_BUFFER_BIT);
+                }
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                return;
+            }
+
+            // generate SSAO texture
+            // ---------------------
+            self.raw.bind(gl);
+            gbuffer.bind_textures(gl);
+            gl.active_texture(glow::TEXTURE2);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.noise.raw()));
+            gl.active_texture(glow::TEXTURE0);
+            let shader = &self.ssao_shader;
+            shader.use_shader(gl);
+            shader.set(gl, "samples", self.kernel.as_slice());
+            shader.set_int(gl, "kernelSize", self.kernel.len() as i32);
+            shader.set_float(gl, "radius", self.settings.radius);
+            shader.set_float(gl, "bias", self.settings.bias);
+            shader.set_float(gl, "power", self.settings.power);
+            shader.set_mat4(gl, "projection", projection);
+            let (width, height) = self.raw.size();
+            let noise_scale = glm::vec2(width as f32, height as f32) / NOISE_SIZE as f32;
+            shader.set(gl, "noiseScale", &noise_scale);
+            self.quad.draw(gl);
+
+            // blur SSAO texture to remove noise
+            // ---------------------------------
+            if self.settings.blur {
+                self.blurred.bind(gl);
+                self.blur_shader.use_shader(gl);
+                self.raw.bind_color(gl, 0, 0);
+                self.quad.draw(gl);
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Shows `view` in the default framebuffer of `width` x `height`, does nothing for
+    /// `SsaoView::Lit`. Depth testing is left disabled.
+    pub fn draw_debug(&self, gl: &Context, view: SsaoView, width: u32, height: u32) {
+        let target = match view {
+            SsaoView::Lit => return,
+            SsaoView::Raw => &self.raw,
+            SsaoView::Blurred => &self.blurred,
+        };
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(0, 0, width as i32, height as i32);
+            gl.disable(glow::DEPTH_TEST);
+            self.debug_shader.use_shader(gl);
+            target.bind_color(gl, 0, 0);
+            self.quad.draw(gl);
+        }
+    }
+
+    /// Toggles, radius, bias, kernel size, power and seed
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    pub fn show_egui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.checkbox(&mut settings.enabled, "SSAO");
+        ui.checkbox(&mut settings.blur, "Blur");
+        ui.add(
+            egui::Slider::new(&mut settings.kernel_size, 1..=MAX_KERNEL_SIZE).text("Kernel size"),
+        );
+        ui.add(egui::Slider::new(&mut settings.radius, 0.01..=2.0).text("Radius"));
+        ui.add(egui::Slider::new(&mut settings.bias, 0.0..=0.2).text("Bias"));
+        ui.add(egui::Slider::new(&mut settings.power, 0.1..=8.0).text("Power"));
+        ui.add(egui::DragValue::new(&mut settings.seed).prefix("Seed: "));
+    }
+
+    /// imgui version of `show_egui`
+    #[cfg(feature = "imgui-support")]
+    pub fn show_imgui<A>(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+        let settings = &mut self.settings;
+        ui.checkbox_config("SSAO", &mut settings.enabled).build();
+        ui.checkbox_config("Blur", &mut settings.blur).build();
+        let mut kernel_size = settings.kernel_size as i32;
+        if ui
+            .slider_int_config("Kernel size", &mut kernel_size)
+            .range(1, MAX_KERNEL_SIZE as i32)
+            .build()
+        {
+            settings.kernel_size = kernel_size as usize;
+        }
+        ui.slider_float_config("Radius", &mut settings.radius)
+            .range(0.01, 2.0)
+            .build();
+        ui.slider_float_config("Bias", &mut settings.bias)
+            .range(0.0, 0.2)
+            .build();
+        ui.slider_float_config("Power", &mut settings.power)
+            .range(0.1, 8.0)
+            .build();
+        let mut seed = settings.seed as i32;
+        if ui
+            .slider_int_config("Seed", &mut seed)
+            .range(0, 1000)
+            .build()
+        {
+            settings.seed = seed as u64;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::mesh::Vertex;
+use nalgebra_glm as glm;
+use std::collections::HashMap;
+
+/// below this, a normal or a tangent is treated as degenerate
+const EPSILON: f32 = 1e-8;
+
+/// Fills `tangent` and `bitangent` of `vertices` for the triangle list `indices` with
+/// MikkTSpace, the tangent space normal maps are usually baked in.
+///
+/// MikkTSpace works on the corners of the triangles, so a vertex whose corners get different
+/// tangents (where mirrored and unmirrored texture coordinates meet, or at hard edges of the
+/// texture space) is split: the copy is appended to `vertices` and the `indices` of the
+/// differing corners point to it. The bitangent is the cross product of normal and tangent,
+/// flipped where the texture is mirrored. Vertices not used by any triangle get an arbitrary
+/// tangent perpendicular to their normal.
+pub fn generate_mikktspace_tangents(vertices: &mut Vec<Vertex>, indices: &mut [u32]) {
+    let mut geometry = Corners {
+        vertices,
+        indices,
+        tangents: vec![None; indices.len()],
+    };
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        log::warn!("MikkTSpace failed, tangents are arbitrary");
+    }
+    let tangents = geometry.tangents;
+
+    for vertex in vertices.iter_mut() {
+        let normal = safe_normalize(&vertex.normal).unwrap_or_else(glm::Vec3::y);
+        vertex.tangent = any_perpendicular(&normal);
+        vertex.bitangent = normal.cross(&vertex.tangent);
+    }
+    // the tangent each vertex got first, and the copies made for other ones
+    let mut assigned = vec![None; vertices.len()];
+    let mut copies = HashMap::new();
+    for (index, tangent) in indices.iter_mut().zip(tangents) {
+        let Some(tangent) = tangent else {
+            continue;
+        };
+        let key = tangent.map(f32::to_bits);
+        let original = *index as usize;
+        match assigned[original] {
+            None => assigned[original] = Some(key),
+            Some(first) if first == key => continue,
+            Some(_) => {
+                *index = *copies.entry((original, key)).or_insert_with(|| {
+                    vertices.push(vertices[original]);
+                    vertices.len() as u32 - 1
+                });
+            }
+        }
+        let vertex = &mut vertices[*index as usize];
+        let normal = safe_normalize(&vertex.normal).unwrap_or_else(glm::Vec3::y);
+        vertex.tangent = safe_normalize(&glm::vec3(tangent[0], tangent[1], tangent[2]))
+            .unwrap_or_else(|| any_perpendicular(&normal));
+        vertex.bitangent = normal.cross(&vertex.tangent) * tangent[3];
+    }
+}
+
+/// the triangles of an indexed mesh as MikkTSpace sees them, collecting a tangent per corner
+/// with the sign of the bitangent in `w`
+struct Corners<'a> {
+    vertices: &'a [Vertex],
+    indices: &'a [u32],
+    tangents: Vec<Option<[f32; 4]>>,
+}
+
+impl Corners<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl bevy_mikktspace::Geometry for Corners<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        let normal = self.vertex(face, vert).normal;
+        safe_normalize(&normal).unwrap_or(normal).into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).tex_coords.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = Some(tangent);
+    }
+}
+
+fn safe_normalize(v: &glm::Vec3) -> Option<glm::Vec3> {
+    let length = v.norm();
+    (length > EPSILON).then(|| v / length)
+}
+
+fn any_perpendicular(normal: &glm::Vec3) -> glm::Vec3 {
+    let axis = if normal.x.abs() < 0.9 {
+        glm::Vec3::x()
+    } else {
+        glm::Vec3::y()
+    };
+    normal.cross(&axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, u: f32, v: f32) -> Vertex {
+        Vertex {
+            position: glm::vec3(x, y, 0.0),
+            normal: glm::vec3(0.0, 0.0, 1.0),
+            tex_coords: glm::vec2(u, v),
+            ..Default::default()
+        }
+    }
+
+    fn assert_near(actual: &glm::Vec3, expected: glm::Vec3) {
+        assert!(
+            glm::distance(actual, &expected) < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn quad_tangents_follow_the_texture_axes() {
+        let mut vertices = vec![
+            vertex(-1.0, -1.0, 0.0, 0.0),
+            vertex(1.0, -1.0, 1.0, 0.0),
+            vertex(1.0, 1.0, 1.0, 1.0),
+            vertex(-1.0, 1.0, 0.0, 1.0),
+        ];
+        let mut indices = vec![0, 1, 2, 0, 2, 3];
+        generate_mikktspace_tangents(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3]);
+        for vertex in &vertices {
+            assert_near(&vertex.tangent, glm::vec3(1.0, 0.0, 0.0));
+            assert_near(&vertex.bitangent, glm::vec3(0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn vertices_shared_with_mirrored_uvs_are_split() {
+        // the second triangle mirrors the texture along the shared edge from 0 to 2
+        let mut vertices = vec![
+            vertex(0.0, 0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 1.0, 0.0),
+            vertex(0.0, 1.0, 0.0, 1.0),
+            vertex(-1.0, 0.0, 1.0, 0.0),
+        ];
+        let mut indices = vec![0, 1, 2, 0, 2, 3];
+        generate_mikktspace_tangents(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices, [0, 1, 2, 4, 5, 3]);
+        for (copy, original) in [(4, 0), (5, 2)] {
+            assert_eq!(vertices[copy].position, vertices[original].position);
+            assert_eq!(vertices[copy].tex_coords, vertices[original].tex_coords);
+        }
+        for vertex in &vertices[..3] {
+            assert_near(&vertex.tangent, glm::vec3(1.0, 0.0, 0.0));
+            assert_near(&vertex.bitangent, glm::vec3(0.0, 1.0, 0.0));
+        }
+        // the copies and the vertex only used mirrored
+        for vertex in &vertices[3..] {
+            assert_near(&vertex.tangent, glm::vec3(-1.0, 0.0, 0.0));
+            assert_near(&vertex.bitangent, glm::vec3(0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn degenerate_uvs_get_a_tangent_perpendicular_to_the_normal() {
+        let mut vertices = vec![
+            vertex(0.0, 0.0, 0.5, 0.5),
+            vertex(1.0, 0.0, 0.5, 0.5),
+            vertex(0.0, 1.0, 0.5, 0.5),
+        ];
+        let mut indices = vec![0, 1, 2];
+        generate_mikktspace_tangents(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 3);
+        for vertex in &vertices {
+            assert!((vertex.tangent.norm() - 1.0).abs() < 1e-5);
+            assert!(vertex.tangent.dot(&vertex.normal).abs() < 1e-5);
+            // MikkTSpace flags the bitangent of triangles without texture space as flipped
+            assert_near(&vertex.bitangent, -vertex.normal.cross(&vertex.tangent));
+        }
+    }
+}
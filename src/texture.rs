@@ -1,3 +1,4 @@
+use crate::gl_object::{OwnedTexture, SharedGl};
 use glow::{
     Context, HasContext, LINEAR, LINEAR_MIPMAP_LINEAR, REPEAT, RGBA, TEXTURE_2D,
     TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TEXTURE_WRAP_S, TEXTURE_WRAP_T, UNSIGNED_BYTE,
@@ -12,16 +13,17 @@ pub enum TextureType {
     Height,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A 2D texture, deleted when dropped
+#[derive(Debug)]
 pub struct Texture {
-    raw: glow::Texture,
+    raw: OwnedTexture,
     file_name: String,
     ty: TextureType,
 }
 
 impl Texture {
     pub fn from_image(
-        gl: &Context,
+        gl: &SharedGl,
         img: &image::DynamicImage,
         file_name: &str,
         ty: TextureType,
@@ -53,14 +55,14 @@ impl Texture {
             gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as i32);
             gl.bind_texture(TEXTURE_2D, None);
 
-            texture
+            OwnedTexture::new(gl, texture, file_name)
         };
         let file_name = file_name.to_string();
         Ok(Texture { raw, file_name, ty })
     }
 
     pub fn from_bytes(
-        gl: &Context,
+        gl: &SharedGl,
         bytes: &[u8],
         file_name: &str,
         ty: TextureType,
@@ -69,9 +71,38 @@ impl Texture {
         Self::from_image(gl, &img, file_name, ty)
     }
 
+    /// An empty texture with immutable storage, e.g. as image written by compute shaders.
+    /// `internal_format` is sized, like `glow::RGBA32F`.
+    pub fn new_storage(
+        gl: &SharedGl,
+        width: u32,
+        height: u32,
+        internal_format: u32,
+        name: &str,
+    ) -> anyhow::Result<Self> {
+        let raw = unsafe {
+            let texture = gl
+                .create_texture()
+                .map_err(|e| anyhow::anyhow!("Failed to create texture: {:?}", e))?;
+            gl.bind_texture(TEXTURE_2D, Some(texture));
+            gl.tex_storage_2d(TEXTURE_2D, 1, internal_format, width as i32, height as i32);
+            gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as i32);
+            gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as i32);
+            gl.bind_texture(TEXTURE_2D, None);
+            OwnedTexture::new(gl, texture, name)
+        };
+        Ok(Texture {
+            raw,
+            file_name: name.to_string(),
+            ty: TextureType::Diffuse,
+        })
+    }
+
     pub fn set_wrap_mode(&self, gl: &Context, wrap_s: i32, wrap_t: i32) {
         unsafe {
-            gl.bind_texture(TEXTURE_2D, Some(self.raw));
+            gl.bind_texture(TEXTURE_2D, Some(self.raw.raw()));
             gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, wrap_s);
             gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, wrap_t);
             gl.bind_texture(TEXTURE_2D, None);
@@ -81,7 +112,7 @@ impl Texture {
     #[allow(dead_code)]
     pub fn set_filter_mode(&self, gl: &Context, min_filter: i32, mag_filter: i32) {
         unsafe {
-            gl.bind_texture(TEXTURE_2D, Some(self.raw));
+            gl.bind_texture(TEXTURE_2D, Some(self.raw.raw()));
             gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, min_filter);
             gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, mag_filter);
             gl.bind_texture(TEXTURE_2D, None);
@@ -93,7 +124,7 @@ impl Texture {
     }
 
     pub fn raw(&self) -> glow::Texture {
-        self.raw
+        self.raw.raw()
     }
 
     #[allow(dead_code)]
@@ -101,16 +132,19 @@ impl Texture {
         &self.file_name
     }
 
-    pub fn delete(&self, gl: &Context) {
+    /// Binds level 0 to the image unit `unit` for image load/store, `access` is
+    /// `glow::READ_ONLY`, `WRITE_ONLY` or `READ_WRITE` and `format` matches the shader's layout
+    /// qualifier, e.g. `glow::RGBA32F` for `layout(rgba32f)`.
+    pub fn bind_image(&self, gl: &Context, unit: u32, access: u32, format: u32) {
         unsafe {
-            gl.delete_texture(self.raw);
+            gl.bind_image_texture(unit, self.raw.raw(), 0, false, 0, access, format);
         }
     }
 
     pub fn bind(&self, gl: &Context, slot: u32) {
         unsafe {
             gl.active_texture(glow::TEXTURE0 + slot);
-            gl.bind_texture(TEXTURE_2D, Some(self.raw));
+            gl.bind_texture(TEXTURE_2D, Some(self.raw.raw()));
         }
     }
 }
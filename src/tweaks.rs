@@ -0,0 +1,456 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+
+/// where overrides are saved, relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+pub const TWEAKS_DIR: &str = "tweaks";
+
+/// A uniform value the tweak panel can edit
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TweakValue {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+}
+
+impl TweakValue {
+    #[cfg(any(
+        all(not(target_arch = "wasm32"), feature = "egui-support"),
+        feature = "imgui-support"
+    ))]
+    fn kind(&self) -> &'static str {
+        match self {
+            TweakValue::Float(_) => "float",
+            TweakValue::Int(_) => "int",
+            TweakValue::Bool(_) => "bool",
+            TweakValue::Vec3(_) => "vec3",
+            TweakValue::Vec4(_) => "vec4",
+        }
+    }
+
+    #[cfg(any(
+        all(not(target_arch = "wasm32"), feature = "egui-support"),
+        feature = "imgui-support"
+    ))]
+    fn to_line(self) -> String {
+        let values = match self {
+            TweakValue::Float(v) => vec![v.to_string()],
+            TweakValue::Int(v) => vec![v.to_string()],
+            TweakValue::Bool(v) => vec![v.to_string()],
+            TweakValue::Vec3(v) => v.iter().map(f32::to_string).collect(),
+            TweakValue::Vec4(v) => v.iter().map(f32::to_string).collect(),
+        };
+        format!("{}\t{}", self.kind(), values.join(" "))
+    }
+
+    fn from_line(kind: &str, values: &str) -> Option<Self> {
+        let floats = || {
+            values
+                .split_whitespace()
+                .map(|v| v.parse::<f32>().ok())
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(match kind {
+            "float" => TweakValue::Float(values.trim().parse().ok()?),
+            "int" => TweakValue::Int(values.trim().parse().ok()?),
+            "bool" => TweakValue::Bool(values.trim().parse().ok()?),
+            "vec3" => TweakValue::Vec3(floats()?.try_into().ok()?),
+            "vec4" => TweakValue::Vec4(floats()?.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    /// Slider range for the value the tutorial sets: up to twice its size but at least
+    /// `at_least`, and as far below 0 where it is negative
+    #[cfg(any(
+        all(not(target_arch = "wasm32"), feature = "egui-support"),
+        feature = "imgui-support"
+    ))]
+    fn slider_range(&self, at_least: f32) -> (f32, f32) {
+        let components = match self {
+            TweakValue::Float(v) => vec![*v],
+            TweakValue::Int(v) => vec![*v as f32],
+            TweakValue::Vec3(v) => v.to_vec(),
+            TweakValue::Bool(_) | TweakValue::Vec4(_) => vec![],
+        };
+        let size = components.iter().fold(0.0_f32, |size, v| size.max(v.abs()));
+        let max = (size * 2.0).max(at_least);
+        let negative = components.iter().any(|v| *v < 0.0);
+        (if negative { -max } else { 0.0 }, max)
+    }
+
+    /// vectors whose name looks like a color get a color picker
+    pub fn is_color(name: &str) -> bool {
+        let name = name.to_lowercase();
+        ["color", "colour", "ambient", "diffuse", "specular"]
+            .iter()
+            .any(|word| name.contains(word))
+    }
+}
+
+struct ProgramTweaks {
+    /// `MyShader::tweak_key`
+    key: String,
+    /// the editable uniforms by name, with the last value set by the tutorial. Only filled in
+    /// once the program is used with the panel open
+    uniforms: BTreeMap<String, TweakValue>,
+    /// by uniform name
+    overrides: BTreeMap<String, TweakValue>,
+}
+
+#[derive(Default)]
+struct Tweaks {
+    /// overrides are only loaded and saved once this is set
+    tutorial_id: Option<String>,
+    /// indexed by `index`, entries are never removed so the indices stay valid
+    programs: Vec<ProgramTweaks>,
+    /// overrides changed since the last `save`, which waits for the end of a drag
+    #[cfg_attr(
+        not(any(
+            all(not(target_arch = "wasm32"), feature = "egui-support"),
+            feature = "imgui-support"
+        )),
+        allow(dead_code)
+    )]
+    unsaved: bool,
+}
+
+thread_local! {
+    static TWEAKS: RefCell<Tweaks> = RefCell::new(Tweaks::default());
+    /// changes with every override, 0 until `load` so headless runs skip the tweaks entirely
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+    /// whether the panel listed the uniforms in the last frame, they are only tracked then
+    static PANEL_OPEN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Loads the overrides saved for `tutorial_id` and saves changes from now on.
+/// Only called for windowed runs, headless runs and golden images ignore the panel.
+pub fn load(tutorial_id: &str) {
+    TWEAKS.with(|tweaks| {
+        let mut tweaks = tweaks.borrow_mut();
+        tweaks.tutorial_id = Some(tutorial_id.to_string());
+        tweaks.programs.clear();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(content) = std::fs::read_to_string(path(tutorial_id)) {
+            let mut count = 0;
+            for line in content.lines() {
+                let mut parts = line.splitn(4, '\t');
+                let (Some(key), Some(name), Some(kind), Some(values)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                if let Some(value) = TweakValue::from_line(kind, values) {
+                    let index = find_or_add(&mut tweaks, key);
+                    tweaks.programs[index]
+                        .overrides
+                        .insert(name.to_string(), value);
+                    count += 1;
+                }
+            }
+            log::info!("Loaded {} uniform overrides", count);
+        }
+    });
+    changed();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(tutorial_id: &str) -> std::path::PathBuf {
+    std::path::Path::new(TWEAKS_DIR).join(format!("{}.txt", tutorial_id))
+}
+
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "egui-support"),
+    feature = "imgui-support"
+))]
+fn save(tweaks: &Tweaks) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(tutorial_id) = &tweaks.tutorial_id {
+        let content = tweaks
+            .programs
+            .iter()
+            .flat_map(|program| {
+                program.overrides.iter().map(|(name, value)| {
+                    format!("{}\t{}\t{}\n", program.key, name, value.to_line())
+                })
+            })
+            .collect::<String>();
+        let result = std::fs::create_dir_all(TWEAKS_DIR)
+            .and_then(|_| std::fs::write(path(tutorial_id), content));
+        if let Err(e) = result {
+            log::error!("Cannot save uniform overrides: {}", e);
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = tweaks;
+}
+
+fn find_or_add(tweaks: &mut Tweaks, key: &str) -> usize {
+    if let Some(index) = tweaks.programs.iter().position(|p| p.key == key) {
+        return index;
+    }
+    tweaks.programs.push(ProgramTweaks {
+        key: key.to_string(),
+        uniforms: BTreeMap::new(),
+        overrides: BTreeMap::new(),
+    });
+    tweaks.programs.len() - 1
+}
+
+fn changed() {
+    GENERATION.with(|generation| generation.set(generation.get() + 1));
+}
+
+/// Changes whenever an override does, so shaders only fetch their overrides again then.
+/// 0 while the tweaks are not in use
+pub fn generation() -> u64 {
+    GENERATION.with(Cell::get)
+}
+
+/// whether shaders have to register their uniforms and report the values they set
+pub fn panel_open() -> bool {
+    PANEL_OPEN.with(Cell::get)
+}
+
+/// index of the shader `key`, for the other functions
+pub fn index(key: &str) -> usize {
+    TWEAKS.with(|tweaks| find_or_add(&mut tweaks.borrow_mut(), key))
+}
+
+/// `uniforms` are the editable uniforms of the shader `index` with their current values
+pub fn register(index: usize, uniforms: Vec<(String, TweakValue)>) {
+    TWEAKS.with(|tweaks| {
+        tweaks.borrow_mut().programs[index].uniforms = uniforms.into_iter().collect();
+    });
+}
+
+/// Remembers the value the tutorial sets, to show it in the panel when there is no override
+pub fn record(index: usize, name: &str, value: TweakValue) {
+    TWEAKS.with(|tweaks| {
+        if let Some(uniform) = tweaks.borrow_mut().programs[index].uniforms.get_mut(name) {
+            *uniform = value;
+        }
+    });
+}
+
+/// overrides of the shader `index` by uniform name, applied whenever it is used so uniforms the
+/// tutorial sets only once, or never, are overridden too
+pub fn overrides(index: usize) -> HashMap<String, TweakValue> {
+    TWEAKS.with(|tweaks| {
+        tweaks.borrow().programs[index]
+            .overrides
+            .iter()
+            .map(|(name, value)| (name.clone(), *value))
+            .collect()
+    })
+}
+
+/// Lets `edit` change the shown value of every uniform, storing the changes as overrides.
+/// `edit` gets (shader key, uniform name, value, value set by the tutorial, overridden) and
+/// returns what to do.
+/// Shaders register their uniforms while this is called every frame.
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "egui-support"),
+    feature = "imgui-support"
+))]
+fn edit_all(mut edit: impl FnMut(&str, &str, &mut TweakValue, TweakValue, bool) -> Edit) {
+    PANEL_OPEN.with(|open| open.set(true));
+    let edited = TWEAKS.with(|tweaks| {
+        let mut tweaks = tweaks.borrow_mut();
+        let tweaks = &mut *tweaks;
+        let mut changed = false;
+        let mut finished = false;
+        for program in &mut tweaks.programs {
+            for (name, uniform) in &program.uniforms {
+                let overridden = program.overrides.get(name).copied();
+                let shown = overridden.unwrap_or(*uniform);
+                let mut value = shown;
+                match edit(
+                    &program.key,
+                    name,
+                    &mut value,
+                    *uniform,
+                    overridden.is_some(),
+                ) {
+                    Edit::None => {}
+                    Edit::Changed => {
+                        program.overrides.insert(name.clone(), value);
+                        changed = true;
+                    }
+                    Edit::Finished => {
+                        if value != shown {
+                            program.overrides.insert(name.clone(), value);
+                            changed = true;
+                        }
+                        finished = true;
+                    }
+                    Edit::Reset => {
+                        program.overrides.remove(name);
+                        changed = true;
+                        finished = true;
+                    }
+                }
+            }
+        }
+        tweaks.unsaved |= changed;
+        if finished && tweaks.unsaved {
+            save(tweaks);
+            tweaks.unsaved = false;
+        }
+        changed
+    });
+    if edited {
+        changed();
+    }
+}
+
+/// saves what is left unsaved once the panel is collapsed in the middle of an edit
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "egui-support"),
+    feature = "imgui-support"
+))]
+fn save_if_closed() {
+    if panel_open() {
+        return;
+    }
+    TWEAKS.with(|tweaks| {
+        let mut tweaks = tweaks.borrow_mut();
+        if tweaks.unsaved {
+            save(&tweaks);
+            tweaks.unsaved = false;
+        }
+    });
+}
+
+/// called before showing the panel, `edit_all` sets it again if the panel is not collapsed
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "egui-support"),
+    feature = "imgui-support"
+))]
+fn panel_closed() {
+    PANEL_OPEN.with(|open| open.set(false));
+}
+
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "egui-support"),
+    feature = "imgui-support"
+))]
+enum Edit {
+    None,
+    /// while dragging, applied right away but saved later
+    Changed,
+    /// the edit is done, e.g. the mouse button released, with or without a change this frame
+    Finished,
+    Reset,
+}
+
+/// Shows the tweak panel listing the editable uniforms of every shader used so far
+#[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+pub fn show_egui(egui_ctx: &egui::Context) {
+    panel_closed();
+    egui::Window::new("Uniforms")
+        .default_open(false)
+        .vscroll(true)
+        .show(egui_ctx, |ui| {
+            let mut current_key = String::new();
+            edit_all(|key, name, value, uniform, overridden| {
+                if key != current_key {
+                    ui.separator();
+                    ui.strong(key);
+                    current_key = key.to_string();
+                }
+                let mut edit = Edit::None;
+                ui.horizontal(|ui| {
+                    let response = match value {
+                        TweakValue::Float(v) => {
+                            let (min, max) = uniform.slider_range(1.0);
+                            ui.add(egui::Slider::new(v, min..=max).clamp_to_range(false))
+                        }
+                        TweakValue::Int(v) => {
+                            let (min, max) = uniform.slider_range(10.0);
+                            let range = min as i32..=max as i32;
+                            ui.add(egui::Slider::new(v, range).clamp_to_range(false))
+                        }
+                        TweakValue::Bool(v) => ui.checkbox(v, ""),
+                        TweakValue::Vec3(v) if TweakValue::is_color(name) => {
+                            ui.color_edit_button_rgb(v)
+                        }
+                        TweakValue::Vec3(v) => v
+                            .iter_mut()
+                            .map(|v| ui.add(egui::DragValue::new(v).speed(0.01)))
+                            .reduce(|a, b| a | b)
+                            .unwrap(),
+                        TweakValue::Vec4(v) => ui.color_edit_button_rgba_unmultiplied(v),
+                    };
+                    ui.label(name);
+                    // the color pickers and sliders keep the pointer while dragging
+                    let dragging = ui.ctx().is_using_pointer();
+                    if response.drag_stopped()
+                        || response.lost_focus()
+                        || (response.changed() && !dragging)
+                    {
+                        edit = Edit::Finished;
+                    } else if response.changed() {
+                        edit = Edit::Changed;
+                    }
+                    if overridden && ui.small_button("reset").clicked() {
+                        edit = Edit::Reset;
+                    }
+                });
+                edit
+            });
+        });
+    save_if_closed();
+}
+
+/// imgui version of `show_egui`
+#[cfg(feature = "imgui-support")]
+pub fn show_imgui<A>(ui: &easy_imgui_window::easy_imgui::Ui<A>) {
+    panel_closed();
+    ui.window_config("Uniforms").with(|| {
+        let mut current_key = String::new();
+        edit_all(|key, name, value, uniform, overridden| {
+            if key != current_key {
+                ui.separator();
+                ui.text(key);
+                current_key = key.to_string();
+            }
+            let label = format!("{}##{}", name, key);
+            let changed = match value {
+                TweakValue::Float(v) => {
+                    let (min, max) = uniform.slider_range(1.0);
+                    ui.slider_float_config(&label, v).range(min, max).build()
+                }
+                TweakValue::Int(v) => {
+                    let (min, max) = uniform.slider_range(10.0);
+                    ui.slider_int_config(&label, v)
+                        .range(min as i32, max as i32)
+                        .build()
+                }
+                TweakValue::Bool(v) => ui.checkbox_config(&label, v).build(),
+                TweakValue::Vec3(v) if TweakValue::is_color(name) => {
+                    ui.color_edit_3_config(&label, v).build()
+                }
+                TweakValue::Vec3(v) => {
+                    let (min, max) = uniform.slider_range(1.0);
+                    ui.slider_float_3_config(&label, v).range(min, max).build()
+                }
+                TweakValue::Vec4(v) => ui.color_edit_4_config(&label, v).build(),
+            };
+            let finished = ui.is_item_deactivated_after_edit();
+            let reset = overridden && {
+                ui.same_line();
+                ui.button_config(format!("reset##{}{}", key, name)).build()
+            };
+            match (changed, finished, reset) {
+                (_, _, true) => Edit::Reset,
+                (_, true, _) => Edit::Finished,
+                (true, _, _) => Edit::Changed,
+                _ => Edit::None,
+            }
+        });
+    });
+    save_if_closed();
+}
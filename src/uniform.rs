@@ -0,0 +1,172 @@
+use crate::tweaks::TweakValue;
+use glow::{Context, HasContext, UniformLocation};
+use nalgebra_glm as glm;
+
+/// What `MyShader::set` does when the uniform is not active in the program, either because
+/// it is misspelled or because the compiler optimized it away.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MissingUniformPolicy {
+    #[default]
+    Panic,
+    /// log a warning the first time each name is missed. No tutorial picks it yet
+    #[allow(dead_code)]
+    WarnOnce,
+    Ignore,
+}
+
+/// A value that can be uploaded to a uniform with `MyShader::set`. Slices fill uniform arrays
+/// starting at the given element.
+pub trait Uniform {
+    #[allow(clippy::missing_safety_doc)]
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation);
+
+    /// the value as shown in the tweak panel, for the types it can edit
+    fn tweak_value(&self) -> Option<TweakValue> {
+        None
+    }
+}
+
+impl Uniform for bool {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_i32(Some(location), *self as i32);
+    }
+
+    fn tweak_value(&self) -> Option<TweakValue> {
+        Some(TweakValue::Bool(*self))
+    }
+}
+
+impl Uniform for i32 {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_i32(Some(location), *self);
+    }
+
+    fn tweak_value(&self) -> Option<TweakValue> {
+        Some(TweakValue::Int(*self))
+    }
+}
+
+impl Uniform for u32 {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_u32(Some(location), *self);
+    }
+}
+
+impl Uniform for f32 {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_f32(Some(location), *self);
+    }
+
+    fn tweak_value(&self) -> Option<TweakValue> {
+        Some(TweakValue::Float(*self))
+    }
+}
+
+/// vectors and slices of them go through the `*_slice` functions, which also take arrays
+macro_rules! impl_uniform_slice {
+    ($($ty:ty, $scalar:ty => $function:ident $(, $tweak:ident)?;)*) => {
+        $(
+            impl Uniform for $ty {
+                unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+                    gl.$function(Some(location), self.as_slice());
+                }
+
+                $(
+                    fn tweak_value(&self) -> Option<TweakValue> {
+                        Some(TweakValue::$tweak((*self).into()))
+                    }
+                )?
+            }
+
+            impl Uniform for [$ty] {
+                unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+                    gl.$function(Some(location), bytemuck::cast_slice::<$ty, $scalar>(self));
+                }
+            }
+        )*
+    };
+}
+
+impl_uniform_slice! {
+    glm::Vec2, f32 => uniform_2_f32_slice;
+    glm::Vec3, f32 => uniform_3_f32_slice, Vec3;
+    glm::Vec4, f32 => uniform_4_f32_slice, Vec4;
+    glm::IVec2, i32 => uniform_2_i32_slice;
+    glm::IVec3, i32 => uniform_3_i32_slice;
+    glm::IVec4, i32 => uniform_4_i32_slice;
+    glm::UVec2, u32 => uniform_2_u32_slice;
+    glm::UVec3, u32 => uniform_3_u32_slice;
+    glm::UVec4, u32 => uniform_4_u32_slice;
+}
+
+macro_rules! impl_uniform_matrix {
+    ($($ty:ty => $function:ident;)*) => {
+        $(
+            impl Uniform for $ty {
+                unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+                    gl.$function(Some(location), false, self.as_slice());
+                }
+            }
+
+            impl Uniform for [$ty] {
+                unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+                    gl.$function(Some(location), false, bytemuck::cast_slice::<$ty, f32>(self));
+                }
+            }
+        )*
+    };
+}
+
+impl_uniform_matrix! {
+    glm::Mat2 => uniform_matrix_2_f32_slice;
+    glm::Mat3 => uniform_matrix_3_f32_slice;
+    glm::Mat4 => uniform_matrix_4_f32_slice;
+}
+
+impl Uniform for [f32] {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_f32_slice(Some(location), self);
+    }
+}
+
+impl Uniform for [i32] {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_i32_slice(Some(location), self);
+    }
+}
+
+impl Uniform for [u32] {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        gl.uniform_1_u32_slice(Some(location), self);
+    }
+}
+
+impl<T, const N: usize> Uniform for [T; N]
+where
+    [T]: Uniform,
+{
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        self.as_slice().set_uniform(gl, location)
+    }
+}
+
+impl<T> Uniform for Vec<T>
+where
+    [T]: Uniform,
+{
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        self.as_slice().set_uniform(gl, location)
+    }
+}
+
+impl Uniform for TweakValue {
+    unsafe fn set_uniform(&self, gl: &Context, location: &UniformLocation) {
+        match self {
+            TweakValue::Float(v) => v.set_uniform(gl, location),
+            TweakValue::Int(v) => v.set_uniform(gl, location),
+            TweakValue::Bool(v) => v.set_uniform(gl, location),
+            TweakValue::Vec3(v) => gl.uniform_3_f32_slice(Some(location), v),
+            TweakValue::Vec4(v) => gl.uniform_4_f32_slice(Some(location), v),
+        }
+    }
+}
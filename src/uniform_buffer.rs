@@ -0,0 +1,352 @@
+use crate::gl_object::{OwnedBuffer, SharedGl};
+use glow::{Context, HasContext};
+use nalgebra_glm as glm;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+/// A type that can be a member of a std140 uniform block
+pub trait Std140Member {
+    /// base alignment in bytes
+    const ALIGN: usize;
+    /// bytes taken by the member, without padding up to the next one
+    const SIZE: usize;
+    /// e.g. `glow::FLOAT_VEC3`, the element type for arrays
+    const GL_TYPE: u32;
+    /// array length, 1 for plain members
+    const LEN: usize = 1;
+
+    /// writes the member to `out`, which is `SIZE` bytes long
+    fn write(&self, out: &mut [u8]);
+}
+
+macro_rules! impl_std140_scalar {
+    ($($ty:ty => $gl_type:expr;)*) => {
+        $(
+            impl Std140Member for $ty {
+                const ALIGN: usize = 4;
+                const SIZE: usize = 4;
+                const GL_TYPE: u32 = $gl_type;
+
+                fn write(&self, out: &mut [u8]) {
+                    out.copy_from_slice(&self.to_ne_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_scalar! {
+    f32 => glow::FLOAT;
+    i32 => glow::INT;
+    u32 => glow::UNSIGNED_INT;
+}
+
+impl Std140Member for bool {
+    const ALIGN: usize = 4;
+    const SIZE: usize = 4;
+    const GL_TYPE: u32 = glow::BOOL;
+
+    fn write(&self, out: &mut [u8]) {
+        (*self as u32).write(out);
+    }
+}
+
+/// vec3 is aligned like vec4 but only takes 12 bytes, a float can follow it
+macro_rules! impl_std140_vector {
+    ($($ty:ty, $scalar:ty, $align:expr => $gl_type:expr;)*) => {
+        $(
+            impl Std140Member for $ty {
+                const ALIGN: usize = $align;
+                const SIZE: usize = std::mem::size_of::<$ty>();
+                const GL_TYPE: u32 = $gl_type;
+
+                fn write(&self, out: &mut [u8]) {
+                    out.copy_from_slice(bytemuck::cast_slice::<$scalar, u8>(self.as_slice()));
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_vector! {
+    glm::Vec2, f32, 8 => glow::FLOAT_VEC2;
+    glm::Vec3, f32, 16 => glow::FLOAT_VEC3;
+    glm::Vec4, f32, 16 => glow::FLOAT_VEC4;
+    glm::IVec2, i32, 8 => glow::INT_VEC2;
+    glm::IVec3, i32, 16 => glow::INT_VEC3;
+    glm::IVec4, i32, 16 => glow::INT_VEC4;
+    glm::UVec2, u32, 8 => glow::UNSIGNED_INT_VEC2;
+    glm::UVec3, u32, 16 => glow::UNSIGNED_INT_VEC3;
+    glm::UVec4, u32, 16 => glow::UNSIGNED_INT_VEC4;
+}
+
+/// matrices are arrays of columns, each padded to a vec4
+macro_rules! impl_std140_matrix {
+    ($($ty:ty, $columns:expr => $gl_type:expr;)*) => {
+        $(
+            impl Std140Member for $ty {
+                const ALIGN: usize = 16;
+                const SIZE: usize = $columns * 16;
+                const GL_TYPE: u32 = $gl_type;
+
+                fn write(&self, out: &mut [u8]) {
+                    for (column, out) in self.column_iter().zip(out.chunks_exact_mut(16)) {
+                        for (value, out) in column.iter().zip(out.chunks_exact_mut(4)) {
+                            value.write(out);
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_matrix! {
+    glm::Mat2, 2 => glow::FLOAT_MAT2;
+    glm::Mat3, 3 => glow::FLOAT_MAT3;
+    glm::Mat4, 4 => glow::FLOAT_MAT4;
+}
+
+/// array elements are padded to a multiple of 16 bytes, arrays of arrays are not supported
+impl<T: Std140Member, const N: usize> Std140Member for [T; N] {
+    const ALIGN: usize = round_up(T::ALIGN, 16);
+    const SIZE: usize = round_up(T::SIZE, 16) * N;
+    const GL_TYPE: u32 = T::GL_TYPE;
+    const LEN: usize = N;
+
+    fn write(&self, out: &mut [u8]) {
+        for (value, out) in self.iter().zip(out.chunks_exact_mut(round_up(T::SIZE, 16))) {
+            value.write(&mut out[..T::SIZE]);
+        }
+    }
+}
+
+pub const fn round_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Where a member of a `Std140` struct is placed in the block
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Std140Field {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    pub align: usize,
+    pub gl_type: u32,
+    pub len: usize,
+}
+
+impl Std140Field {
+    pub const fn new<T: Std140Member>(name: &'static str) -> Self {
+        Self {
+            name,
+            offset: 0,
+            size: T::SIZE,
+            align: T::ALIGN,
+            gl_type: T::GL_TYPE,
+            len: T::LEN,
+        }
+    }
+
+    /// places `fields` one after another, in declaration order
+    pub const fn layout<const N: usize>(mut fields: [Self; N]) -> [Self; N] {
+        let mut offset = 0;
+        let mut i = 0;
+        while i < N {
+            fields[i].offset = round_up(offset, fields[i].align);
+            offset = fields[i].offset + fields[i].size;
+            i += 1;
+        }
+        fields
+    }
+
+    /// size of a block with `fields`, padded to a multiple of a vec4
+    pub const fn block_size(fields: &[Self]) -> usize {
+        match fields.last() {
+            Some(last) => round_up(last.offset + last.size, 16),
+            None => 0,
+        }
+    }
+}
+
+/// A struct laid out like a std140 uniform block, declared with `std140!`
+pub trait Std140 {
+    const FIELDS: &'static [Std140Field];
+    const SIZE: usize;
+
+    /// writes every field at its std140 offset into `out`, which is `SIZE` bytes long
+    fn write_std140(&self, out: &mut [u8]);
+}
+
+/// Declares a struct and implements `Std140` for it, the members have to be `Std140Member`s:
+///
+/// ```ignore
+/// std140! {
+///     struct Matrices {
+///         projection: glm::Mat4,
+///         view: glm::Mat4,
+///     }
+/// }
+/// ```
+macro_rules! std140 {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($field_vis $field: $ty),*
+        }
+
+        impl $crate::uniform_buffer::Std140 for $name {
+            const FIELDS: &'static [$crate::uniform_buffer::Std140Field] =
+                &$crate::uniform_buffer::Std140Field::layout([
+                    $($crate::uniform_buffer::Std140Field::new::<$ty>(stringify!($field))),*
+                ]);
+            const SIZE: usize = $crate::uniform_buffer::Std140Field::block_size(Self::FIELDS);
+
+            fn write_std140(&self, out: &mut [u8]) {
+                let mut fields = Self::FIELDS.iter();
+                $(
+                    let field = fields.next().unwrap();
+                    $crate::uniform_buffer::Std140Member::write(
+                        &self.$field,
+                        &mut out[field.offset..field.offset + field.size],
+                    );
+                )*
+            }
+        }
+    };
+}
+
+pub(crate) use std140;
+
+thread_local! {
+    /// binding points owned by a `UniformBuffer`
+    static BINDINGS: RefCell<BTreeSet<u32>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+/// A uniform buffer holding a `T`, bound to its own binding point for as long as it lives.
+/// Programs read it through `MyShader::bind_block`.
+pub struct UniformBuffer<T> {
+    buffer: OwnedBuffer,
+    binding: u32,
+    data: Vec<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    /// Creates the buffer and binds it to the lowest free binding point
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new(gl: &SharedGl) -> Self {
+        let binding = BINDINGS.with(|bindings| {
+            let mut bindings = bindings.borrow_mut();
+            let binding = (0..).find(|b| !bindings.contains(b)).unwrap();
+            bindings.insert(binding);
+            binding
+        });
+        let buffer = OwnedBuffer::create_buffer(gl, "uniform buffer");
+        gl.bind_buffer(glow::UNIFORM_BUFFER, Some(buffer.raw()));
+        gl.buffer_data_size(glow::UNIFORM_BUFFER, T::SIZE as i32, glow::DYNAMIC_DRAW);
+        gl.bind_buffer(glow::UNIFORM_BUFFER, None);
+        gl.bind_buffer_base(glow::UNIFORM_BUFFER, binding, Some(buffer.raw()));
+        Self {
+            buffer,
+            binding,
+            data: vec![0; T::SIZE],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates the buffer and uploads `value`
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn new_with(gl: &SharedGl, value: &T) -> Self {
+        let mut ubo = Self::new(gl);
+        ubo.update(gl, value);
+        ubo
+    }
+
+    /// uploads the whole of `value`
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn update(&mut self, gl: &Context, value: &T) {
+        value.write_std140(&mut self.data);
+        gl.bind_buffer(glow::UNIFORM_BUFFER, Some(self.buffer.raw()));
+        gl.buffer_sub_data_u8_slice(glow::UNIFORM_BUFFER, 0, &self.data);
+        gl.bind_buffer(glow::UNIFORM_BUFFER, None);
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+}
+
+/// frees the binding point, the buffer deletes itself
+impl<T> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        BINDINGS.with(|bindings| bindings.borrow_mut().remove(&self.binding));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(fields: &[Std140Field]) -> Vec<usize> {
+        fields.iter().map(|field| field.offset).collect()
+    }
+
+    #[test]
+    fn float_packs_after_vec3() {
+        let fields = Std140Field::layout([
+            Std140Field::new::<glm::Vec3>("position"),
+            Std140Field::new::<f32>("radius"),
+        ]);
+        assert_eq!(offsets(&fields), [0, 12]);
+        assert_eq!(Std140Field::block_size(&fields), 16);
+    }
+
+    #[test]
+    fn scalar_array_elements_have_a_16_byte_stride() {
+        let fields = Std140Field::layout([
+            Std140Field::new::<f32>("count"),
+            Std140Field::new::<[f32; 3]>("weights"),
+            Std140Field::new::<f32>("scale"),
+        ]);
+        assert_eq!(offsets(&fields), [0, 16, 64]);
+        assert_eq!(fields[1].size, 48);
+        assert_eq!(fields[1].len, 3);
+        assert_eq!(Std140Field::block_size(&fields), 80);
+
+        let mut out = [0; 48];
+        [1.0_f32, 2.0, 3.0].write(&mut out);
+        for (i, value) in [1.0_f32, 2.0, 3.0].iter().enumerate() {
+            assert_eq!(out[i * 16..i * 16 + 4], value.to_ne_bytes());
+        }
+    }
+
+    #[test]
+    fn mat3_columns_are_padded_to_vec4() {
+        let fields = Std140Field::layout([
+            Std140Field::new::<f32>("time"),
+            Std140Field::new::<glm::Mat3>("normal_matrix"),
+            Std140Field::new::<glm::Vec2>("offset"),
+        ]);
+        assert_eq!(offsets(&fields), [0, 16, 64]);
+        assert_eq!(fields[1].size, 48);
+        assert_eq!(Std140Field::block_size(&fields), 80);
+
+        // floats, so the bytes are aligned for them
+        let mut floats = [0.0_f32; 12];
+        glm::Mat3::from_columns(&[
+            glm::vec3(1.0, 2.0, 3.0),
+            glm::vec3(4.0, 5.0, 6.0),
+            glm::vec3(7.0, 8.0, 9.0),
+        ])
+        .write(bytemuck::cast_slice_mut(&mut floats));
+        assert_eq!(floats[..4], [1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(floats[8..11], [7.0, 8.0, 9.0]);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::capture::{CaptureRequest, CaptureSource};
+use crate::clock::{Clock, FixedClock, PausedClock, RealClock, ScaledClock};
+use crate::gl_object::SharedGl;
+use crate::recording::{Recorder, Recording};
 use glow::{Context, HasContext};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use typed_builder::TypedBuilder;
@@ -13,8 +19,11 @@ pub const TIME_STEP: Duration = Duration::from_nanos(1_000_000_000 / FPS as u64)
 
 pub struct Game<A: Application> {
     input: WinitInputHelper,
-    app: A,
+    /// `None` once exited, the app is dropped while the context is still current
+    app: Option<A>,
     ctx: AppContext,
+    /// `Some` while recording, the clock is stepped by the render loop then
+    recorder: Option<Recorder>,
 }
 
 #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
@@ -44,6 +53,40 @@ pub trait Application: Sized {
     unsafe fn exit(&mut self, _ctx: &AppContext) {}
 }
 
+/// where the frames of an `Application` end up
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// a winit window on desktop, a canvas on the web
+    #[default]
+    Windowed,
+    /// no window at all: render `frames` frames into an offscreen framebuffer, then exit.
+    /// Uses an EGL device display, so it works without a display server (e.g. Mesa llvmpipe)
+    Headless { frames: u32 },
+}
+
+thread_local! {
+    static BACKEND_OVERRIDE: Cell<Option<Backend>> = const { Cell::new(None) };
+    static RECORDING_OVERRIDE: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// force a backend for every following `run`, whatever the tutorial asked for in its `WindowInitInfo`
+pub fn set_backend_override(backend: Option<Backend>) {
+    BACKEND_OVERRIDE.with(|b| b.set(backend));
+}
+
+pub fn backend_override() -> Option<Backend> {
+    BACKEND_OVERRIDE.with(Cell::get)
+}
+
+/// record every following `run`, see `set_backend_override`
+pub fn set_recording_override(recording: Option<Recording>) {
+    RECORDING_OVERRIDE.with(|r| *r.borrow_mut() = recording);
+}
+
+pub fn recording_override() -> Option<Recording> {
+    RECORDING_OVERRIDE.with(|r| r.borrow().clone())
+}
+
 #[derive(TypedBuilder, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WindowInitInfo {
     #[builder(default = 800)]
@@ -52,17 +95,60 @@ pub struct WindowInitInfo {
     pub height: u32,
     #[builder(default = "learn_opengl_rs".to_string())]
     pub title: String,
+    /// Desktop OpenGL version of the context, e.g. 4.3 for compute shaders. `run` logs an error
+    /// and returns when the driver cannot provide it; the web only has WebGL2, i.e. 3.3.
     #[builder(default = 3)]
     pub major: u8,
     #[builder(default = 3)]
     pub minor: u8,
     #[builder(default = 1)]
     pub num_samples: u8,
+    #[builder(default)]
+    pub backend: Backend,
+    /// lock time to a fixed step and write out every frame
+    #[builder(default, setter(strip_option))]
+    pub recording: Option<Recording>,
+}
+
+impl WindowInitInfo {
+    /// GLSL version of the requested desktop context
+    pub fn desktop_shader_version(&self) -> &'static str {
+        match (self.major, self.minor) {
+            (4, 0) => "#version 400 core",
+            (4, 1) => "#version 410 core",
+            (4, 2) => "#version 420 core",
+            (4, 3) => "#version 430 core",
+            (4, 4) => "#version 440 core",
+            (4, 5) => "#version 450 core",
+            (4, 6) => "#version 460 core",
+            _ => "#version 330 core",
+        }
+    }
+
+    /// Why the context `gl` falls short of the requested version, if it does
+    pub fn check_context_version(&self, gl: &glow::Context) -> Result<(), String> {
+        let version = gl.version();
+        let requested = (self.major as u32, self.minor as u32);
+        if version.is_embedded && requested > (3, 3) {
+            return Err(format!(
+                "OpenGL {}.{} is not available on {}",
+                self.major, self.minor, version.vendor_info
+            ));
+        }
+        if !version.is_embedded && (version.major, version.minor) < requested {
+            return Err(format!(
+                "OpenGL {}.{} was requested, the driver only provides {}.{}",
+                self.major, self.minor, version.major, version.minor
+            ));
+        }
+        Ok(())
+    }
 }
 
 pub struct AppContext {
+    /// `None` when running headless, egui needs an event loop
     #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
-    pub egui_glow: egui_glow::EguiGlow,
+    pub egui_glow: Option<egui_glow::EguiGlow>,
     #[cfg(feature = "imgui-support")]
     pub imgui_renderer: easy_imgui_window::easy_imgui_renderer::Renderer,
     #[cfg(feature = "imgui-support")]
@@ -70,16 +156,16 @@ pub struct AppContext {
     pub gl_context: GLContext,
     pub app_state: AppState,
     pub gl_state: GlState,
+    /// captures asked for during input handling, taken right after the next `Application::render`
+    pub capture_requests: RefCell<Vec<CaptureRequest>>,
 }
 
 pub struct GLContext {
-    #[cfg(feature = "imgui-support")]
-    pub gl: Rc<glow::Context>,
-    #[cfg(not(feature = "imgui-support"))]
-    pub gl: Arc<glow::Context>,
+    pub gl: SharedGl,
 
+    /// `None` when running headless
     #[cfg(not(target_arch = "wasm32"))]
-    pub gl_surface: glutin::surface::Surface<glutin::surface::WindowSurface>,
+    pub gl_surface: Option<glutin::surface::Surface<glutin::surface::WindowSurface>>,
 }
 
 pub struct AppState {
@@ -92,6 +178,14 @@ pub struct AppState {
     pub last_render_time: chrono::DateTime<chrono::Utc>,
     pub update_delta_time: f32,
     pub render_delta_time: f32,
+    /// wall clock time of the last update, `last_update_time` stops while paused
+    pub last_real_update_time: chrono::DateTime<chrono::Utc>,
+    /// seconds between the last two updates on the wall clock, whatever the `clock` does
+    pub real_delta_time: f32,
+    /// source of "now", the wall clock unless paused, scaled or running headless
+    pub clock: Box<dyn Clock>,
+    /// time scale to go back to, `Some` while paused
+    resume_scale: Option<f64>,
 }
 
 #[derive(Debug, Default)]
@@ -101,7 +195,8 @@ pub struct GlState {
 
 #[allow(dead_code)]
 impl AppContext {
-    pub fn gl(&self) -> &glow::Context {
+    /// the shared context, derefs to `glow::Context` and is what GL objects are created with
+    pub fn gl(&self) -> &SharedGl {
         &self.gl_context.gl
     }
 
@@ -120,6 +215,11 @@ impl AppContext {
         self.app_state.update_delta_time
     }
 
+    /// for input and camera movement, which should keep working while the time is paused
+    pub fn real_delta_time(&self) -> f32 {
+        self.app_state.real_delta_time
+    }
+
     pub fn render_delta_time(&self) -> f32 {
         self.app_state.render_delta_time
     }
@@ -143,10 +243,164 @@ impl AppContext {
     pub fn suggested_shader_version(&self) -> &'static str {
         self.app_state.suggested_shader_version
     }
+
+    /// size in pixels of the default framebuffer. Differs from `width`/`height` on HiDPI screens
+    /// until the first resize, as the window is created with a logical size
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(gl_surface) = &self.gl_context.gl_surface {
+            use glutin::surface::GlSurface;
+            if let (Some(width), Some(height)) = (gl_surface.width(), gl_surface.height()) {
+                return (width, height);
+            }
+        }
+        let scale_factor = self.app_state.scale_factor;
+        (
+            (self.app_state.width as f64 * scale_factor).round() as u32,
+            (self.app_state.height as f64 * scale_factor).round() as u32,
+        )
+    }
+
+    /// Captures after the next `render`, when the frame is complete. Use this from `process_input`,
+    /// the back buffer is undefined between two frames
+    pub fn request_capture(&self, request: CaptureRequest) {
+        self.capture_requests.borrow_mut().push(request);
+    }
+
+    /// Reads back the source of `request` now and writes it to `capture::CAPTURE_DIR`
+    pub unsafe fn capture(&self, request: &CaptureRequest) -> anyhow::Result<PathBuf> {
+        let gl = self.gl();
+        let hdr = request.format.is_hdr();
+        let image = match request.source {
+            CaptureSource::Screen => {
+                let (width, height) = self.framebuffer_size();
+                crate::capture::read_framebuffer(
+                    gl,
+                    None,
+                    crate::capture::Attachment::Color(0),
+                    width,
+                    height,
+                    hdr,
+                )
+            }
+            CaptureSource::Framebuffer {
+                framebuffer,
+                attachment,
+                width,
+                height,
+            } => crate::capture::read_framebuffer(
+                gl,
+                Some(framebuffer),
+                attachment,
+                width,
+                height,
+                hdr,
+            ),
+            CaptureSource::Texture {
+                texture,
+                attachment,
+                width,
+                height,
+            } => crate::capture::read_texture(gl, texture, attachment, width, height, hdr)?,
+        };
+        crate::capture::save(&image, &request.name, request.format)
+    }
+
+    pub unsafe fn process_capture_requests(&self) {
+        let requests = self.capture_requests.take();
+        for request in requests {
+            match self.capture(&request) {
+                Ok(path) => log::info!("Captured {:?} to {}", request.source, path.display()),
+                Err(e) => log::error!("Cannot capture {:?}: {}", request.source, e),
+            }
+        }
+    }
 }
 
 #[allow(dead_code)]
 impl AppState {
+    pub fn new(
+        suggested_shader_version: &'static str,
+        width: u32,
+        height: u32,
+        scale_factor: f64,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            suggested_shader_version,
+            width,
+            height,
+            scale_factor,
+            start: now,
+            last_update_time: now,
+            last_render_time: now,
+            update_delta_time: 0.0,
+            render_delta_time: 0.0,
+            last_real_update_time: now,
+            real_delta_time: 0.0,
+            clock: Box::new(RealClock),
+            resume_scale: None,
+        }
+    }
+
+    pub fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.clock.now()
+    }
+
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+        self.resume_scale = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.resume_scale.is_some()
+    }
+
+    pub fn pause(&mut self) {
+        if !self.is_paused() {
+            let scale = self.clock.scale();
+            self.set_clock(PausedClock::new(self.now()));
+            self.resume_scale = Some(scale);
+        }
+    }
+
+    /// continues from where the clock was paused, no time is skipped
+    pub fn resume(&mut self) {
+        if let Some(scale) = self.resume_scale {
+            self.set_clock(ScaledClock::new(self.now(), scale));
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// moves a paused clock forward by `step`
+    pub fn step(&mut self, step: Duration) {
+        if let Some(scale) = self.resume_scale {
+            self.set_clock(PausedClock::new(
+                self.now() + chrono::Duration::from_std(step).unwrap(),
+            ));
+            self.resume_scale = Some(scale);
+        }
+    }
+
+    /// speed of time compared to the wall clock, e.g. 0.5 for slow motion
+    pub fn time_scale(&self) -> f64 {
+        self.resume_scale.unwrap_or_else(|| self.clock.scale())
+    }
+
+    pub fn set_time_scale(&mut self, scale: f64) {
+        match &mut self.resume_scale {
+            Some(resume_scale) => *resume_scale = scale,
+            None => self.set_clock(ScaledClock::new(self.now(), scale)),
+        }
+    }
+
     pub fn start(&self) -> chrono::DateTime<chrono::Utc> {
         self.start
     }
@@ -155,6 +409,10 @@ impl AppState {
         self.update_delta_time
     }
 
+    pub fn real_delta_time(&self) -> f32 {
+        self.real_delta_time
+    }
+
     pub fn render_delta_time(&self) -> f32 {
         self.render_delta_time
     }
@@ -168,7 +426,7 @@ impl AppState {
     }
 
     pub fn elapsed_time(&self) -> chrono::Duration {
-        chrono::Utc::now() - self.start
+        self.now() - self.start
     }
 
     pub fn elapsed_time_secs(&self) -> f32 {
@@ -188,10 +446,56 @@ impl AppState {
     }
 }
 
+/// Advances the `FixedClock` of `state` by one frame of `TIME_STEP`, with `UPDATE_PER_SECOND / FPS`
+/// updates in between, as the game loop would do at full speed.
+pub unsafe fn step_fixed_frame<App: Application>(app: &mut App, state: &mut AppState) {
+    let update_delta_time = 1.0 / UPDATE_PER_SECOND as f32;
+    state.clock.tick();
+    let now = state.now();
+    for _ in 0..UPDATE_PER_SECOND / FPS {
+        state.update_delta_time = update_delta_time;
+        state.real_delta_time = update_delta_time;
+        state.last_update_time = now;
+        app.update(update_delta_time);
+    }
+    state.render_delta_time = TIME_STEP.as_secs_f32();
+    state.last_render_time = now;
+}
+
+/// Starts recording, or logs why it cannot.
+pub fn start_recording(recording: &Recording, width: u32, height: u32) -> Option<Recorder> {
+    match Recorder::new(recording, width, height) {
+        Ok(recorder) => Some(recorder),
+        Err(e) => {
+            log::error!("Cannot start recording: {}", e);
+            None
+        }
+    }
+}
+
+pub fn finish_recording(recorder: Option<Recorder>) {
+    if let Some(Err(e)) = recorder.map(Recorder::finish) {
+        log::error!("Recording failed: {}", e);
+    }
+}
+
 pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
+    let backend = backend_override().unwrap_or(init_info.backend);
+    let recording = recording_override().or(init_info.recording.clone());
+    if let Backend::Headless { frames } = backend {
+        cfg_if::cfg_if! {
+            if #[cfg(any(target_arch = "wasm32", target_os = "macos"))] {
+                log::error!("Headless backend needs EGL, unavailable here. {} frames skipped", frames);
+            } else {
+                crate::headless::run::<App>(init_info, frames, recording).await;
+            }
+        }
+        return;
+    }
+
     let width = init_info.width;
     let height = init_info.height;
-    let title = init_info.title;
+    let title = init_info.title.clone();
 
     // Create a context from a WebGL2 context on wasm32 targets
     #[cfg(target_arch = "wasm32")]
@@ -232,6 +536,10 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
             .dyn_into::<web_sys::WebGl2RenderingContext>()
             .unwrap();
         let gl = glow::Context::from_webgl2_context(webgl2_context);
+        if let Err(e) = init_info.check_context_version(&gl) {
+            log::error!("{}", e);
+            return;
+        }
 
         (gl, "#version 300 es", window, event_loop)
     };
@@ -294,11 +602,14 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         }
         let context_attributes = context_attributes.build(raw_window_handle);
 
-        let not_current_gl_context = unsafe {
-            gl_display
-                .create_context(&gl_config, &context_attributes)
-                .expect("Cannot create GL context")
-        };
+        let not_current_gl_context =
+            match unsafe { gl_display.create_context(&gl_config, &context_attributes) } {
+                Ok(context) => context,
+                Err(e) => {
+                    log::error!("Cannot create an OpenGL {}.{} context: {}", major, minor, e);
+                    return;
+                }
+            };
 
         let window = window.unwrap();
 
@@ -314,6 +625,11 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         #[allow(unused_mut)]
         let mut gl =
             unsafe { glow::Context::from_loader_function_cstr(|s| gl_display.get_proc_address(s)) };
+        crate::reflection::load_functions(|s| gl_display.get_proc_address(s));
+        if let Err(e) = init_info.check_context_version(&gl) {
+            log::error!("{}", e);
+            return;
+        }
 
         #[cfg(debug_assertions)]
         {
@@ -328,7 +644,7 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
             gl,
             gl_surface,
             gl_context,
-            "#version 330 core",
+            init_info.desktop_shader_version(),
             window,
             event_loop,
         )
@@ -341,12 +657,8 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
             let scale_factor= window.scale_factor();
         }
     }
-    #[cfg(feature = "imgui-support")]
     #[allow(clippy::arc_with_non_send_sync)]
-    let gl = std::rc::Rc::new(gl);
-
-    #[cfg(not(feature = "imgui-support"))]
-    let gl = Arc::new(gl);
+    let gl = SharedGl::new(gl);
 
     #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
     let egui_glow = {
@@ -368,7 +680,7 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         r.set_background_color(None);
         r
     };
-    let ctx = AppContext {
+    let mut ctx = AppContext {
         #[cfg(feature = "imgui-support")]
         imgui_renderer,
         #[cfg(feature = "imgui-support")]
@@ -376,30 +688,29 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         gl_context: GLContext {
             gl,
             #[cfg(not(target_arch = "wasm32"))]
-            gl_surface,
-        },
-        app_state: AppState {
-            suggested_shader_version: shader_version,
-            width,
-            height,
-            scale_factor,
-            start: chrono::Utc::now(),
-            last_update_time: chrono::Utc::now(),
-            last_render_time: chrono::Utc::now(),
-            update_delta_time: 0.0,
-            render_delta_time: 0.0,
+            gl_surface: Some(gl_surface),
         },
+        app_state: AppState::new(shader_version, width, height, scale_factor),
         #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
-        egui_glow,
+        egui_glow: Some(egui_glow),
         gl_state: GlState::default(),
+        capture_requests: RefCell::default(),
     };
 
+    let recorder = recording.and_then(|recording| {
+        let (width, height) = ctx.framebuffer_size();
+        let start = ctx.app_state.start;
+        ctx.app_state.set_clock(FixedClock::new(start, TIME_STEP));
+        start_recording(&recording, width, height)
+    });
+
     let app = App::new(&ctx).await;
 
     let game = Game {
         input: WinitInputHelper::new(),
-        app,
+        app: Some(app),
         ctx,
+        recorder,
     };
 
     let window = Arc::new(window);
@@ -411,28 +722,63 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         UPDATE_PER_SECOND as u32,
         0.1,
         move |g| {
+            if g.game.recorder.is_some() {
+                // updates are driven by the fixed clock in the render callback
+                return;
+            }
+            let Some(app) = &mut g.game.app else {
+                return;
+            };
             let ctx = &mut g.game.ctx;
-            let now = chrono::Utc::now();
+            let now = ctx.app_state.now();
             ctx.app_state.update_delta_time =
                 (now - ctx.app_state.last_update_time).num_milliseconds() as f32 / 1000.0;
-            ctx.app_state.last_update_time = chrono::Utc::now();
-            g.game.app.update(ctx.app_state.update_delta_time);
+            ctx.app_state.last_update_time = now;
+            let real_now = chrono::Utc::now();
+            ctx.app_state.real_delta_time =
+                (real_now - ctx.app_state.last_real_update_time).num_milliseconds() as f32 / 1000.0;
+            ctx.app_state.last_real_update_time = real_now;
+            app.update(ctx.app_state.update_delta_time);
         },
         move |g| {
             let ctx = &mut g.game.ctx;
-            let now = chrono::Utc::now();
-            let app = &mut g.game.app;
-            ctx.app_state.render_delta_time =
-                (now - ctx.app_state.last_render_time).num_milliseconds() as f32 / 1000.0;
-            ctx.app_state.last_render_time = chrono::Utc::now();
+            let Some(app) = &mut g.game.app else {
+                return;
+            };
+            if g.game.recorder.is_some() {
+                step_fixed_frame(app, &mut ctx.app_state);
+            } else {
+                ctx.app_state.clock.tick();
+                let now = ctx.app_state.now();
+                ctx.app_state.render_delta_time =
+                    (now - ctx.app_state.last_render_time).num_milliseconds() as f32 / 1000.0;
+                ctx.app_state.last_render_time = now;
+            }
             #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
-            ctx.egui_glow.run(&g.window, |egui_ctx| {
-                app.ui(&ctx.app_state, &ctx.gl_context, egui_ctx);
-            });
+            if let Some(egui_glow) = &mut ctx.egui_glow {
+                egui_glow.run(&g.window, |egui_ctx| {
+                    app.ui(&ctx.app_state, &ctx.gl_context, egui_ctx);
+                    crate::hot_reload::show_errors(egui_ctx);
+                    crate::tweaks::show_egui(egui_ctx);
+                });
+            }
 
             let gl = &ctx.gl_context.gl;
             restore_gl_states(gl, &ctx.gl_state.states);
             app.render(ctx);
+            ctx.process_capture_requests();
+
+            if let Some(recorder) = &mut g.game.recorder {
+                let (width, height) = ctx.framebuffer_size();
+                if let Err(e) = recorder.record_frame(gl, None, width, height) {
+                    log::error!("Recording stopped: {}", e);
+                    finish_recording(g.game.recorder.take());
+                } else if recorder.is_done() {
+                    exit_app(&mut g.game.app, ctx, g.game.recorder.take());
+                    g.exit();
+                    return;
+                }
+            }
 
             // we have debug callback already
             // #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
@@ -465,12 +811,16 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
                 record_gl_states(gl, &mut ctx.gl_state.states);
 
                 #[cfg(feature = "egui-support")]
-                ctx.egui_glow.paint(&g.window);
+                if let Some(egui_glow) = &mut ctx.egui_glow {
+                    egui_glow.paint(&g.window);
+                }
 
                 use game_loop::TimeTrait;
                 use glutin::surface::GlSurface;
 
-                ctx.gl_context.gl_surface.swap_buffers(&gl_context).unwrap();
+                if let Some(gl_surface) = &ctx.gl_context.gl_surface {
+                    gl_surface.swap_buffers(&gl_context).unwrap();
+                }
 
                 let dt = TIME_STEP.as_secs_f64() - game_loop::Time::now().sub(&g.current_instant());
                 if dt > 0.0 {
@@ -480,7 +830,9 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
         },
         move |g, event| {
             let input = &mut g.game.input;
-            let app = &mut g.game.app;
+            let Some(app) = &mut g.game.app else {
+                return;
+            };
             let ctx = &mut g.game.ctx;
 
             #[cfg(feature = "imgui-support")]
@@ -491,7 +843,7 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
                     &mut ctx.imgui_status,
                     &mut EasyImGuiFacade(app),
                     event,
-                    easy_imgui_window::EventFlags::DoNotRender
+                    easy_imgui_window::EventFlags::DoNotRender,
                 );
                 if ui_wants.want_capture_keyboard || ui_wants.want_capture_mouse {
                     //TODO: separate mouse/keyboard capture
@@ -504,10 +856,7 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
                     || input.close_requested()
                     || input.destroyed()
                 {
-                    log::info!("Exiting");
-                    app.exit(ctx);
-                    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
-                    ctx.egui_glow.destroy();
+                    exit_app(&mut g.game.app, ctx, g.game.recorder.take());
                     g.exit();
                     return;
                 }
@@ -519,6 +868,13 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
                     app.resize(ctx, width, height);
                     return;
                 }
+                if input.key_pressed(winit::keyboard::KeyCode::F12) {
+                    ctx.request_capture(CaptureRequest::builder().build());
+                }
+                // recordings run on their own fixed clock
+                if g.game.recorder.is_none() {
+                    process_time_hotkeys(&mut ctx.app_state, input);
+                }
 
                 app.process_input(ctx, input);
                 #[allow(clippy::needless_return)]
@@ -526,8 +882,10 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
             }
 
             #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
-            if let winit::event::Event::WindowEvent { event, .. } = &event {
-                let event_response = ctx.egui_glow.on_window_event(&g.window, event);
+            if let (winit::event::Event::WindowEvent { event, .. }, Some(egui_glow)) =
+                (&event, &mut ctx.egui_glow)
+            {
+                let event_response = egui_glow.on_window_event(&g.window, event);
                 if event_response.repaint {
                     g.window.request_redraw();
                 }
@@ -537,6 +895,46 @@ pub async unsafe fn run<App: Application + 'static>(init_info: WindowInitInfo) {
     .unwrap();
 }
 
+/// `P` pauses and resumes, `.` steps one frame while paused, `[` and `]` halve and double the speed
+fn process_time_hotkeys(state: &mut AppState, input: &WinitInputHelper) {
+    use winit::keyboard::KeyCode;
+    if input.key_pressed(KeyCode::KeyP) {
+        state.toggle_pause();
+        let paused = state.is_paused();
+        log::info!("Time {}", if paused { "paused" } else { "resumed" });
+    }
+    if input.key_pressed(KeyCode::Period) && state.is_paused() {
+        state.step(TIME_STEP);
+        log::info!("Stepped to {:.3}s", state.elapsed_time_secs());
+    }
+    let scale = state.time_scale();
+    if input.key_pressed(KeyCode::BracketLeft) {
+        state.set_time_scale(scale / 2.0);
+        log::info!("Time scale {}", state.time_scale());
+    }
+    if input.key_pressed(KeyCode::BracketRight) {
+        state.set_time_scale(scale * 2.0);
+        log::info!("Time scale {}", state.time_scale());
+    }
+}
+
+unsafe fn exit_app<App: Application>(
+    app: &mut Option<App>,
+    ctx: &mut AppContext,
+    recorder: Option<Recorder>,
+) {
+    log::info!("Exiting");
+    finish_recording(recorder);
+    if let Some(mut app) = app.take() {
+        app.exit(ctx);
+    }
+    #[cfg(all(not(target_arch = "wasm32"), feature = "egui-support"))]
+    if let Some(egui_glow) = &mut ctx.egui_glow {
+        egui_glow.destroy();
+    }
+    crate::gl_object::report_leaks();
+}
+
 #[cfg(feature = "imgui-support")]
 pub struct EasyImGuiFacade<'a, A>(&'a mut A);
 
@@ -544,11 +942,11 @@ pub struct EasyImGuiFacade<'a, A>(&'a mut A);
 impl<'a, A: Application> easy_imgui_window::easy_imgui::UiBuilder for EasyImGuiFacade<'a, A> {
     fn do_ui(&mut self, ui: &easy_imgui_window::easy_imgui::Ui<Self>) {
         self.0.do_ui(ui);
+        crate::tweaks::show_imgui(ui);
     }
 }
 
-#[allow(dead_code)]
-unsafe fn set_debug_callback(gl: &mut Context) {
+pub unsafe fn set_debug_callback(gl: &mut Context) {
     gl.debug_message_callback(|source, gltype, id, severity, message| {
         let source = match source {
             glow::DEBUG_SOURCE_API => "API",
@@ -613,8 +1011,7 @@ unsafe fn set_debug_callback(gl: &mut Context) {
     });
 }
 
-#[allow(dead_code)]
-fn record_gl_states(gl: &glow::Context, states: &mut HashMap<u32, bool>) {
+pub fn record_gl_states(gl: &glow::Context, states: &mut HashMap<u32, bool>) {
     unsafe {
         states.insert(glow::BLEND, gl.is_enabled(glow::BLEND));
         states.insert(glow::CULL_FACE, gl.is_enabled(glow::CULL_FACE));
@@ -638,7 +1035,7 @@ fn record_gl_states(gl: &glow::Context, states: &mut HashMap<u32, bool>) {
     }
 }
 
-fn restore_gl_states(gl: &glow::Context, states: &HashMap<u32, bool>) {
+pub fn restore_gl_states(gl: &glow::Context, states: &HashMap<u32, bool>) {
     unsafe {
         for (state, enabled) in states {
             if *enabled {